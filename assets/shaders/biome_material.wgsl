@@ -0,0 +1,75 @@
+// Biome Material Shader
+// Enhanced material shader with biome-specific properties
+
+#import bevy_pbr::forward_io::VertexOutput
+
+// Each property is its own binding, matching the layout `AsBindGroup` derives for BiomeMaterial
+@group(2) @binding(0) var<uniform> base_color: vec4<f32>;
+@group(2) @binding(1) var base_color_texture: texture_2d<f32>;
+@group(2) @binding(2) var base_color_sampler: sampler;
+@group(2) @binding(3) var<uniform> roughness: f32;
+@group(2) @binding(4) var<uniform> metallic: f32;
+@group(2) @binding(5) var<uniform> reflectance: f32;
+@group(2) @binding(6) var<uniform> normal_map_intensity: f32;
+@group(2) @binding(7) var<uniform> ambient_occlusion: f32;
+@group(2) @binding(8) var<uniform> emissive: vec4<f32>;
+@group(2) @binding(9) var<uniform> height_variation: f32;
+@group(2) @binding(10) var<uniform> moisture_effect: f32;
+@group(2) @binding(11) var<uniform> temperature_effect: f32;
+
+@fragment
+fn fragment(input: VertexOutput) -> @location(0) vec4<f32> {
+    // Sample base color texture
+    var color = textureSample(base_color_texture, base_color_sampler, input.uv).rgb;
+
+    // Apply biome-specific modifications
+    color = mix(color, base_color.rgb, height_variation);
+
+    // Moisture effect - makes colors more saturated in wet biomes
+    color = mix(color, color * vec3<f32>(1.0, 1.1, 1.0), moisture_effect);
+
+    // Temperature effect - makes colors warmer in hot biomes, cooler in cold biomes
+    if (temperature_effect > 0.7) {
+        // Hot biome - add red/yellow tones
+        color = mix(color, color * vec3<f32>(1.2, 0.9, 0.8), 0.3);
+    } else if (temperature_effect < 0.3) {
+        // Cold biome - add blue tones
+        color = mix(color, color * vec3<f32>(0.8, 0.9, 1.2), 0.3);
+    }
+
+    // Apply emissive color
+    var final_color = color + emissive.rgb;
+
+    // Basic lighting calculation
+    let normal = normalize(input.world_normal);
+    let view_dir = normalize(-input.world_position.xyz);
+
+    // Simple diffuse lighting
+    let light_dir = normalize(vec3<f32>(1.0, 1.0, 1.0));
+    var diffuse = max(dot(normal, light_dir), 0.0);
+
+    // Apply ambient occlusion
+    diffuse = mix(diffuse, diffuse * 0.5, ambient_occlusion);
+
+    // Per-vertex occlusion from the blocks around each corner, stored in the vertex colour
+    var vertex_occlusion = 1.0;
+#ifdef VERTEX_COLORS
+    vertex_occlusion = input.color.r;
+#endif
+
+    // Combine with base color; occluded corners lose ambient and direct light alike
+    final_color = final_color * (0.3 + diffuse * 0.7) * vertex_occlusion;
+
+    // Apply reflectance
+    if (metallic > 0.0) {
+        // Simple specular highlight for metallic surfaces
+        let half_vec = normalize(light_dir + view_dir);
+        let specular = pow(max(dot(normal, half_vec), 0.0), 32.0);
+        final_color += specular * metallic * 0.5 * vertex_occlusion;
+    }
+
+    // Apply roughness to diffuse lighting
+    final_color = mix(final_color, final_color * 0.8, roughness);
+
+    return vec4<f32>(final_color, 1.0);
+}
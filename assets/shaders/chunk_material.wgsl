@@ -0,0 +1,70 @@
+// Chunk Material Shader
+// Draws chunk meshes from the block texture array with per-vertex layer, biome tint and shading,
+// lighting each vertex by its skylight at the current sky brightness and its block light
+
+#import bevy_pbr::mesh_functions::{get_world_from_local, mesh_position_local_to_clip, mesh_normal_local_to_world}
+
+@group(2) @binding(0) var block_textures: texture_2d_array<f32>;
+@group(2) @binding(1) var block_sampler: sampler;
+@group(2) @binding(2) var<uniform> alpha_cutoff: f32;
+@group(2) @binding(3) var<uniform> opacity: f32;
+@group(2) @binding(4) var<uniform> sky_brightness: f32;
+
+// Caves are never completely black, so they stay navigable
+const DARKEST: f32 = 0.05;
+
+struct Vertex {
+    @builtin(instance_index) instance_index: u32,
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+    // Ambient occlusion, as a grey colour
+    @location(3) shade: vec4<f32>,
+    @location(4) texture_layer: u32,
+    @location(5) biome_tint: vec3<f32>,
+    // Skylight and block light, as fractions of the brightest light level
+    @location(6) light: vec2<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world_normal: vec3<f32>,
+    @location(1) uv: vec2<f32>,
+    @location(2) shade: vec4<f32>,
+    @location(3) @interpolate(flat) texture_layer: u32,
+    @location(4) biome_tint: vec3<f32>,
+};
+
+// How bright a surface looks under its light; the same as `light_brightness` in lighting.rs
+fn light_brightness(light: vec2<f32>) -> f32 {
+    let level = max(light.x * sky_brightness, light.y);
+    return DARKEST + (1.0 - DARKEST) * pow(level, 1.5);
+}
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    var out: VertexOutput;
+    let world_from_local = get_world_from_local(vertex.instance_index);
+    out.clip_position = mesh_position_local_to_clip(world_from_local, vec4<f32>(vertex.position, 1.0));
+    out.world_normal = mesh_normal_local_to_world(vertex.normal, vertex.instance_index);
+    out.uv = vertex.uv;
+    out.shade = vec4<f32>(vertex.shade.rgb * light_brightness(vertex.light), vertex.shade.a);
+    out.texture_layer = vertex.texture_layer;
+    out.biome_tint = vertex.biome_tint;
+    return out;
+}
+
+@fragment
+fn fragment(input: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = textureSample(block_textures, block_sampler, input.uv, input.texture_layer);
+    if (texel.a < alpha_cutoff) {
+        discard;
+    }
+
+    // Faces turned towards the sun are a little brighter, so the sides of blocks stand apart
+    let sun_direction = normalize(vec3<f32>(0.3, 1.0, 0.5));
+    let directional = 0.75 + 0.25 * max(dot(normalize(input.world_normal), sun_direction), 0.0);
+
+    let color = texel.rgb * input.biome_tint * input.shade.rgb * directional;
+    return vec4<f32>(color, texel.a * opacity);
+}
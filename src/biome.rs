@@ -0,0 +1,371 @@
+// Biome registry for Bevy Craft
+// This module defines the biomes shared by world generation, rendering and weather
+//
+// Biomes are data rather than code: each one lists the climate it covers, its surface blocks,
+// the features that grow in it, its colour tint and its weather. The registry is read from
+// `assets/biomes.ron` at startup, with a copy of that file built into the game as a fallback,
+// so adding or tuning a biome only takes an edit to the file.
+//
+// Chunks store the small numeric id of each column's biome and look everything else up here.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::block::BlockType;
+use crate::features::TreeKind;
+
+/// Where the biome registry is loaded from at startup
+pub const BIOME_REGISTRY_PATH: &str = "assets/biomes.ron";
+
+/// Registry shipped with the game, used when the file on disk is missing or broken
+const BUILTIN_BIOMES: &str = include_str!("../assets/biomes.ron");
+
+/// Stable identifier of a biome, as stored in chunks and world saves
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+pub struct BiomeId(pub u8);
+
+/// Climate a biome covers
+/// Each range includes its minimum and excludes its maximum; a range left out of the file
+/// covers everything.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClimateRange {
+    pub temperature: (f32, f32),
+    pub moisture: (f32, f32),
+    /// Terrain height relative to the sea level
+    pub altitude: (i32, i32),
+}
+
+impl Default for ClimateRange {
+    fn default() -> Self {
+        Self {
+            temperature: (f32::MIN, f32::MAX),
+            moisture: (f32::MIN, f32::MAX),
+            altitude: (i32::MIN, i32::MAX),
+        }
+    }
+}
+
+impl ClimateRange {
+    pub fn contains(&self, temperature: f32, moisture: f32, altitude: i32) -> bool {
+        (self.temperature.0..self.temperature.1).contains(&temperature)
+            && (self.moisture.0..self.moisture.1).contains(&moisture)
+            && (self.altitude.0..self.altitude.1).contains(&altitude)
+    }
+}
+
+/// Surface blocks used up to a terrain height
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct SurfaceLayer {
+    /// Columns lower than this use the layer; the last layer usually leaves it out
+    #[serde(default)]
+    pub below: Option<i32>,
+    pub top: BlockType,
+    pub sub_surface: BlockType,
+}
+
+/// Something that grows or forms in a biome
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum BiomeFeature {
+    /// Trees of one kind, with the chance of one starting on each grass column
+    Trees { kind: TreeKind, chance: f32 },
+    /// Scales how many veins of an ore start in the biome
+    Ore { block: BlockType, factor: f32 },
+    /// Sand reaching down into the ground below low dunes
+    SandDrifts,
+    /// Bands of stone showing through the soil of mid-height slopes
+    StoneOutcrops,
+    /// A thicker layer of dirt under the surface
+    DeepSoil,
+    /// Ruins, with their chance per structure region
+    Ruins { chance: f32 },
+}
+
+/// What falls from the sky when a biome's weather turns bad
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Precipitation {
+    Rain,
+    Snow,
+    /// Dry biomes get sandstorms instead of rain
+    Sand,
+}
+
+/// Weather conditions of a biome
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WeatherProfile {
+    pub precipitation: Precipitation,
+    /// Average temperature in Celsius
+    pub temperature: f32,
+    /// Average humidity (0.0 to 1.0)
+    pub humidity: f32,
+}
+
+impl Default for WeatherProfile {
+    fn default() -> Self {
+        Self {
+            precipitation: Precipitation::Rain,
+            temperature: 20.0,
+            humidity: 0.5,
+        }
+    }
+}
+
+/// A single biome definition
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Biome {
+    pub id: BiomeId,
+    pub name: String,
+    /// Climates the biome covers; biomes without any are only placed directly, like rivers
+    #[serde(default)]
+    pub climate: Vec<ClimateRange>,
+    /// Surface layers, checked in order against the terrain height
+    pub surface: Vec<SurfaceLayer>,
+    #[serde(default)]
+    pub features: Vec<BiomeFeature>,
+    /// How strongly 3D density noise shapes the terrain, from flat (0.0) to full cliffs (1.0)
+    pub ruggedness: f32,
+    /// Colour multiplier applied to the biome's block textures
+    pub tint: (f32, f32, f32),
+    /// Terrain height the biome's textures are generated for
+    pub texture_height: f32,
+    pub weather: WeatherProfile,
+}
+
+impl Biome {
+    /// Top and sub-surface blocks for a column reaching up to `height`
+    pub fn surface_blocks(&self, height: i32) -> (BlockType, BlockType) {
+        self.surface
+            .iter()
+            .find(|layer| layer.below.is_none_or(|below| height < below))
+            .or(self.surface.last())
+            .map(|layer| (layer.top, layer.sub_surface))
+            .unwrap_or((BlockType::Grass, BlockType::Dirt))
+    }
+
+    /// The trees growing in the biome and their chance per grass column
+    pub fn trees(&self) -> Option<(TreeKind, f32)> {
+        self.features.iter().find_map(|feature| match feature {
+            BiomeFeature::Trees { kind, chance } => Some((*kind, *chance)),
+            _ => None,
+        })
+    }
+
+    /// How common an ore is in the biome relative to its base frequency
+    pub fn ore_factor(&self, block_type: BlockType) -> f32 {
+        self.features
+            .iter()
+            .find_map(|feature| match feature {
+                BiomeFeature::Ore { block, factor } if *block == block_type => Some(*factor),
+                _ => None,
+            })
+            .unwrap_or(1.0)
+    }
+
+    /// Chance of a ruin in each structure region whose ruin site lands in the biome
+    pub fn ruin_chance(&self) -> f32 {
+        self.features
+            .iter()
+            .find_map(|feature| match feature {
+                BiomeFeature::Ruins { chance } => Some(*chance),
+                _ => None,
+            })
+            .unwrap_or(0.0)
+    }
+}
+
+/// Layout of the registry file
+#[derive(Debug, Serialize, Deserialize)]
+struct BiomeRegistryFile {
+    /// Name of the biome used where no climate range matches
+    fallback: String,
+    biomes: Vec<Biome>,
+}
+
+/// Every biome in the game, shared by world generation and rendering
+#[derive(Resource, Debug, Clone)]
+pub struct BiomeRegistry {
+    biomes: Vec<Biome>,
+    by_id: HashMap<BiomeId, usize>,
+    fallback: usize,
+}
+
+impl Default for BiomeRegistry {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl BiomeRegistry {
+    /// The registry built into the game
+    pub fn builtin() -> Self {
+        Self::from_ron(BUILTIN_BIOMES).expect("built-in biome registry is valid")
+    }
+
+    /// Parse a registry from RON text
+    pub fn from_ron(text: &str) -> io::Result<Self> {
+        let file: BiomeRegistryFile = ron::from_str(text)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        Self::from_biomes(file.biomes, &file.fallback)
+    }
+
+    /// Build a registry, checking that ids and names are unique
+    fn from_biomes(biomes: Vec<Biome>, fallback: &str) -> io::Result<Self> {
+        let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+
+        let mut by_id = HashMap::new();
+        for (index, biome) in biomes.iter().enumerate() {
+            if by_id.insert(biome.id, index).is_some() {
+                return Err(invalid(format!("duplicate biome id {}", biome.id.0)));
+            }
+            if biomes[..index].iter().any(|other| other.name == biome.name) {
+                return Err(invalid(format!("duplicate biome name {:?}", biome.name)));
+            }
+            if biome.surface.is_empty() {
+                return Err(invalid(format!("biome {:?} has no surface", biome.name)));
+            }
+        }
+        let fallback = biomes
+            .iter()
+            .position(|biome| biome.name == fallback)
+            .ok_or_else(|| invalid(format!("unknown fallback biome {:?}", fallback)))?;
+
+        Ok(Self {
+            biomes,
+            by_id,
+            fallback,
+        })
+    }
+
+    /// Load a registry from a RON file
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::from_ron(&fs::read_to_string(path)?)
+    }
+
+    /// Load the registry from disk, falling back to the built-in one if that fails
+    pub fn load_or_builtin(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+        match Self::load(path) {
+            Ok(registry) => {
+                println!(
+                    "🌍 Loaded {} biomes from {}",
+                    registry.biomes.len(),
+                    path.display()
+                );
+                registry
+            }
+            Err(error) => {
+                println!(
+                    "⚠️  Could not load biomes from {}: {}, using built-in biomes",
+                    path.display(),
+                    error
+                );
+                Self::builtin()
+            }
+        }
+    }
+
+    /// Look up a biome by id
+    /// Ids the registry doesn't know, for example from a save made with other biomes, map to
+    /// the fallback biome.
+    pub fn get(&self, id: BiomeId) -> &Biome {
+        let index = self.by_id.get(&id).copied().unwrap_or(self.fallback);
+        &self.biomes[index]
+    }
+
+    pub fn by_name(&self, name: &str) -> Option<&Biome> {
+        self.biomes.iter().find(|biome| biome.name == name)
+    }
+
+    #[allow(dead_code)]
+    pub fn iter(&self) -> impl Iterator<Item = &Biome> {
+        self.biomes.iter()
+    }
+
+    /// Biome of a column with the given climate and terrain height above the sea level
+    pub fn classify(&self, temperature: f32, moisture: f32, altitude: i32) -> &Biome {
+        self.biomes
+            .iter()
+            .find(|biome| {
+                biome
+                    .climate
+                    .iter()
+                    .any(|range| range.contains(temperature, moisture, altitude))
+            })
+            .unwrap_or(&self.biomes[self.fallback])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_registry_classifies_climates() {
+        let biomes = BiomeRegistry::builtin();
+        let name = |temperature, moisture, altitude| {
+            biomes
+                .classify(temperature, moisture, altitude)
+                .name
+                .as_str()
+        };
+
+        assert_eq!(name(0.5, 0.5, -30), "deep_ocean");
+        assert_eq!(name(0.5, 0.5, -5), "ocean");
+        assert_eq!(name(0.5, 0.8, 1), "swamp");
+        assert_eq!(name(0.5, 0.2, 1), "beach");
+        assert_eq!(name(0.5, 0.5, 50), "snowy_mountain");
+        assert_eq!(name(0.5, 0.5, 40), "mountain");
+        assert_eq!(name(0.5, 0.5, 30), "hills");
+        assert_eq!(name(0.8, 0.1, 10), "desert");
+        assert_eq!(name(0.6, 0.8, 10), "forest");
+        assert_eq!(name(0.8, 0.5, 10), "plains");
+        assert_eq!(name(0.1, 0.5, 10), "tundra");
+
+        // Rivers are never picked by climate, only looked up by name
+        let river = biomes.by_name("river").unwrap();
+        assert!(biomes
+            .iter()
+            .all(|biome| biome.climate.is_empty() == (biome.id == river.id)));
+        assert_eq!(biomes.get(river.id).name, "river");
+
+        let desert = biomes.by_name("desert").unwrap();
+        assert_eq!(
+            desert.surface_blocks(10),
+            (BlockType::Sand, BlockType::Sand)
+        );
+        assert_eq!(
+            desert.surface_blocks(40),
+            (BlockType::Sand, BlockType::Stone)
+        );
+        assert_eq!(desert.ore_factor(BlockType::GoldOre), 2.0);
+        assert_eq!(desert.ore_factor(BlockType::IronOre), 1.0);
+        assert!(desert.trees().is_none());
+    }
+
+    #[test]
+    fn test_registry_rejects_duplicate_ids() {
+        let text = r#"(
+            fallback: "a",
+            biomes: [
+                (id: 1, name: "a", surface: [(top: Grass, sub_surface: Dirt)], ruggedness: 0.1,
+                 tint: (1.0, 1.0, 1.0), texture_height: 10.0,
+                 weather: (precipitation: Rain, temperature: 20.0, humidity: 0.5)),
+                (id: 1, name: "b", surface: [(top: Sand, sub_surface: Sand)], ruggedness: 0.1,
+                 tint: (1.0, 1.0, 1.0), texture_height: 10.0,
+                 weather: (precipitation: Rain, temperature: 20.0, humidity: 0.5)),
+            ],
+        )"#;
+        assert!(BiomeRegistry::from_ron(text).is_err());
+
+        // Unknown ids fall back instead of failing
+        let biomes = BiomeRegistry::builtin();
+        assert_eq!(biomes.get(BiomeId(250)).name, "plains");
+    }
+}
@@ -0,0 +1,245 @@
+// Biome blending for Bevy Craft
+// This module softens the borders between biomes during world generation
+//
+// Biomes are sampled on a coarse lattice in world coordinates around the chunk. Every column
+// then looks at the samples within the blend radius, weighted by distance, to get:
+// - a smoothly averaged temperature and moisture, used for biome colours
+// - a weight per nearby biome, used to blend per-biome terrain shaping
+// - a dithered pick of one nearby sample's climate, used for the surface material, so borders
+//   fray into a mix of both biomes' blocks instead of a straight line
+// Because the lattice and the dithering only depend on world coordinates, neighbouring chunks
+// blend identically along their shared edge.
+
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+use crate::biome::{BiomeId, BiomeRegistry};
+use crate::chunk::{ChunkPosition, CHUNK_SIZE};
+use crate::noise::{generate_biome_info, generate_heightmap, NoiseSettings};
+use crate::world_gen::WorldGenSettings;
+
+/// Distance between biome samples in blocks
+const SAMPLE_SPACING: i32 = 4;
+
+/// Climate and biome at one lattice point
+#[derive(Debug, Clone, Copy)]
+struct BiomeSample {
+    temperature: f32,
+    moisture: f32,
+    biome: BiomeId,
+}
+
+/// Blended biome information for one column
+#[derive(Debug, Clone)]
+pub struct ColumnBlend {
+    /// Distance-weighted average climate
+    pub temperature: f32,
+    pub moisture: f32,
+    /// Climate of the nearby sample picked for this column's surface material
+    pub surface_temperature: f32,
+    pub surface_moisture: f32,
+    /// Share of each nearby biome, summing to 1
+    pub biome_weights: Vec<(BiomeId, f32)>,
+}
+
+impl ColumnBlend {
+    /// Weighted average of a per-biome value
+    pub fn blend_value(&self, value: impl Fn(BiomeId) -> f32) -> f32 {
+        self.biome_weights
+            .iter()
+            .map(|(biome, weight)| value(*biome) * weight)
+            .sum()
+    }
+}
+
+/// Biome samples covering one chunk and its blend radius
+pub struct BiomeBlender<'a> {
+    biomes: &'a BiomeRegistry,
+    radius: i32,
+    seed: u32,
+    samples: HashMap<IVec2, BiomeSample>,
+    noise_settings: NoiseSettings,
+    sea_level: i32,
+}
+
+impl<'a> BiomeBlender<'a> {
+    pub fn new(
+        settings: &WorldGenSettings,
+        biomes: &'a BiomeRegistry,
+        noise_settings: &NoiseSettings,
+        chunk_pos: ChunkPosition,
+    ) -> Self {
+        let radius = settings.biome_blend_radius.max(0);
+        let mut blender = Self {
+            biomes,
+            radius,
+            seed: settings.seed,
+            samples: HashMap::new(),
+            noise_settings: noise_settings.clone(),
+            sea_level: settings.sea_level,
+        };
+
+        if radius > 0 {
+            let chunk_min = chunk_pos.min_block_position();
+            let min_x = (chunk_min.x - radius).div_euclid(SAMPLE_SPACING);
+            let max_x = (chunk_min.x + CHUNK_SIZE as i32 + radius).div_euclid(SAMPLE_SPACING);
+            let min_z = (chunk_min.z - radius).div_euclid(SAMPLE_SPACING);
+            let max_z = (chunk_min.z + CHUNK_SIZE as i32 + radius).div_euclid(SAMPLE_SPACING);
+            for lattice_x in min_x..=max_x {
+                for lattice_z in min_z..=max_z {
+                    let position = IVec2::new(lattice_x, lattice_z) * SAMPLE_SPACING;
+                    let sample = blender.sample(position.x, position.y);
+                    blender.samples.insert(position, sample);
+                }
+            }
+        }
+
+        blender
+    }
+
+    /// Sample the unblended biome at a world position
+    fn sample(&self, world_x: i32, world_z: i32) -> BiomeSample {
+        let (temperature, moisture) =
+            generate_biome_info(world_x as f32, world_z as f32, &self.noise_settings);
+        let height = generate_heightmap(world_x as f32, world_z as f32, &self.noise_settings);
+        BiomeSample {
+            temperature,
+            moisture,
+            biome: self
+                .biomes
+                .classify(temperature, moisture, height as i32 - self.sea_level)
+                .id,
+        }
+    }
+
+    /// Blend the biomes around a column
+    pub fn blend(&self, world_x: i32, world_z: i32) -> ColumnBlend {
+        if self.radius == 0 {
+            let sample = self.sample(world_x, world_z);
+            return ColumnBlend {
+                temperature: sample.temperature,
+                moisture: sample.moisture,
+                surface_temperature: sample.temperature,
+                surface_moisture: sample.moisture,
+                biome_weights: vec![(sample.biome, 1.0)],
+            };
+        }
+
+        let column = Vec2::new(world_x as f32, world_z as f32);
+        let radius = self.radius as f32;
+        let mut weighted: Vec<(f32, IVec2, BiomeSample)> = Vec::new();
+        for (position, sample) in &self.samples {
+            let distance = position.as_vec2().distance(column);
+            if distance < radius {
+                let falloff = 1.0 - distance / radius;
+                weighted.push((falloff * falloff, *position, *sample));
+            }
+        }
+
+        // The lattice is dense enough that a sample is always within the radius, but fall back
+        // to the column's own biome rather than divide by zero
+        if weighted.is_empty() {
+            let position = IVec2::new(world_x, world_z);
+            weighted.push((1.0, position, self.sample(world_x, world_z)));
+        }
+        let total_weight: f32 = weighted.iter().map(|(weight, _, _)| weight).sum();
+
+        // Iterate samples in a fixed order so every chunk makes the same dithered pick
+        weighted.sort_by(|a, b| {
+            b.0.total_cmp(&a.0)
+                .then(a.1.x.cmp(&b.1.x))
+                .then(a.1.y.cmp(&b.1.y))
+        });
+
+        let mut blend = ColumnBlend {
+            temperature: 0.0,
+            moisture: 0.0,
+            surface_temperature: weighted[0].2.temperature,
+            surface_moisture: weighted[0].2.moisture,
+            biome_weights: Vec::new(),
+        };
+        let mut pick = column_dither(world_x, world_z, self.seed) * total_weight;
+        let mut picked = false;
+        for (weight, _, sample) in &weighted {
+            let share = weight / total_weight;
+            blend.temperature += sample.temperature * share;
+            blend.moisture += sample.moisture * share;
+            match blend
+                .biome_weights
+                .iter_mut()
+                .find(|(biome, _)| *biome == sample.biome)
+            {
+                Some((_, biome_weight)) => *biome_weight += share,
+                None => blend.biome_weights.push((sample.biome, share)),
+            }
+
+            if !picked && pick < *weight {
+                blend.surface_temperature = sample.temperature;
+                blend.surface_moisture = sample.moisture;
+                picked = true;
+            }
+            pick -= weight;
+        }
+
+        blend
+    }
+}
+
+/// Stable per-column value in [0, 1) used to dither surface materials
+fn column_dither(world_x: i32, world_z: i32, seed: u32) -> f32 {
+    let mut hash = (world_x as u32)
+        .wrapping_mul(0x27D4EB2D)
+        .wrapping_add((world_z as u32).wrapping_mul(0x165667B1))
+        ^ seed.wrapping_mul(0x9E3779B9);
+    hash ^= hash >> 15;
+    hash = hash.wrapping_mul(0x85EBCA6B);
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(0xC2B2AE35);
+    hash ^= hash >> 16;
+    (hash >> 8) as f32 / (1u32 << 24) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blend_matches_across_chunk_borders() {
+        let settings = WorldGenSettings {
+            seed: 21,
+            ..default()
+        };
+        let noise_settings = NoiseSettings {
+            seed: settings.seed,
+            biome_scale: settings.biome_scale,
+            ..NoiseSettings::default()
+        };
+        let biomes = BiomeRegistry::builtin();
+        let left = BiomeBlender::new(
+            &settings,
+            &biomes,
+            &noise_settings,
+            ChunkPosition::new(0, 0),
+        );
+        let right = BiomeBlender::new(
+            &settings,
+            &biomes,
+            &noise_settings,
+            ChunkPosition::new(1, 0),
+        );
+
+        // Columns on either side of the border see the same samples from both chunks
+        for world_z in 0..CHUNK_SIZE as i32 {
+            for world_x in [15, 16] {
+                let a = left.blend(world_x, world_z);
+                let b = right.blend(world_x, world_z);
+                assert!((a.temperature - b.temperature).abs() < 1e-5);
+                assert!((a.moisture - b.moisture).abs() < 1e-5);
+                assert_eq!(a.surface_temperature, b.surface_temperature);
+
+                let total: f32 = a.biome_weights.iter().map(|(_, weight)| weight).sum();
+                assert!((total - 1.0).abs() < 1e-4);
+            }
+        }
+    }
+}
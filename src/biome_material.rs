@@ -6,6 +6,7 @@ use bevy::render::alpha::AlphaMode;
 use bevy::render::render_resource::{AsBindGroup, ShaderRef};
 use std::collections::{HashMap, VecDeque};
 
+use crate::biome::BiomeId;
 use crate::biome_textures::BiomeTextureParams;
 use crate::block::BlockType;
 
@@ -88,6 +89,7 @@ impl Material for BiomeMaterial {
 
 /// Biome material properties configuration
 #[derive(Debug, Clone)]
+#[allow(dead_code)]
 pub struct BiomeMaterialProperties {
     pub base_roughness: f32,
     pub base_metallic: f32,
@@ -114,13 +116,15 @@ impl Default for BiomeMaterialProperties {
 
 /// Biome material configuration for different block types
 #[derive(Debug, Clone)]
+#[allow(dead_code)]
 pub struct BiomeMaterialConfig {
     #[allow(dead_code)]
     pub block_type: BlockType,
     pub base_properties: BiomeMaterialProperties,
-    pub biome_effects: HashMap<String, BiomeMaterialProperties>, // Biome-specific overrides
+    pub biome_effects: HashMap<BiomeId, BiomeMaterialProperties>, // Biome-specific overrides
 }
 
+#[allow(dead_code)]
 impl BiomeMaterialConfig {
     pub fn new(block_type: BlockType) -> Self {
         let base_properties = match block_type {
@@ -181,9 +185,8 @@ impl BiomeMaterialConfig {
 
     /// Add biome-specific material properties
     #[allow(dead_code)]
-    pub fn add_biome_effect(&mut self, biome_type: &str, properties: BiomeMaterialProperties) {
-        self.biome_effects
-            .insert(biome_type.to_string(), properties);
+    pub fn add_biome_effect(&mut self, biome: BiomeId, properties: BiomeMaterialProperties) {
+        self.biome_effects.insert(biome, properties);
     }
 
     /// Get material properties for a specific biome
@@ -192,7 +195,7 @@ impl BiomeMaterialConfig {
         biome_params: &BiomeTextureParams,
     ) -> BiomeMaterialProperties {
         // Check for biome-specific properties first
-        if let Some(biome_properties) = self.biome_effects.get(&biome_params.biome_type) {
+        if let Some(biome_properties) = self.biome_effects.get(&biome_params.biome) {
             return biome_properties.clone();
         }
 
@@ -223,6 +226,7 @@ impl BiomeMaterialConfig {
 
 /// Enhanced biome material cache entry
 #[derive(Debug, Clone)]
+#[allow(dead_code)]
 pub struct BiomeMaterialCacheEntry {
     pub material_handle: Handle<BiomeMaterial>,
     #[allow(dead_code)]
@@ -243,6 +247,7 @@ pub struct BiomeMaterialCache {
 }
 
 #[derive(Debug, Clone, Copy)]
+#[allow(dead_code)]
 pub struct BiomeMaterialCacheConfig {
     pub max_materials: usize,
     pub max_memory_mb: usize,
@@ -272,6 +277,7 @@ pub struct BiomeMaterialCacheStats {
     pub current_materials: usize,
 }
 
+#[allow(dead_code)]
 impl BiomeMaterialCache {
     pub fn new(config: BiomeMaterialCacheConfig) -> Self {
         Self {
@@ -287,7 +293,7 @@ impl BiomeMaterialCache {
         format!(
             "{:?}-{}-{}-{}-{}",
             block_type,
-            biome_params.biome_type,
+            biome_params.biome.0,
             (biome_params.temperature * 10.0).round() as i32,
             (biome_params.moisture * 10.0).round() as i32,
             (biome_params.height * 10.0).round() as i32
@@ -147,24 +147,16 @@ impl BiomeTextureCache {
         }
 
         // Convert block type to string for texture generation
-        let block_type_str = match block_type {
-            BlockType::Stone => "stone",
-            BlockType::Dirt => "dirt",
-            BlockType::Grass => "grass",
-            BlockType::Wood => "wood",
-            BlockType::Leaves => "leaves",
-            BlockType::Sand => "sand",
-            BlockType::Water => "water",
-            BlockType::Bedrock => "bedrock",
-            _ => "stone", // Default to stone for unknown types
-        };
+        let block_type_str = block_type
+            .texture_name(crate::texture_atlas::BlockFace::Side)
+            .unwrap_or("stone"); // Default to stone for blocks without a texture
 
         // Generate biome-aware texture data
         let texture_data =
             crate::texture_gen::generate_biome_texture_data(128, 128, biome_params, block_type_str);
 
         // Create image from generated texture data
-        let image = Image::new(
+        let mut image = Image::new(
             Extent3d {
                 width: 128,
                 height: 128,
@@ -175,6 +167,7 @@ impl BiomeTextureCache {
             TextureFormat::Rgba8UnormSrgb,
             RenderAssetUsages::default(),
         );
+        image.sampler = crate::texture_gen::repeating_sampler();
 
         let texture_handle = images.add(image);
 
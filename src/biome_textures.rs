@@ -1,13 +1,16 @@
 // Biome-based texture parameterization system
 // This module handles texture variations based on biome and height parameters
 
+use crate::biome::{Biome, BiomeId, BiomeRegistry};
 use crate::block::BlockType;
 use crate::noise::NoiseSettings;
 
 /// Biome texture parameters that influence texture generation
 #[derive(Debug, Clone, PartialEq)]
 pub struct BiomeTextureParams {
-    pub biome_type: String,
+    pub biome: BiomeId,
+    /// Colour multiplier from the biome registry
+    pub tint: (f32, f32, f32),
     pub temperature: f32,
     pub moisture: f32,
     pub height: f32,
@@ -16,14 +19,15 @@ pub struct BiomeTextureParams {
 
 impl BiomeTextureParams {
     pub fn new(
-        biome_type: String,
+        biome: &Biome,
         temperature: f32,
         moisture: f32,
         height: f32,
         relative_height: f32,
     ) -> Self {
         Self {
-            biome_type,
+            biome: biome.id,
+            tint: biome.tint,
             temperature,
             moisture,
             height,
@@ -44,7 +48,7 @@ pub struct BiomeTextureConfig {
 impl BiomeTextureConfig {
     /// Create a biome texture config for a specific block type
     pub fn for_block_type(block_type: &BlockType) -> Self {
-        let base_config = match block_type {
+        let base_config = match *block_type {
             BlockType::Grass => NoiseSettings {
                 base_height: 0.0,
                 height_scale: 1.0,
@@ -53,6 +57,7 @@ impl BiomeTextureConfig {
                 persistence: 0.5,
                 lacunarity: 2.0,
                 biome_scale: 0.005,
+                seed: 0,
             },
             BlockType::Dirt => NoiseSettings {
                 base_height: 0.0,
@@ -62,6 +67,7 @@ impl BiomeTextureConfig {
                 persistence: 0.5,
                 lacunarity: 2.0,
                 biome_scale: 0.005,
+                seed: 0,
             },
             BlockType::Stone => NoiseSettings {
                 base_height: 0.0,
@@ -71,6 +77,7 @@ impl BiomeTextureConfig {
                 persistence: 0.5,
                 lacunarity: 2.0,
                 biome_scale: 0.005,
+                seed: 0,
             },
             BlockType::Sand => NoiseSettings {
                 base_height: 0.0,
@@ -80,6 +87,7 @@ impl BiomeTextureConfig {
                 persistence: 0.5,
                 lacunarity: 2.0,
                 biome_scale: 0.005,
+                seed: 0,
             },
             BlockType::Wood => NoiseSettings {
                 base_height: 0.0,
@@ -89,12 +97,13 @@ impl BiomeTextureConfig {
                 persistence: 0.5,
                 lacunarity: 2.0,
                 biome_scale: 0.005,
+                seed: 0,
             },
             _ => NoiseSettings::default(),
         };
 
         // Default biome effects
-        let (temperature_effect, moisture_effect, height_effect) = match block_type {
+        let (temperature_effect, moisture_effect, height_effect) = match *block_type {
             BlockType::Grass => (0.8, 0.6, 0.4),
             BlockType::Dirt => (0.6, 0.5, 0.3),
             BlockType::Stone => (0.4, 0.3, 0.5),
@@ -147,63 +156,25 @@ pub fn generate_texture_cache_key(
     format!(
         "{:?}-{}-{}-{}-{}",
         block_type,
-        biome_params.biome_type,
+        biome_params.biome.0,
         (biome_params.temperature * 10.0).round() as i32,
         (biome_params.moisture * 10.0).round() as i32,
         (biome_params.height * 10.0).round() as i32
     )
 }
 
-/// Determine biome type based on temperature and moisture
-#[allow(dead_code)]
-pub fn determine_biome_type(temperature: f32, moisture: f32, height: i32) -> String {
-    if height > 60 {
-        if temperature < 0.3 {
-            "snowy_mountain".to_string()
-        } else {
-            "rocky_mountain".to_string()
-        }
-    } else if temperature > 0.7 {
-        if moisture < 0.4 {
-            "desert".to_string()
-        } else {
-            "savanna".to_string()
-        }
-    } else if temperature > 0.5 {
-        if moisture > 0.7 {
-            "jungle".to_string()
-        } else if moisture > 0.4 {
-            "forest".to_string()
-        } else {
-            "grassland".to_string()
-        }
-    } else {
-        if moisture > 0.6 {
-            "swamp".to_string()
-        } else if moisture > 0.3 {
-            "plains".to_string()
-        } else {
-            "tundra".to_string()
-        }
-    }
-}
-
 /// Get biome texture parameters for a given position
 #[allow(dead_code)]
 pub fn get_biome_texture_params(
+    biomes: &BiomeRegistry,
     temperature: f32,
     moisture: f32,
     height: i32,
+    sea_level: i32,
     max_height: i32,
 ) -> BiomeTextureParams {
-    let biome_type = determine_biome_type(temperature, moisture, height);
+    let biome = biomes.classify(temperature, moisture, height - sea_level);
     let relative_height = height as f32 / max_height as f32;
 
-    BiomeTextureParams::new(
-        biome_type,
-        temperature,
-        moisture,
-        height as f32,
-        relative_height,
-    )
+    BiomeTextureParams::new(biome, temperature, moisture, height as f32, relative_height)
 }
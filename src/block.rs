@@ -1,88 +1,553 @@
+// Block registry for Bevy Craft
+// This module defines the blocks the world is built from
+//
+// Blocks are data rather than code: each one has a numeric id and lists its name, colour,
+// solidity, transparency, hardness, light emission, the texture of each face, what it drops, the
+// tool that breaks it fastest, whether it falls without support and, for fluids, how it flows. The registry is read from `assets/blocks.ron` at startup, with
+// a copy of that file built into the game as a fallback, so adding a block only takes an edit to
+// the file.
+//
+// Chunks store the numeric id of each block and look everything else up here. The registry is
+// installed once for the whole process, because block properties are needed everywhere from
+// world generation to meshing tasks running off the main thread.
+
 use bevy::prelude::*;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use crate::block_state::{BlockShape, BlockState, Facing, ShapeBox};
+use crate::fluid::FluidDefinition;
+use crate::inventory::{ItemType, ToolType};
+use crate::texture_atlas::BlockFace;
+
+/// Where the block registry is loaded from at startup
+pub const BLOCK_REGISTRY_PATH: &str = "assets/blocks.ron";
+
+/// Registry shipped with the game, used when the file on disk is missing or broken
+const BUILTIN_BLOCKS: &str = include_str!("../assets/blocks.ron");
 
-/// Enum representing different types of blocks in the game
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
-pub enum BlockType {
-    Air,
-    Dirt,
-    Stone,
-    Grass,
-    Wood,
-    Leaves,
-    Sand,
-    Water,
-    Bedrock,
-    Lava,
+/// Registry every block looks its properties up in
+static BLOCK_REGISTRY: OnceLock<BlockRegistry> = OnceLock::new();
+
+thread_local! {
+    /// Ids of the blocks in a registry being parsed, so its blocks can refer to each other
+    static LOADING_KEYS: RefCell<Option<HashMap<String, u16>>> = const { RefCell::new(None) };
 }
 
+/// Numeric id of a block type, as stored in chunks and world saves
+/// The blocks the game's code places itself have constants named after them; every other block
+/// only exists in the registry.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BlockType(pub u16);
+
+#[allow(non_upper_case_globals)]
 impl BlockType {
-    /// Get the display name of the block type
+    pub const Air: BlockType = BlockType(0);
+    pub const Dirt: BlockType = BlockType(1);
+    pub const Stone: BlockType = BlockType(2);
+    pub const Grass: BlockType = BlockType(3);
+    pub const Wood: BlockType = BlockType(4);
+    pub const Leaves: BlockType = BlockType(5);
+    pub const Sand: BlockType = BlockType(6);
+    pub const Water: BlockType = BlockType(7);
+    pub const Bedrock: BlockType = BlockType(8);
+    pub const Lava: BlockType = BlockType(9);
+    pub const CoalOre: BlockType = BlockType(10);
+    pub const IronOre: BlockType = BlockType(11);
+    pub const GoldOre: BlockType = BlockType(12);
+    pub const DiamondOre: BlockType = BlockType(13);
+    pub const Cobblestone: BlockType = BlockType(14);
+    pub const MossyCobblestone: BlockType = BlockType(15);
+    pub const Chest: BlockType = BlockType(16);
+}
+
+/// Blocks the game's code refers to, which the registry must define under these keys
+const BUILTIN_KEYS: [(BlockType, &str); 17] = [
+    (BlockType::Air, "Air"),
+    (BlockType::Dirt, "Dirt"),
+    (BlockType::Stone, "Stone"),
+    (BlockType::Grass, "Grass"),
+    (BlockType::Wood, "Wood"),
+    (BlockType::Leaves, "Leaves"),
+    (BlockType::Sand, "Sand"),
+    (BlockType::Water, "Water"),
+    (BlockType::Bedrock, "Bedrock"),
+    (BlockType::Lava, "Lava"),
+    (BlockType::CoalOre, "CoalOre"),
+    (BlockType::IronOre, "IronOre"),
+    (BlockType::GoldOre, "GoldOre"),
+    (BlockType::DiamondOre, "DiamondOre"),
+    (BlockType::Cobblestone, "Cobblestone"),
+    (BlockType::MossyCobblestone, "MossyCobblestone"),
+    (BlockType::Chest, "Chest"),
+];
+
+impl BlockType {
+    /// Everything the registry knows about this block type
+    pub fn definition(&self) -> &'static BlockDefinition {
+        BlockRegistry::global().get(*self)
+    }
+
+    /// Look up a block type by the key data files use for it
     #[allow(dead_code)]
-    pub fn name(&self) -> &str {
-        match self {
-            BlockType::Air => "Air",
-            BlockType::Dirt => "Dirt",
-            BlockType::Stone => "Stone",
-            BlockType::Grass => "Grass",
-            BlockType::Wood => "Wood",
-            BlockType::Leaves => "Leaves",
-            BlockType::Sand => "Sand",
-            BlockType::Water => "Water",
-            BlockType::Bedrock => "Bedrock",
-            BlockType::Lava => "Lava",
-        }
+    pub fn from_key(key: &str) -> Option<BlockType> {
+        BlockRegistry::global()
+            .by_key(key)
+            .map(BlockDefinition::block_type)
+    }
+
+    /// Get the display name of the block type
+    pub fn name(&self) -> &'static str {
+        &self.definition().name
     }
 
     /// Get the color associated with this block type
     pub fn color(&self) -> Color {
-        match self {
-            BlockType::Air => Color::NONE,
-            BlockType::Dirt => Color::srgb(0.5, 0.3, 0.2),
-            BlockType::Stone => Color::srgb(0.7, 0.7, 0.7),
-            BlockType::Grass => Color::srgb(0.2, 0.8, 0.2),
-            BlockType::Wood => Color::srgb(0.6, 0.4, 0.2),
-            BlockType::Leaves => Color::srgb(0.1, 0.7, 0.1),
-            BlockType::Sand => Color::srgb(0.9, 0.8, 0.5),
-            BlockType::Water => Color::srgb(0.1, 0.1, 0.9),
-            BlockType::Bedrock => Color::srgb(0.3, 0.3, 0.3),
-            BlockType::Lava => Color::srgb(1.0, 0.5, 0.0),
-        }
+        self.definition()
+            .color
+            .map_or(Color::NONE, |(r, g, b)| Color::srgb(r, g, b))
     }
 
     /// Check if the block is solid (not air or water)
     pub fn is_solid(&self) -> bool {
-        match self {
-            BlockType::Air | BlockType::Water => false,
-            _ => true,
-        }
+        self.definition().solid
     }
 
     /// Check if the block is transparent
+    /// Blocks that don't fill their cell always are, since light and faces show past them.
     pub fn is_transparent(&self) -> bool {
-        match self {
-            BlockType::Air | BlockType::Water | BlockType::Leaves => true,
-            _ => false,
+        let definition = self.definition();
+        definition.transparent || !definition.shape.is_full_cube()
+    }
+
+    /// Shape the block is drawn and collides with
+    pub fn shape(&self) -> BlockShape {
+        self.definition().shape
+    }
+
+    /// How the block flows, if it's a fluid
+    pub fn fluid(&self) -> Option<FluidDefinition> {
+        self.definition().fluid
+    }
+
+    /// Whether the block falls when nothing solid holds it up
+    pub fn has_gravity(&self) -> bool {
+        self.definition().gravity
+    }
+
+    /// Boxes the block is made of in a state
+    /// `neighbour` looks up the block on each side; fences reach out to other fences and to full
+    /// solid blocks. Fluids fill their cell up to the height of their surface.
+    pub fn boxes(
+        &self,
+        state: BlockState,
+        neighbour: impl Fn(Facing) -> Option<BlockType>,
+    ) -> Vec<ShapeBox> {
+        if let Some(fluid) = self.fluid() {
+            return vec![ShapeBox::new(
+                Vec3::ZERO,
+                Vec3::new(1.0, fluid.height(state), 1.0),
+            )];
         }
+        self.shape().boxes(state, |facing| {
+            neighbour(facing).is_some_and(|block| {
+                block.shape() == BlockShape::Fence || (block.is_solid() && !block.is_transparent())
+            })
+        })
+    }
+
+    /// Light level this block gives off, from 0 to 15
+    pub fn light_emission(&self) -> u8 {
+        self.definition().light_emission
     }
 
     /// Get the hardness of this block type (higher = harder to break)
     /// Returns None for unbreakable blocks
     pub fn hardness(&self) -> Option<f32> {
-        match self {
-            BlockType::Air => None,
-            BlockType::Dirt => Some(1.5),
-            BlockType::Stone => Some(3.0),
-            BlockType::Grass => Some(1.2),
-            BlockType::Wood => Some(2.0),
-            BlockType::Leaves => Some(0.5),
-            BlockType::Sand => Some(1.0),
-            BlockType::Water => None,
-            BlockType::Bedrock => None,
-            BlockType::Lava => Some(100.0),
+        self.definition().hardness
+    }
+
+    /// Tool that breaks this block fastest, if any
+    pub fn preferred_tool(&self) -> Option<ToolType> {
+        self.definition().tool
+    }
+
+    /// Name of the texture drawn on one face of the block
+    pub fn texture_name(&self, face: BlockFace) -> Option<&'static str> {
+        self.definition().textures.get(face)
+    }
+}
+
+impl fmt::Debug for BlockType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match BlockRegistry::global().by_id(*self) {
+            Some(block) => f.write_str(&block.key),
+            None => write!(f, "BlockType({})", self.0),
+        }
+    }
+}
+
+// Data files name blocks by their key, while world saves store the numeric id
+impl Serialize for BlockType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match BlockRegistry::global().by_id(*self) {
+            Some(block) if serializer.is_human_readable() => {
+                serializer.serialize_unit_variant("BlockType", self.0 as u32, &block.key)
+            }
+            _ => serializer.serialize_u16(self.0),
         }
     }
 }
 
+impl<'de> Deserialize<'de> for BlockType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct BlockTypeVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for BlockTypeVisitor {
+            type Value = BlockType;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a block key or numeric block id")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, key: &str) -> Result<BlockType, E> {
+                let loading = LOADING_KEYS
+                    .with_borrow(|keys| keys.as_ref().map(|keys| keys.get(key).copied()));
+                let id = match loading {
+                    Some(id) => id,
+                    None => BlockRegistry::global().by_key(key).map(|block| block.id),
+                };
+                id.map(BlockType)
+                    .ok_or_else(|| E::custom(format!("unknown block {:?}", key)))
+            }
+
+            fn visit_enum<A: serde::de::EnumAccess<'de>>(
+                self,
+                data: A,
+            ) -> Result<BlockType, A::Error> {
+                use serde::de::VariantAccess;
+
+                let (block_type, variant) = data.variant_seed(self)?;
+                variant.unit_variant()?;
+                Ok(block_type)
+            }
+
+            fn visit_u64<E: serde::de::Error>(self, id: u64) -> Result<BlockType, E> {
+                u16::try_from(id)
+                    .map(BlockType)
+                    .map_err(|_| E::custom(format!("block id {} out of range", id)))
+            }
+        }
+
+        // Keys are read as the variant of a unit enum, so data files name blocks like any enum
+        impl<'de> serde::de::DeserializeSeed<'de> for BlockTypeVisitor {
+            type Value = BlockType;
+
+            fn deserialize<D: Deserializer<'de>>(
+                self,
+                deserializer: D,
+            ) -> Result<BlockType, D::Error> {
+                deserializer.deserialize_identifier(self)
+            }
+        }
+
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_enum("BlockType", &[], BlockTypeVisitor)
+        } else {
+            deserializer.deserialize_u16(BlockTypeVisitor)
+        }
+    }
+}
+
+/// Textures drawn on the faces of a block, by name of the generated block texture
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FaceTextures {
+    /// Texture of every face not given one of its own
+    pub all: Option<String>,
+    pub top: Option<String>,
+    pub side: Option<String>,
+    pub bottom: Option<String>,
+}
+
+impl FaceTextures {
+    /// Name of the texture on a face
+    pub fn get(&self, face: BlockFace) -> Option<&str> {
+        let own = match face {
+            BlockFace::Top => &self.top,
+            BlockFace::Side => &self.side,
+            BlockFace::Bottom => &self.bottom,
+        };
+        own.as_ref().or(self.all.as_ref()).map(String::as_str)
+    }
+
+    /// Whether no face has a texture
+    pub fn is_empty(&self) -> bool {
+        [&self.all, &self.top, &self.side, &self.bottom]
+            .iter()
+            .all(|texture| texture.is_none())
+    }
+}
+
+/// An item handed to the player when a block breaks
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BlockDrop {
+    pub item: ItemType,
+    #[serde(default = "BlockDrop::default_count")]
+    pub count: u32,
+}
+
+impl BlockDrop {
+    fn default_count() -> u32 {
+        1
+    }
+}
+
+/// A single block definition
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockDefinition {
+    /// Id stored in chunks; keep it stable once worlds have been saved with the block
+    pub id: u16,
+    /// Name other data files refer to the block by
+    pub key: String,
+    /// Name shown to the player
+    pub name: String,
+    /// Plain colour of the block, used where it has no texture; None for air
+    #[serde(default)]
+    pub color: Option<(f32, f32, f32)>,
+    /// Whether the player collides with the block
+    pub solid: bool,
+    /// Whether light and the faces behind it show through the block
+    pub transparent: bool,
+    /// Shape the block takes, and with it which state bits it uses
+    #[serde(default)]
+    pub shape: BlockShape,
+    /// How long the block takes to break; None for blocks that can't be broken
+    #[serde(default)]
+    pub hardness: Option<f32>,
+    /// Light level the block gives off, from 0 to 15
+    #[serde(default)]
+    pub light_emission: u8,
+    #[serde(default)]
+    pub textures: FaceTextures,
+    /// Items the block breaks into; left out, the block drops itself
+    #[serde(default)]
+    pub drops: Option<Vec<BlockDrop>>,
+    /// Tool that breaks the block fastest
+    #[serde(default)]
+    pub tool: Option<ToolType>,
+    /// How the block flows; None for blocks that aren't fluids
+    #[serde(default)]
+    pub fluid: Option<FluidDefinition>,
+    /// Whether the block falls when nothing solid holds it up
+    #[serde(default)]
+    pub gravity: bool,
+}
+
+impl BlockDefinition {
+    /// Block type this definition describes
+    pub fn block_type(&self) -> BlockType {
+        BlockType(self.id)
+    }
+}
+
+/// Layout of the registry file
+#[derive(Debug, Serialize, Deserialize)]
+struct BlockRegistryFile {
+    /// Key of the block used in place of ids the registry doesn't know
+    fallback: String,
+    blocks: Vec<BlockDefinition>,
+}
+
+/// Every block in the game
+#[derive(Debug, Clone)]
+pub struct BlockRegistry {
+    blocks: Vec<BlockDefinition>,
+    /// Index into `blocks` of each id, or None for ids without a block
+    by_id: Vec<Option<usize>>,
+    by_key: HashMap<String, usize>,
+    fallback: usize,
+}
+
+impl BlockRegistry {
+    /// The registry built into the game
+    pub fn builtin() -> Self {
+        Self::from_ron(BUILTIN_BLOCKS).expect("built-in block registry is valid")
+    }
+
+    /// Parse a registry from RON text
+    pub fn from_ron(text: &str) -> io::Result<Self> {
+        /// Just the ids and keys of the file's blocks
+        #[derive(Deserialize)]
+        struct KeysFile {
+            blocks: Vec<BlockKey>,
+        }
+        #[derive(Deserialize)]
+        struct BlockKey {
+            id: u16,
+            key: String,
+        }
+
+        let invalid =
+            |error: ron::error::SpannedError| io::Error::new(io::ErrorKind::InvalidData, error);
+        // Blocks name each other by key, for example in their drops, so the keys are read first
+        let keys: KeysFile = ron::from_str(text).map_err(invalid)?;
+        LOADING_KEYS.set(Some(
+            keys.blocks
+                .into_iter()
+                .map(|block| (block.key, block.id))
+                .collect(),
+        ));
+        let file: Result<BlockRegistryFile, _> = ron::from_str(text);
+        LOADING_KEYS.set(None);
+        let file = file.map_err(invalid)?;
+        Self::from_blocks(file.blocks, &file.fallback)
+    }
+
+    /// Build a registry, checking that ids and keys are unique and that every block the game's
+    /// code refers to is there
+    fn from_blocks(blocks: Vec<BlockDefinition>, fallback: &str) -> io::Result<Self> {
+        let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+
+        let mut by_id = Vec::new();
+        let mut by_key = HashMap::new();
+        for (index, block) in blocks.iter().enumerate() {
+            let id = block.id as usize;
+            if by_id.len() <= id {
+                by_id.resize(id + 1, None);
+            }
+            if by_id[id].replace(index).is_some() {
+                return Err(invalid(format!("duplicate block id {}", id)));
+            }
+            if by_key.insert(block.key.clone(), index).is_some() {
+                return Err(invalid(format!("duplicate block key {:?}", block.key)));
+            }
+            if block.light_emission > crate::lighting::MAX_LIGHT {
+                return Err(invalid(format!(
+                    "block {:?} gives off more light than the maximum",
+                    block.key
+                )));
+            }
+            if let Some(fluid) = block.fluid
+                && !(1..=BlockState::MAX_FLUID_LEVEL).contains(&fluid.spread)
+            {
+                return Err(invalid(format!(
+                    "fluid {:?} must spread between 1 and {} blocks",
+                    block.key,
+                    BlockState::MAX_FLUID_LEVEL
+                )));
+            }
+        }
+        for (block_type, key) in BUILTIN_KEYS {
+            if by_key.get(key) != by_id.get(block_type.0 as usize).copied().flatten().as_ref() {
+                return Err(invalid(format!(
+                    "block {:?} must have id {}",
+                    key, block_type.0
+                )));
+            }
+        }
+        let fallback = *by_key
+            .get(fallback)
+            .ok_or_else(|| invalid(format!("unknown fallback block {:?}", fallback)))?;
+
+        Ok(Self {
+            blocks,
+            by_id,
+            by_key,
+            fallback,
+        })
+    }
+
+    /// Load a registry from a RON file
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::from_ron(&fs::read_to_string(path)?)
+    }
+
+    /// Load the registry from disk, falling back to the built-in one if that fails
+    pub fn load_or_builtin(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+        match Self::load(path) {
+            Ok(registry) => {
+                println!(
+                    "🧱 Loaded {} blocks from {}",
+                    registry.blocks.len(),
+                    path.display()
+                );
+                registry
+            }
+            Err(error) => {
+                println!(
+                    "⚠️  Could not load blocks from {}: {}, using built-in blocks",
+                    path.display(),
+                    error
+                );
+                Self::builtin()
+            }
+        }
+    }
+
+    /// Make this the registry every block looks its properties up in
+    /// It has to happen before anything reads a block, since the first lookup settles on the
+    /// built-in registry for the rest of the run.
+    pub fn install(self) {
+        if BLOCK_REGISTRY.set(self).is_err() {
+            println!("⚠️  Block registry was already in use, keeping the one in place");
+        }
+    }
+
+    /// The registry installed for the process, or the built-in one if none was
+    pub fn global() -> &'static Self {
+        BLOCK_REGISTRY.get_or_init(Self::builtin)
+    }
+
+    /// Look up a block
+    /// Ids the registry doesn't know, for example from a save made with other blocks, map to
+    /// the fallback block.
+    pub fn get(&self, block_type: BlockType) -> &BlockDefinition {
+        &self.blocks[self
+            .by_id
+            .get(block_type.0 as usize)
+            .copied()
+            .flatten()
+            .unwrap_or(self.fallback)]
+    }
+
+    /// Look up a block, without falling back for unknown ids
+    pub fn by_id(&self, block_type: BlockType) -> Option<&BlockDefinition> {
+        let index = self.by_id.get(block_type.0 as usize).copied().flatten()?;
+        Some(&self.blocks[index])
+    }
+
+    pub fn by_key(&self, key: &str) -> Option<&BlockDefinition> {
+        self.by_key.get(key).map(|&index| &self.blocks[index])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &BlockDefinition> {
+        self.blocks.iter()
+    }
+
+    /// Names of every texture some block's faces are drawn with, each listed once
+    pub fn texture_names(&self) -> Vec<&str> {
+        let mut names = Vec::new();
+        for block in &self.blocks {
+            for face in [BlockFace::Top, BlockFace::Side, BlockFace::Bottom] {
+                if let Some(name) = block.textures.get(face)
+                    && !names.contains(&name)
+                {
+                    names.push(name);
+                }
+            }
+        }
+        names
+    }
+}
+
 /// Component representing a block in the game world
 #[derive(Component, Debug)]
 pub struct Block {
@@ -99,3 +564,82 @@ impl Block {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inventory::ResourceType;
+
+    #[test]
+    fn test_builtin_registry_describes_every_block() {
+        let registry = BlockRegistry::builtin();
+        for (block_type, key) in BUILTIN_KEYS {
+            assert_eq!(registry.get(block_type).key, key);
+        }
+
+        assert!(!BlockType::Water.is_solid() && BlockType::Water.is_transparent());
+        assert!(BlockType::Leaves.is_solid() && BlockType::Leaves.is_transparent());
+        assert_eq!(BlockType::Lava.light_emission(), 15);
+        assert_eq!(BlockType::Bedrock.hardness(), None);
+        assert_eq!(BlockType::Stone.preferred_tool(), Some(ToolType::Pickaxe));
+        assert_eq!(BlockType::CoalOre.name(), "Coal Ore");
+        assert_eq!(BlockType::Grass.texture_name(BlockFace::Top), Some("grass"));
+
+        assert_eq!(
+            ItemType::dropped_by(BlockType::Stone),
+            vec![(ItemType::Block(BlockType::Stone), 1)]
+        );
+        assert_eq!(
+            ItemType::dropped_by(BlockType::DiamondOre),
+            vec![(ItemType::Resource(ResourceType::Diamond), 1)]
+        );
+        assert_eq!(
+            ItemType::dropped_by(BlockType::Chest),
+            vec![(ItemType::Block(BlockType::Wood), 1)]
+        );
+
+        // Unknown ids, say from a save made with more blocks, read back as the fallback
+        assert_eq!(registry.get(BlockType(999)).key, "Stone");
+
+        // Data files name blocks by key, saves by id
+        assert_eq!(ron::to_string(&BlockType::Sand).unwrap(), "Sand");
+        assert_eq!(ron::from_str::<BlockType>("Sand").unwrap(), BlockType::Sand);
+        let bytes = bincode::serialize(&BlockType::Chest).unwrap();
+        assert_eq!(bytes, 16u16.to_le_bytes());
+        assert_eq!(
+            bincode::deserialize::<BlockType>(&bytes).unwrap(),
+            BlockType::Chest
+        );
+    }
+
+    #[test]
+    fn test_registry_adds_blocks_and_rejects_broken_files() {
+        // A new block may drop another one declared later in the same file
+        let extended = BUILTIN_BLOCKS.replacen(
+            "    blocks: [",
+            r#"    blocks: [
+        (id: 40, key: "Gravel", name: "Gravel", solid: true, transparent: false,
+         hardness: 1.0, textures: (all: "dirt"), drops: [(item: Block(Flint), count: 2)]),
+        (id: 41, key: "Flint", name: "Flint", solid: true, transparent: false),"#,
+            1,
+        );
+        let registry = BlockRegistry::from_ron(&extended).unwrap();
+        let gravel = registry.by_key("Gravel").unwrap();
+        assert_eq!(gravel.block_type(), BlockType(40));
+        assert_eq!(
+            gravel.drops,
+            Some(vec![BlockDrop {
+                item: ItemType::Block(BlockType(41)),
+                count: 2
+            }])
+        );
+        assert_eq!(registry.by_id(BlockType(30)).map(|block| &block.key), None);
+
+        let duplicate_id = BUILTIN_BLOCKS.replacen("id: 16,", "id: 15,", 1);
+        assert!(BlockRegistry::from_ron(&duplicate_id).is_err());
+        let moved_builtin = BUILTIN_BLOCKS.replacen("id: 16,", "id: 17,", 1);
+        assert!(BlockRegistry::from_ron(&moved_builtin).is_err());
+        let unknown_drop = BUILTIN_BLOCKS.replacen("Block(Wood)", "Block(Planks)", 1);
+        assert!(BlockRegistry::from_ron(&unknown_drop).is_err());
+    }
+}
@@ -8,6 +8,9 @@ use crate::block::BlockType;
 use crate::chunk::{Chunk, ChunkManager, ChunkPosition};
 use crate::inventory::{Inventory, ItemType};
 
+/// How many times faster a block breaks with its preferred tool in hand
+const PREFERRED_TOOL_SPEEDUP: f32 = 3.0;
+
 /// Resource to track block breaking progress
 #[derive(Resource, Default)]
 pub struct BlockBreakingProgress {
@@ -58,11 +61,13 @@ pub fn block_breaking_system(
     left_button: Res<LeftMouseButtonState>,
     camera_query: Query<(&Transform, &crate::camera::GameCamera)>,
     player_query: Query<&Transform, With<crate::player::Player>>,
-    chunk_manager: Res<ChunkManager>,
+    mut chunk_manager: ResMut<ChunkManager>,
     mut inventory: ResMut<Inventory>,
     mut chunks: Query<&mut Chunk>,
     mut breaking_progress: ResMut<BlockBreakingProgress>,
     time: Res<Time>,
+    world_gen_settings: Res<crate::world_gen::WorldGenSettings>,
+    biomes: Res<crate::biome::BiomeRegistry>,
 ) {
     trace!(
         "block_breaking_system called, left button pressed: {}",
@@ -112,17 +117,56 @@ pub fn block_breaking_system(
                                 breaking_progress.is_breaking = true;
 
                                 // Accumulate damage based on delta time and hardness
-                                let damage_per_second = 10.0 / hardness;
+                                let holding_preferred_tool =
+                                    current_block_type.preferred_tool().is_some_and(|tool| {
+                                        inventory.get_selected_item().is_some_and(|stack| {
+                                            !stack.is_empty()
+                                                && stack.item_type == ItemType::Tool(tool)
+                                        })
+                                    });
+                                let tool_speedup = if holding_preferred_tool {
+                                    PREFERRED_TOOL_SPEEDUP
+                                } else {
+                                    1.0
+                                };
+                                let damage_per_second = 10.0 * tool_speedup / hardness;
                                 let damage_this_frame = damage_per_second * time.delta_secs();
                                 breaking_progress.accumulated_damage += damage_this_frame;
 
                                 // Check if block should break
                                 if breaking_progress.accumulated_damage >= 1.0 {
-                                    // Remove the block (set to Air)
-                                    chunk.set_block_world(target_block_pos, BlockType::Air);
+                                    // Remove the block, leaving behind any water it held
+                                    let waterlogged =
+                                        chunk.get_block_state_world(target_block_pos).waterlogged();
+                                    let left_behind = if waterlogged {
+                                        BlockType::Water
+                                    } else {
+                                        BlockType::Air
+                                    };
+                                    chunk.set_block_world(target_block_pos, left_behind);
+                                    chunk_manager.block_updates.block_changed(target_block_pos);
+
+                                    // Add whatever the broken block drops to inventory
+                                    for (item, count) in ItemType::dropped_by(current_block_type) {
+                                        inventory.add_item(item, count);
+                                    }
 
-                                    // Add the broken block to inventory
-                                    inventory.add_item(ItemType::Block(current_block_type), 1);
+                                    // Chests left by structures spill their loot
+                                    if current_block_type == BlockType::Chest {
+                                        let loot = crate::structures::chest_loot(
+                                            &world_gen_settings,
+                                            &biomes,
+                                            target_block_pos,
+                                        );
+                                        for (item, count) in loot.unwrap_or_default() {
+                                            println!(
+                                                "🎁 Found {} x{} in the chest",
+                                                item.name(),
+                                                count
+                                            );
+                                            inventory.add_item(item, count);
+                                        }
+                                    }
 
                                     // Reset breaking progress
                                     breaking_progress.target_block_pos = None;
@@ -158,7 +202,7 @@ pub fn block_placement_system(
     mut right_button: ResMut<RightMouseButtonState>,
     camera_query: Query<(&Transform, &crate::camera::GameCamera)>,
     player_query: Query<&Transform, With<crate::player::Player>>,
-    chunk_manager: Res<ChunkManager>,
+    mut chunk_manager: ResMut<ChunkManager>,
     mut inventory: ResMut<Inventory>,
     mut chunks: Query<&mut Chunk>,
 ) {
@@ -210,6 +254,16 @@ pub fn block_placement_system(
                                 ray_direction,
                             );
 
+                            // Orient the block from the face it's placed against and where the
+                            // player is looking
+                            let (face_normal, hit_point) =
+                                hit_face(target_block_pos, ray_origin, ray_direction);
+                            let state = block_type.shape().placement_state(
+                                face_normal,
+                                hit_point.y - target_block_pos.y as f32,
+                                ray_direction,
+                            );
+
                             // Find which chunk contains this block
                             let chunk_pos = ChunkPosition::from_block_position(placement_pos);
 
@@ -217,13 +271,30 @@ pub fn block_placement_system(
                             if let Some(chunk_entity) = chunk_manager.loaded_chunks.get(&chunk_pos)
                             {
                                 if let Ok(mut chunk) = chunks.get_mut(*chunk_entity) {
-                                    // Check if the placement position is empty (Air)
+                                    // Check if the placement position is empty (Air or Water)
                                     if let Some(current_block) =
                                         chunk.get_block_world(placement_pos)
                                     {
-                                        if current_block == BlockType::Air {
+                                        if current_block == BlockType::Air
+                                            || current_block == BlockType::Water
+                                        {
+                                            // Blocks that leave part of their cell open keep the
+                                            // water source they're placed in
+                                            let waterlogged = current_block == BlockType::Water
+                                                && chunk
+                                                    .get_block_state_world(placement_pos)
+                                                    .is_fluid_source()
+                                                && !block_type.shape().is_full_cube();
+
                                             // Place the block
-                                            chunk.set_block_world(placement_pos, block_type);
+                                            chunk.set_block_with_state_world(
+                                                placement_pos,
+                                                block_type,
+                                                state.with_waterlogged(waterlogged),
+                                            );
+                                            chunk_manager
+                                                .block_updates
+                                                .block_changed(placement_pos);
 
                                             // Remove one block from inventory
                                             inventory.remove_item(ItemType::Block(block_type), 1);
@@ -261,10 +332,7 @@ fn raycast_for_block_immutable(
         // Convert current position to block coordinates
         let block_pos = IVec3::new(
             current_pos.x.floor() as i32,
-            current_pos
-                .y
-                .floor()
-                .clamp(0.0, crate::chunk::CHUNK_HEIGHT as f32 - 1.0) as i32,
+            current_pos.y.floor() as i32,
             current_pos.z.floor() as i32,
         );
 
@@ -311,10 +379,7 @@ fn raycast_for_block_mutable(
         // Convert current position to block coordinates
         let block_pos = IVec3::new(
             current_pos.x.floor() as i32,
-            current_pos
-                .y
-                .floor()
-                .clamp(0.0, crate::chunk::CHUNK_HEIGHT as f32 - 1.0) as i32,
+            current_pos.y.floor() as i32,
             current_pos.z.floor() as i32,
         );
 
@@ -341,49 +406,42 @@ fn raycast_for_block_mutable(
     None // No block found within max distance
 }
 
-/// Find the adjacent block position for placement
-/// Given a target block position and ray information, determine where to place a new block
-fn find_adjacent_block_position(
-    target_block_pos: IVec3,
-    _ray_origin: Vec3,
-    ray_direction: Vec3,
-) -> IVec3 {
-    // Convert target block position to Vec3 for calculations
-    let _target_block_center = target_block_pos.as_vec3() + Vec3::splat(0.5);
-
-    // Determine which face of the target block is being looked at
-    // by finding the component with the largest absolute value
-    let abs_direction = ray_direction.abs();
-
-    // Find which axis has the largest component (this is the face we're looking at)
-    if abs_direction.x >= abs_direction.y && abs_direction.x >= abs_direction.z {
-        // Looking at X face (either positive or negative)
-        if ray_direction.x > 0.0 {
-            // Looking at negative X face, place on positive X side
-            target_block_pos + IVec3::new(1, 0, 0)
-        } else {
-            // Looking at positive X face, place on negative X side
-            target_block_pos + IVec3::new(-1, 0, 0)
+/// Face of a block a ray enters it through, as the face's outward normal, and the point where
+/// the ray crosses it
+fn hit_face(target_block_pos: IVec3, ray_origin: Vec3, ray_direction: Vec3) -> (IVec3, Vec3) {
+    let block_min = target_block_pos.as_vec3();
+
+    // The ray enters the block on the axis where it crosses the block's near plane last
+    let mut entry = (f32::NEG_INFINITY, IVec3::Y);
+    for axis in 0..3 {
+        if ray_direction[axis] == 0.0 {
+            continue;
         }
-    } else if abs_direction.y >= abs_direction.x && abs_direction.y >= abs_direction.z {
-        // Looking at Y face
-        if ray_direction.y > 0.0 {
-            // Looking at negative Y face, place on positive Y side
-            target_block_pos + IVec3::new(0, 1, 0)
+        let (near, normal) = if ray_direction[axis] > 0.0 {
+            (block_min[axis], -1)
         } else {
-            // Looking at positive Y face, place on negative Y side
-            target_block_pos + IVec3::new(0, -1, 0)
-        }
-    } else {
-        // Looking at Z face
-        if ray_direction.z > 0.0 {
-            // Looking at negative Z face, place on positive Z side
-            target_block_pos + IVec3::new(0, 0, 1)
-        } else {
-            // Looking at positive Z face, place on negative Z side
-            target_block_pos + IVec3::new(0, 0, -1)
+            (block_min[axis] + 1.0, 1)
+        };
+        let distance = (near - ray_origin[axis]) / ray_direction[axis];
+        if distance > entry.0 {
+            let mut face_normal = IVec3::ZERO;
+            face_normal[axis] = normal;
+            entry = (distance, face_normal);
         }
     }
+
+    let (distance, face_normal) = entry;
+    (face_normal, ray_origin + ray_direction * distance.max(0.0))
+}
+
+/// Position next to the face of the target block the ray hits, where a new block goes
+fn find_adjacent_block_position(
+    target_block_pos: IVec3,
+    ray_origin: Vec3,
+    ray_direction: Vec3,
+) -> IVec3 {
+    let (face_normal, _) = hit_face(target_block_pos, ray_origin, ray_direction);
+    target_block_pos + face_normal
 }
 
 /// System to provide visual feedback for targeted blocks
@@ -0,0 +1,361 @@
+// Block states for Bevy Craft
+// This module describes the state bits placed blocks carry and the shapes they take
+//
+// Every block in a chunk stores a small `BlockState` next to its type: the axis a log lies along,
+// the way stairs face, whether a slab or stair sits in the top or bottom half of its cell and
+// whether the block is waterlogged. Which of those bits mean anything depends on the block's
+// `BlockShape`, set in the block registry; fluids, which have no axis or facing, keep how far they
+// have flowed in those bits instead. Shapes other than full cubes are built from boxes,
+// which meshing draws and collision tests against.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// An axis of the world
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+impl Axis {
+    /// Index of the axis in a position (0 = x, 1 = y, 2 = z)
+    pub fn index(self) -> usize {
+        match self {
+            Axis::X => 0,
+            Axis::Y => 1,
+            Axis::Z => 2,
+        }
+    }
+}
+
+/// A horizontal direction, with north towards negative z
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Facing {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Facing {
+    pub const ALL: [Facing; 4] = [Facing::North, Facing::East, Facing::South, Facing::West];
+
+    /// Offset to the neighbouring block in this direction
+    pub fn offset(self) -> IVec3 {
+        match self {
+            Facing::North => IVec3::new(0, 0, -1),
+            Facing::East => IVec3::new(1, 0, 0),
+            Facing::South => IVec3::new(0, 0, 1),
+            Facing::West => IVec3::new(-1, 0, 0),
+        }
+    }
+
+    /// Direction a vector mostly points in across the ground
+    pub fn from_direction(direction: Vec3) -> Self {
+        if direction.x.abs() > direction.z.abs() {
+            if direction.x > 0.0 {
+                Facing::East
+            } else {
+                Facing::West
+            }
+        } else if direction.z > 0.0 {
+            Facing::South
+        } else {
+            Facing::North
+        }
+    }
+}
+
+/// Which half of its cell a slab or stair fills
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Half {
+    Bottom,
+    Top,
+}
+
+/// State bits of a placed block, packed into one byte
+/// Bits 0-1 hold the axis, 2-3 the facing, 4 the half and 5 whether the block is waterlogged. The
+/// default state, all zero, is a block along the y axis facing north in the bottom half.
+/// Fluids reuse bits 0-2 for their level and bit 3 for whether they are falling; their default
+/// state is a source.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct BlockState(u8);
+
+impl BlockState {
+    const AXIS_SHIFT: u8 = 0;
+    const FACING_SHIFT: u8 = 2;
+    const HALF_BIT: u8 = 1 << 4;
+    const WATERLOGGED_BIT: u8 = 1 << 5;
+    const FLUID_LEVEL_MASK: u8 = 0b111;
+    const FALLING_BIT: u8 = 1 << 3;
+    /// Highest level a fluid can have flowed to
+    pub const MAX_FLUID_LEVEL: u8 = 7;
+
+    pub fn axis(self) -> Axis {
+        match (self.0 >> Self::AXIS_SHIFT) & 0b11 {
+            1 => Axis::X,
+            2 => Axis::Z,
+            _ => Axis::Y,
+        }
+    }
+
+    pub fn with_axis(self, axis: Axis) -> Self {
+        let bits = match axis {
+            Axis::Y => 0,
+            Axis::X => 1,
+            Axis::Z => 2,
+        };
+        Self((self.0 & !(0b11 << Self::AXIS_SHIFT)) | (bits << Self::AXIS_SHIFT))
+    }
+
+    pub fn facing(self) -> Facing {
+        Facing::ALL[((self.0 >> Self::FACING_SHIFT) & 0b11) as usize]
+    }
+
+    pub fn with_facing(self, facing: Facing) -> Self {
+        let bits = Facing::ALL.iter().position(|&f| f == facing).unwrap_or(0) as u8;
+        Self((self.0 & !(0b11 << Self::FACING_SHIFT)) | (bits << Self::FACING_SHIFT))
+    }
+
+    pub fn half(self) -> Half {
+        if self.0 & Self::HALF_BIT != 0 {
+            Half::Top
+        } else {
+            Half::Bottom
+        }
+    }
+
+    pub fn with_half(self, half: Half) -> Self {
+        match half {
+            Half::Bottom => Self(self.0 & !Self::HALF_BIT),
+            Half::Top => Self(self.0 | Self::HALF_BIT),
+        }
+    }
+
+    /// Whether water fills the part of the cell the block leaves open
+    pub fn waterlogged(self) -> bool {
+        self.0 & Self::WATERLOGGED_BIT != 0
+    }
+
+    pub fn with_waterlogged(self, waterlogged: bool) -> Self {
+        if waterlogged {
+            Self(self.0 | Self::WATERLOGGED_BIT)
+        } else {
+            Self(self.0 & !Self::WATERLOGGED_BIT)
+        }
+    }
+
+    /// How many blocks a fluid has flowed from its source, with 0 for the source itself
+    pub fn fluid_level(self) -> u8 {
+        self.0 & Self::FLUID_LEVEL_MASK
+    }
+
+    pub fn with_fluid_level(self, level: u8) -> Self {
+        let level = level.min(Self::MAX_FLUID_LEVEL);
+        Self((self.0 & !Self::FLUID_LEVEL_MASK) | level)
+    }
+
+    /// Whether a fluid is pouring down from the block above
+    pub fn is_falling(self) -> bool {
+        self.0 & Self::FALLING_BIT != 0
+    }
+
+    pub fn with_falling(self, falling: bool) -> Self {
+        if falling {
+            Self(self.0 | Self::FALLING_BIT)
+        } else {
+            Self(self.0 & !Self::FALLING_BIT)
+        }
+    }
+
+    /// Whether a fluid is a source, which stays put instead of being fed by its neighbours
+    pub fn is_fluid_source(self) -> bool {
+        self.fluid_level() == 0 && !self.is_falling()
+    }
+}
+
+/// An axis-aligned box inside a block's cell, from (0, 0, 0) to (1, 1, 1) for a full cube
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShapeBox {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl ShapeBox {
+    pub const FULL: ShapeBox = ShapeBox::new(Vec3::ZERO, Vec3::ONE);
+
+    pub const fn new(min: Vec3, max: Vec3) -> Self {
+        Self { min, max }
+    }
+
+    /// The box mirrored top to bottom within its cell
+    fn flipped(self) -> Self {
+        Self::new(
+            Vec3::new(self.min.x, 1.0 - self.max.y, self.min.z),
+            Vec3::new(self.max.x, 1.0 - self.min.y, self.max.z),
+        )
+    }
+}
+
+/// Shape of a block, and which of its state bits it uses
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum BlockShape {
+    /// A full cube
+    #[default]
+    Cube,
+    /// A full cube whose top and bottom textures turn to face along its axis, like a log
+    Pillar,
+    /// The top or bottom half of a cube
+    Slab,
+    /// A slab with a step on the side it faces
+    Stairs,
+    /// A post that reaches out to neighbouring fences and full blocks
+    Fence,
+}
+
+impl BlockShape {
+    /// Whether the shape fills its whole cell
+    pub fn is_full_cube(self) -> bool {
+        matches!(self, BlockShape::Cube | BlockShape::Pillar)
+    }
+
+    /// Boxes the shape is made of in a given state
+    /// `connects` tells fences whether to reach out towards each side.
+    pub fn boxes(self, state: BlockState, connects: impl Fn(Facing) -> bool) -> Vec<ShapeBox> {
+        let in_half = |shape_box: ShapeBox| match state.half() {
+            Half::Bottom => shape_box,
+            Half::Top => shape_box.flipped(),
+        };
+        match self {
+            BlockShape::Cube | BlockShape::Pillar => vec![ShapeBox::FULL],
+            BlockShape::Slab => vec![in_half(ShapeBox::new(Vec3::ZERO, Vec3::new(1.0, 0.5, 1.0)))],
+            BlockShape::Stairs => {
+                // The step is the upper quarter of the cell on the side the stairs face
+                let (min_xz, max_xz) = match state.facing() {
+                    Facing::North => (Vec2::new(0.0, 0.0), Vec2::new(1.0, 0.5)),
+                    Facing::East => (Vec2::new(0.5, 0.0), Vec2::new(1.0, 1.0)),
+                    Facing::South => (Vec2::new(0.0, 0.5), Vec2::new(1.0, 1.0)),
+                    Facing::West => (Vec2::new(0.0, 0.0), Vec2::new(0.5, 1.0)),
+                };
+                vec![
+                    in_half(ShapeBox::new(Vec3::ZERO, Vec3::new(1.0, 0.5, 1.0))),
+                    in_half(ShapeBox::new(
+                        Vec3::new(min_xz.x, 0.5, min_xz.y),
+                        Vec3::new(max_xz.x, 1.0, max_xz.y),
+                    )),
+                ]
+            }
+            BlockShape::Fence => {
+                const POST: (f32, f32) = (0.375, 0.625);
+                const RAIL: (f32, f32) = (0.4375, 0.5625);
+                let mut boxes = vec![ShapeBox::new(
+                    Vec3::new(POST.0, 0.0, POST.0),
+                    Vec3::new(POST.1, 1.0, POST.1),
+                )];
+                for facing in Facing::ALL.into_iter().filter(|&facing| connects(facing)) {
+                    let (min_x, max_x, min_z, max_z) = match facing {
+                        Facing::North => (RAIL.0, RAIL.1, 0.0, POST.0),
+                        Facing::East => (POST.1, 1.0, RAIL.0, RAIL.1),
+                        Facing::South => (RAIL.0, RAIL.1, POST.1, 1.0),
+                        Facing::West => (0.0, POST.0, RAIL.0, RAIL.1),
+                    };
+                    boxes.push(ShapeBox::new(
+                        Vec3::new(min_x, 0.375, min_z),
+                        Vec3::new(max_x, 0.9375, max_z),
+                    ));
+                }
+                boxes
+            }
+        }
+    }
+
+    /// State of a block of this shape placed against a face
+    /// `face_normal` points out of the face that was clicked, `hit_height` is how far up its cell
+    /// the click landed, from 0 to 1, and `look` is where the player is looking.
+    pub fn placement_state(self, face_normal: IVec3, hit_height: f32, look: Vec3) -> BlockState {
+        // Clicking the underside of a block or the upper half of a side puts the block up top
+        let half = if face_normal.y < 0 || (face_normal.y == 0 && hit_height > 0.5) {
+            Half::Top
+        } else {
+            Half::Bottom
+        };
+        let state = BlockState::default();
+        match self {
+            BlockShape::Cube | BlockShape::Fence => state,
+            BlockShape::Pillar => state.with_axis(if face_normal.x != 0 {
+                Axis::X
+            } else if face_normal.z != 0 {
+                Axis::Z
+            } else {
+                Axis::Y
+            }),
+            BlockShape::Slab => state.with_half(half),
+            // Stairs face away from the player, so they climb them walking forwards
+            BlockShape::Stairs => state
+                .with_facing(Facing::from_direction(look))
+                .with_half(half),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_state_bits_round_trip() {
+        let state = BlockState::default()
+            .with_axis(Axis::Z)
+            .with_facing(Facing::West)
+            .with_half(Half::Top)
+            .with_waterlogged(true);
+        assert_eq!(state.axis(), Axis::Z);
+        assert_eq!(state.facing(), Facing::West);
+        assert_eq!(state.half(), Half::Top);
+        assert!(state.waterlogged());
+
+        let state = state.with_facing(Facing::South).with_waterlogged(false);
+        assert_eq!(state.facing(), Facing::South);
+        assert_eq!(state.axis(), Axis::Z);
+        assert!(!state.waterlogged());
+        assert_eq!(BlockState::default().axis(), Axis::Y);
+    }
+
+    #[test]
+    fn test_placement_orients_shapes_from_the_clicked_face() {
+        let look_north = Vec3::new(0.1, -0.3, -1.0);
+
+        // Logs lie along the face they were placed against
+        let side = BlockShape::Pillar.placement_state(IVec3::X, 0.5, look_north);
+        assert_eq!(side.axis(), Axis::X);
+        let top = BlockShape::Pillar.placement_state(IVec3::Y, 1.0, look_north);
+        assert_eq!(top.axis(), Axis::Y);
+
+        // Slabs take the half of the cell that was clicked
+        let low = BlockShape::Slab.placement_state(IVec3::Z, 0.3, look_north);
+        let high = BlockShape::Slab.placement_state(IVec3::Z, 0.8, look_north);
+        let under = BlockShape::Slab.placement_state(IVec3::NEG_Y, 0.0, look_north);
+        assert_eq!(low.half(), Half::Bottom);
+        assert_eq!(high.half(), Half::Top);
+        assert_eq!(under.half(), Half::Top);
+        let slab = BlockShape::Slab.boxes(high, |_| false);
+        assert_eq!(
+            slab,
+            vec![ShapeBox::new(Vec3::new(0.0, 0.5, 0.0), Vec3::ONE)]
+        );
+
+        // Stairs face the way the player looks, with the step on that side
+        let stairs = BlockShape::Stairs.placement_state(IVec3::Y, 1.0, look_north);
+        assert_eq!(stairs.facing(), Facing::North);
+        let boxes = BlockShape::Stairs.boxes(stairs, |_| false);
+        assert_eq!(boxes[1].min, Vec3::new(0.0, 0.5, 0.0));
+        assert_eq!(boxes[1].max, Vec3::new(1.0, 1.0, 0.5));
+
+        // Fences grow a rail for each side they connect to
+        let fence = BlockShape::Fence.boxes(BlockState::default(), |facing| facing == Facing::East);
+        assert_eq!(fence.len(), 2);
+        assert_eq!(fence[1].max.x, 1.0);
+    }
+}
@@ -0,0 +1,346 @@
+// Block updates for Bevy Craft
+// This module runs the ticks that let placed blocks react to the blocks around them
+//
+// Whenever a block changes, by the player or by another update, it's recorded in the
+// `BlockUpdates` of `ChunkManager`. On the next tick each block touching it that reacts to its
+// neighbours is scheduled: fluids after their own `tick_delay`, and blocks that fall without
+// support a tick later. When an update is due the block looks at its neighbours again, flowing on
+// or falling away, and whatever it changes is recorded in turn, so updates ripple outwards through
+// the world tick by tick and across chunk borders.
+//
+// An update that reaches into a chunk that isn't loaded waits for that chunk and runs again when
+// it loads, so flows stopped at the edge of the loaded world carry on later. Updates only live in
+// memory; flowing fluid read back from the world save is scheduled again as its chunk loads.
+
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+use crate::block::BlockType;
+use crate::block_state::BlockState;
+use crate::chunk::{BlockUpdates, Chunk, ChunkManager, ChunkPosition, CHUNK_SIZE};
+use crate::chunk_section::SECTION_SIZE;
+use crate::fluid::update_fluid;
+use crate::gravity::{spawn_falling_block, update_gravity_block, FallingBlockAssets};
+
+/// Seconds between two block update ticks
+const TICK_SECONDS: f32 = 1.0 / 20.0;
+
+/// Most ticks run in one frame, so a slow frame doesn't hold the game up catching up
+const MAX_TICKS_PER_FRAME: u32 = 4;
+
+/// Most block updates run in one tick; the rest wait for the next
+const MAX_UPDATES_PER_TICK: usize = 4096;
+
+/// Ticks an unsupported block waits before it falls
+const GRAVITY_TICK_DELAY: u32 = 2;
+
+/// Offsets of the six blocks touching a block
+const NEIGHBOUR_OFFSETS: [IVec3; 6] = [
+    IVec3::X,
+    IVec3::NEG_X,
+    IVec3::Y,
+    IVec3::NEG_Y,
+    IVec3::Z,
+    IVec3::NEG_Z,
+];
+
+/// Blocks that block updates read and change
+pub trait BlockWorld {
+    /// Block and state at a world position, with empty space as air; None where the world isn't
+    /// loaded or is out of the world's height, which updates leave alone
+    fn block(&self, position: IVec3) -> Option<(BlockType, BlockState)>;
+
+    /// Whether a chunk is loaded and has its blocks
+    fn is_chunk_loaded(&self, chunk_position: ChunkPosition) -> bool;
+
+    fn set_block(&mut self, position: IVec3, block_type: BlockType, state: BlockState);
+}
+
+/// The chunks `ChunkManager` has loaded, as a world for block updates
+pub struct LoadedChunks<'a, 'w, 's, 'c> {
+    pub loaded_chunks: &'a HashMap<ChunkPosition, Entity>,
+    pub chunks: &'a mut Query<'w, 's, &'c mut Chunk>,
+}
+
+impl LoadedChunks<'_, '_, '_, '_> {
+    fn entity(&self, position: IVec3) -> Option<Entity> {
+        self.loaded_chunks
+            .get(&ChunkPosition::from_block_position(position))
+            .copied()
+    }
+}
+
+impl BlockWorld for LoadedChunks<'_, '_, '_, '_> {
+    fn block(&self, position: IVec3) -> Option<(BlockType, BlockState)> {
+        let chunk = self.chunks.get(self.entity(position)?).ok()?;
+        if !chunk.is_generated || !chunk.contains(position) {
+            return None;
+        }
+        Some((
+            chunk.get_block_world(position).unwrap_or(BlockType::Air),
+            chunk.get_block_state_world(position),
+        ))
+    }
+
+    fn is_chunk_loaded(&self, chunk_position: ChunkPosition) -> bool {
+        self.loaded_chunks
+            .get(&chunk_position)
+            .and_then(|&entity| self.chunks.get(entity).ok())
+            .is_some_and(|chunk| chunk.is_generated)
+    }
+
+    fn set_block(&mut self, position: IVec3, block_type: BlockType, state: BlockState) {
+        let Some(entity) = self.entity(position) else {
+            return;
+        };
+        if let Ok(mut chunk) = self.chunks.get_mut(entity) {
+            chunk.set_block_with_state_world(position, block_type, state);
+        }
+    }
+}
+
+/// System that runs block update ticks at a steady rate
+/// Blocks that lose their support on a tick are spawned as falling blocks.
+pub fn block_update_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut elapsed: Local<f32>,
+    mut chunk_manager: ResMut<ChunkManager>,
+    mut chunks: Query<&mut Chunk>,
+    falling_block_assets: Res<FallingBlockAssets>,
+) {
+    let chunk_manager = &mut *chunk_manager;
+    let mut world = LoadedChunks {
+        loaded_chunks: &chunk_manager.loaded_chunks,
+        chunks: &mut chunks,
+    };
+
+    *elapsed += time.delta_secs();
+    let mut ticks = 0;
+    while *elapsed >= TICK_SECONDS && ticks < MAX_TICKS_PER_FRAME {
+        *elapsed -= TICK_SECONDS;
+        ticks += 1;
+        for (position, block_type, state) in
+            run_block_tick(&mut world, &mut chunk_manager.block_updates)
+        {
+            spawn_falling_block(
+                &mut commands,
+                &falling_block_assets,
+                position,
+                block_type,
+                state,
+            );
+        }
+    }
+    // Time the game couldn't catch up on is let go rather than piling up
+    *elapsed = elapsed.min(TICK_SECONDS);
+}
+
+/// Run one tick: schedule the blocks next to blocks that changed, then update the ones due
+/// Returns the blocks that lost their support and were taken out of the world to fall.
+pub fn run_block_tick(
+    world: &mut impl BlockWorld,
+    updates: &mut BlockUpdates,
+) -> Vec<(IVec3, BlockType, BlockState)> {
+    updates.resume_waiting(|chunk_position| world.is_chunk_loaded(chunk_position));
+
+    for changed in updates.take_changed() {
+        for position in std::iter::once(changed).chain(NEIGHBOUR_OFFSETS.map(|by| changed + by)) {
+            let Some((block_type, _)) = world.block(position) else {
+                wait_for_chunk_of(world, updates, position, changed);
+                continue;
+            };
+            if let Some(fluid) = block_type.fluid() {
+                updates.schedule(position, fluid.tick_delay);
+            } else if block_type.has_gravity() {
+                updates.schedule(position, GRAVITY_TICK_DELAY);
+            }
+        }
+    }
+
+    let due = updates.advance();
+    let (now, later) = due.split_at(due.len().min(MAX_UPDATES_PER_TICK));
+    for &position in later {
+        updates.schedule(position, 1);
+    }
+    let mut falling = Vec::new();
+    for &position in now {
+        if !wait_for_chunk_of(world, updates, position, position) {
+            continue;
+        }
+        for changed in update_fluid(world, position) {
+            updates.block_changed(changed);
+        }
+        if let Some((block_type, state)) = update_gravity_block(world, position) {
+            updates.block_changed(position);
+            falling.push((position, block_type, state));
+        }
+        // Whatever couldn't be seen past the edge of the loaded world is looked at again later
+        for by in NEIGHBOUR_OFFSETS {
+            wait_for_chunk_of(world, updates, position + by, position);
+        }
+    }
+    falling
+}
+
+/// Make a block's update wait if a position it looks at is in a chunk that isn't loaded
+/// Returns whether the chunk is loaded.
+fn wait_for_chunk_of(
+    world: &impl BlockWorld,
+    updates: &mut BlockUpdates,
+    position: IVec3,
+    waiting: IVec3,
+) -> bool {
+    let chunk_position = ChunkPosition::from_block_position(position);
+    let loaded = world.is_chunk_loaded(chunk_position);
+    if !loaded {
+        updates.wait_for_chunk(chunk_position, waiting);
+    }
+    loaded
+}
+
+/// Schedule the flowing fluid in a chunk read back from the world save
+/// Updates aren't saved, so flows that were still running when the chunk was saved carry on from
+/// here. Sources stay put by themselves and are left alone.
+pub fn schedule_flowing_fluids(chunk: &Chunk, updates: &mut BlockUpdates) {
+    let is_flowing = |block_type: BlockType, state: BlockState| {
+        block_type.fluid().is_some() && !state.is_fluid_source()
+    };
+    let min_position = chunk.position.min_block_position();
+    for section_index in 0..chunk.data.section_count() {
+        if !chunk.data.section_may_contain(section_index, is_flowing) {
+            continue;
+        }
+        let min_y = chunk.data.section_min_y(section_index);
+        for y in min_y..min_y + SECTION_SIZE as i32 {
+            for x in 0..CHUNK_SIZE {
+                for z in 0..CHUNK_SIZE {
+                    let Some(block_type) = chunk.data.get_block(x, y, z) else {
+                        continue;
+                    };
+                    let state = chunk.data.get_block_state(x, y, z);
+                    if let Some(fluid) = block_type.fluid()
+                        && !state.is_fluid_source()
+                    {
+                        let position = min_position + IVec3::new(x as i32, y, z as i32);
+                        updates.schedule(position, fluid.tick_delay);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// A small patch of world on a stone floor at y = 0, held in a map
+/// It spans the four chunks around the origin, any of which can be left unloaded.
+#[cfg(test)]
+#[derive(Default)]
+pub struct TestWorld {
+    pub blocks: HashMap<IVec3, (BlockType, BlockState)>,
+    pub unloaded: std::collections::HashSet<ChunkPosition>,
+}
+
+#[cfg(test)]
+impl BlockWorld for TestWorld {
+    fn block(&self, position: IVec3) -> Option<(BlockType, BlockState)> {
+        if position.x.abs() > 12 || position.z.abs() > 12 || !(0..8).contains(&position.y) {
+            return None;
+        }
+        if !self.is_chunk_loaded(ChunkPosition::from_block_position(position)) {
+            return None;
+        }
+        if position.y == 0 {
+            return Some((BlockType::Stone, BlockState::default()));
+        }
+        Some(
+            self.blocks
+                .get(&position)
+                .copied()
+                .unwrap_or((BlockType::Air, BlockState::default())),
+        )
+    }
+
+    fn set_block(&mut self, position: IVec3, block_type: BlockType, state: BlockState) {
+        self.blocks.insert(position, (block_type, state));
+    }
+
+    fn is_chunk_loaded(&self, chunk_position: ChunkPosition) -> bool {
+        !self.unloaded.contains(&chunk_position)
+    }
+}
+
+#[cfg(test)]
+impl TestWorld {
+    /// Place a block and run updates for a while, returning the blocks that fell in order
+    pub fn place_and_settle(&mut self, position: IVec3, block_type: BlockType) -> Vec<IVec3> {
+        let mut updates = BlockUpdates::default();
+        self.set_block(position, block_type, BlockState::default());
+        updates.block_changed(position);
+        (0..2000)
+            .flat_map(|_| run_block_tick(self, &mut updates))
+            .map(|(position, _, _)| position)
+            .collect()
+    }
+
+    pub fn block_type(&self, position: IVec3) -> BlockType {
+        self.block(position).unwrap().0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::WorldHeight;
+
+    #[test]
+    fn test_flow_waits_at_an_unloaded_chunk_and_carries_on_when_it_loads() {
+        let mut world = TestWorld::default();
+        world.unloaded.insert(ChunkPosition::new(0, -1));
+        let mut updates = BlockUpdates::default();
+        let source = IVec3::new(-3, 1, -5);
+        world.set_block(source, BlockType::Water, BlockState::default());
+        updates.block_changed(source);
+        for _ in 0..500 {
+            run_block_tick(&mut world, &mut updates);
+        }
+        assert_eq!(world.block_type(source - IVec3::X), BlockType::Water);
+        assert!(world.blocks.keys().all(|position| position.x < 0));
+
+        // The flow picks up at the border once the chunk is there
+        world.unloaded.clear();
+        for _ in 0..500 {
+            run_block_tick(&mut world, &mut updates);
+        }
+        let water = BlockType::Water.fluid().unwrap();
+        for distance in 1..=water.spread as i32 {
+            let (block_type, state) = world.block(source + IVec3::X * distance).unwrap();
+            assert_eq!(block_type, BlockType::Water);
+            assert_eq!(state.fluid_level(), distance as u8);
+        }
+    }
+
+    #[test]
+    fn test_flowing_fluid_loaded_from_a_save_is_scheduled() {
+        let mut chunk = Chunk::new(ChunkPosition::new(1, -1), WorldHeight::new(0, 32));
+        let source = IVec3::new(20, 17, -10);
+        let flowing = IVec3::new(21, 17, -10);
+        let falling = IVec3::new(21, 3, -9);
+        chunk.set_block_with_state_world(source, BlockType::Water, BlockState::default());
+        chunk.set_block_with_state_world(
+            flowing,
+            BlockType::Water,
+            BlockState::default().with_fluid_level(1),
+        );
+        chunk.set_block_with_state_world(
+            falling,
+            BlockType::Lava,
+            BlockState::default().with_falling(true),
+        );
+
+        let mut updates = BlockUpdates::default();
+        schedule_flowing_fluids(&chunk, &mut updates);
+        let mut due: Vec<IVec3> = (0..100).flat_map(|_| updates.advance()).collect();
+        due.sort_by_key(|position| position.y);
+        assert_eq!(due, [falling, flowing]);
+    }
+}
@@ -0,0 +1,347 @@
+// Cave carving for Bevy Craft
+// This module hollows out generated terrain with worm caves, cavern chambers and ravines
+//
+// Worm caves and ravines are random walks that start in a chunk and wander into its
+// neighbours. Each walk is driven by an RNG seeded from the world seed and the chunk it starts
+// in, so every chunk can replay the walks of all nearby chunks and carve exactly the part that
+// falls inside it. Caverns come from 3D noise sampled in world coordinates. Either way the result
+// never depends on the order chunks are generated in.
+
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::f32::consts::PI;
+
+use crate::block::BlockType;
+use crate::chunk::{ChunkData, ChunkPosition, CHUNK_SIZE};
+use crate::noise::{derive_seed, generate_density_noise, NoiseSettings};
+use crate::world_gen::WorldGenSettings;
+
+/// How many chunks away a cave or ravine may start and still reach the current chunk
+const CARVER_RANGE_CHUNKS: i32 = 8;
+
+/// Seed channels for the cave carvers
+const WORM_CAVE_SEED_CHANNEL: u32 = 10;
+const RAVINE_SEED_CHANNEL: u32 = 11;
+const CAVERN_SEED_CHANNEL: u32 = 12;
+
+/// Caverns stay this many blocks below the surface so they don't swallow hillsides
+const CAVERN_SURFACE_MARGIN: i32 = 10;
+
+/// Shape of a single random-walk tunnel
+struct Tunnel {
+    start: Vec3,
+    yaw: f32,
+    pitch: f32,
+    length: usize,
+    /// Horizontal radius at the widest point
+    radius: f32,
+    /// Vertical radius relative to the horizontal one; ravines are much taller than wide
+    vertical_scale: f32,
+    /// How quickly the pitch settles back to horizontal each step
+    pitch_damping: f32,
+}
+
+/// Carve caves, caverns and ravines into a freshly generated chunk
+/// `surface_heights` holds the heightmap height of each column, indexed `[x][z]`.
+pub fn carve_caves(
+    data: &mut ChunkData,
+    chunk_pos: ChunkPosition,
+    settings: &WorldGenSettings,
+    surface_heights: &[[i32; CHUNK_SIZE]; CHUNK_SIZE],
+) {
+    carve_caverns(data, chunk_pos, settings, surface_heights);
+
+    for source_x in (chunk_pos.x - CARVER_RANGE_CHUNKS)..=(chunk_pos.x + CARVER_RANGE_CHUNKS) {
+        for source_z in (chunk_pos.z - CARVER_RANGE_CHUNKS)..=(chunk_pos.z + CARVER_RANGE_CHUNKS) {
+            let source = ChunkPosition::new(source_x, source_z);
+
+            let mut rng = chunk_rng(settings.seed, WORM_CAVE_SEED_CHANNEL, source);
+            for _ in 0..cave_count(&mut rng, settings.cave_frequency) {
+                let tunnel = random_worm_cave(&mut rng, source, data);
+                carve_tunnel(data, chunk_pos, &tunnel, &mut rng);
+            }
+
+            let mut rng = chunk_rng(settings.seed, RAVINE_SEED_CHANNEL, source);
+            if rng.gen_range(0.0..1.0) < settings.ravine_chance {
+                let tunnel = random_ravine(&mut rng, source, data);
+                carve_tunnel(data, chunk_pos, &tunnel, &mut rng);
+            }
+        }
+    }
+}
+
+/// Create the RNG for one generation feature in one source chunk
+/// Shared by every pass that must replay features started in neighbouring chunks.
+pub fn chunk_rng(seed: u32, channel: u32, source: ChunkPosition) -> StdRng {
+    let channel_seed = derive_seed(seed, channel) as u64;
+    let position = ((source.x as u32 as u64) << 32) | source.z as u32 as u64;
+    StdRng::seed_from_u64(channel_seed.wrapping_mul(0x9E3779B97F4A7C15) ^ position)
+}
+
+/// Number of worm caves starting in a chunk for an average frequency
+fn cave_count(rng: &mut StdRng, cave_frequency: f32) -> usize {
+    let whole = cave_frequency.max(0.0).floor();
+    let extra = rng.gen_range(0.0..1.0) < cave_frequency - whole;
+    whole as usize + extra as usize
+}
+
+/// Pick a random horizontal starting point inside a source chunk
+fn random_start(rng: &mut StdRng, source: ChunkPosition, y: f32) -> Vec3 {
+    let origin = source.min_block_position().as_vec3();
+    Vec3::new(
+        origin.x + rng.gen_range(0.0..CHUNK_SIZE as f32),
+        y,
+        origin.z + rng.gen_range(0.0..CHUNK_SIZE as f32),
+    )
+}
+
+/// Roll a winding worm cave starting in a source chunk
+fn random_worm_cave(rng: &mut StdRng, source: ChunkPosition, data: &ChunkData) -> Tunnel {
+    // Bias caves towards the deep rock, where most of the world's stone is
+    let min_y = (data.min_y() + 8) as f32;
+    let max_y = 80.0_f32.max(min_y + 1.0);
+    let depth: f32 = rng.gen_range(0.0..1.0);
+    let y = min_y + (max_y - min_y) * depth * depth;
+
+    Tunnel {
+        start: random_start(rng, source, y),
+        yaw: rng.gen_range(0.0..PI * 2.0),
+        pitch: rng.gen_range(-0.25..0.25),
+        length: rng.gen_range(60..140),
+        radius: rng.gen_range(1.5..3.5),
+        vertical_scale: 0.8,
+        pitch_damping: 0.7,
+    }
+}
+
+/// Roll a narrow, deep ravine starting in a source chunk
+fn random_ravine(rng: &mut StdRng, source: ChunkPosition, data: &ChunkData) -> Tunnel {
+    let min_y = (data.min_y() + 20) as f32;
+    let y = rng.gen_range(min_y..min_y.max(50.0) + 1.0);
+
+    Tunnel {
+        start: random_start(rng, source, y),
+        yaw: rng.gen_range(0.0..PI * 2.0),
+        pitch: rng.gen_range(-0.05..0.05),
+        length: rng.gen_range(80..160),
+        radius: rng.gen_range(1.5..3.0),
+        vertical_scale: 6.0,
+        pitch_damping: 0.9,
+    }
+}
+
+/// Walk a tunnel and carve the parts that fall inside the target chunk
+/// The walk itself is replayed in full so that every chunk sees the same path.
+fn carve_tunnel(data: &mut ChunkData, chunk_pos: ChunkPosition, tunnel: &Tunnel, rng: &mut StdRng) {
+    let chunk_min = chunk_pos.min_block_position().as_vec3();
+    let chunk_center = chunk_min + Vec3::new(CHUNK_SIZE as f32 * 0.5, 0.0, CHUNK_SIZE as f32 * 0.5);
+
+    let mut position = tunnel.start;
+    let mut yaw = tunnel.yaw;
+    let mut pitch = tunnel.pitch;
+    let mut yaw_change = 0.0;
+    let mut pitch_change = 0.0;
+
+    for step in 0..tunnel.length {
+        // Widest in the middle, tapering towards both ends
+        let progress = step as f32 / tunnel.length as f32;
+        let radius = 1.0 + tunnel.radius * (progress * PI).sin();
+
+        let horizontal = pitch.cos();
+        position += Vec3::new(yaw.cos() * horizontal, pitch.sin(), yaw.sin() * horizontal);
+
+        pitch *= tunnel.pitch_damping;
+        pitch += pitch_change * 0.1;
+        yaw += yaw_change * 0.1;
+        pitch_change = pitch_change * 0.9 + rng.gen_range(-1.0..1.0) * 2.0;
+        yaw_change = yaw_change * 0.75 + rng.gen_range(-1.0..1.0) * 4.0;
+
+        // The RNG must still be advanced for every step, so only the carving is skipped
+        let horizontal_distance =
+            Vec2::new(position.x - chunk_center.x, position.z - chunk_center.z).length();
+        if horizontal_distance > radius + CHUNK_SIZE as f32 {
+            continue;
+        }
+
+        carve_ellipsoid(
+            data,
+            chunk_min,
+            position,
+            radius,
+            radius * tunnel.vertical_scale,
+        );
+    }
+}
+
+/// Remove every carvable block inside an ellipsoid
+fn carve_ellipsoid(
+    data: &mut ChunkData,
+    chunk_min: Vec3,
+    center: Vec3,
+    horizontal_radius: f32,
+    vertical_radius: f32,
+) {
+    let local_center = center - chunk_min;
+    let min_x = ((local_center.x - horizontal_radius).floor() as i32).max(0);
+    let max_x = ((local_center.x + horizontal_radius).ceil() as i32).min(CHUNK_SIZE as i32 - 1);
+    let min_z = ((local_center.z - horizontal_radius).floor() as i32).max(0);
+    let max_z = ((local_center.z + horizontal_radius).ceil() as i32).min(CHUNK_SIZE as i32 - 1);
+    let min_y = ((center.y - vertical_radius).floor() as i32).max(data.min_y() + 1);
+    let max_y = ((center.y + vertical_radius).ceil() as i32).min(data.max_y() - 1);
+
+    for x in min_x..=max_x {
+        for z in min_z..=max_z {
+            for y in min_y..=max_y {
+                let offset = Vec3::new(
+                    (x as f32 + 0.5 - local_center.x) / horizontal_radius,
+                    (y as f32 + 0.5 - center.y) / vertical_radius,
+                    (z as f32 + 0.5 - local_center.z) / horizontal_radius,
+                );
+                if offset.length_squared() < 1.0 {
+                    carve_block(data, x as usize, y, z as usize);
+                }
+            }
+        }
+    }
+}
+
+/// Hollow out large cavern chambers using 3D noise
+fn carve_caverns(
+    data: &mut ChunkData,
+    chunk_pos: ChunkPosition,
+    settings: &WorldGenSettings,
+    surface_heights: &[[i32; CHUNK_SIZE]; CHUNK_SIZE],
+) {
+    if settings.cavern_density <= 0.0 {
+        return;
+    }
+
+    let cavern_settings = NoiseSettings {
+        scale: 0.03,
+        octaves: 2,
+        persistence: 0.5,
+        lacunarity: 2.0,
+        seed: derive_seed(settings.seed, CAVERN_SEED_CHANNEL),
+        ..NoiseSettings::default()
+    };
+    // Higher density lowers the noise threshold, hollowing out more of the rock
+    let threshold = 0.5 - settings.cavern_density;
+    let chunk_min = chunk_pos.min_block_position();
+
+    for (local_x, column_heights) in surface_heights.iter().enumerate() {
+        for (local_z, surface_height) in column_heights.iter().enumerate() {
+            let world_x = chunk_min.x + local_x as i32;
+            let world_z = chunk_min.z + local_z as i32;
+            let top = (surface_height - CAVERN_SURFACE_MARGIN).min(data.max_y() - 1);
+
+            for y in (data.min_y() + 4)..top {
+                // Squash vertically so chambers are wider than they are tall
+                let noise = generate_density_noise(
+                    world_x as f32,
+                    y as f32 * 1.6,
+                    world_z as f32,
+                    &cavern_settings,
+                );
+                if noise > threshold {
+                    carve_block(data, local_x, y, local_z);
+                }
+            }
+        }
+    }
+}
+
+/// Remove a block unless it is one that caves must never cut through
+/// Blocks holding up water stay too, so sea and lake floors don't open into caves.
+fn carve_block(data: &mut ChunkData, local_x: usize, y: i32, local_z: usize) {
+    if data.get_block(local_x, y + 1, local_z) == Some(BlockType::Water) {
+        return;
+    }
+    match data.get_block(local_x, y, local_z) {
+        None | Some(BlockType::Bedrock) | Some(BlockType::Water) | Some(BlockType::Lava) => {}
+        Some(_) => data.clear_block(local_x, y, local_z),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::WorldHeight;
+
+    /// Build a chunk of solid stone so every carved block is visible
+    fn solid_chunk() -> ChunkData {
+        let mut data = ChunkData::new(WorldHeight::default());
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                for y in data.min_y()..64 {
+                    data.set_block(x, y, z, BlockType::Stone);
+                }
+            }
+        }
+        data
+    }
+
+    fn count_carved(data: &ChunkData) -> usize {
+        let mut carved = 0;
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                for y in data.min_y()..64 {
+                    if data.get_block(x, y, z).is_none() {
+                        carved += 1;
+                    }
+                }
+            }
+        }
+        carved
+    }
+
+    #[test]
+    fn test_cave_carving_is_deterministic() {
+        let settings = WorldGenSettings {
+            seed: 1234,
+            ..default()
+        };
+        let surface_heights = [[64; CHUNK_SIZE]; CHUNK_SIZE];
+
+        let mut carved_chunks = 0;
+        for chunk_x in 0..4 {
+            let chunk_pos = ChunkPosition::new(chunk_x, -2);
+            let mut first = solid_chunk();
+            let mut second = solid_chunk();
+            carve_caves(&mut first, chunk_pos, &settings, &surface_heights);
+            carve_caves(&mut second, chunk_pos, &settings, &surface_heights);
+
+            // Carving depends only on the seed and world position
+            for x in 0..CHUNK_SIZE {
+                for z in 0..CHUNK_SIZE {
+                    for y in first.min_y()..64 {
+                        assert_eq!(first.get_block(x, y, z), second.get_block(x, y, z));
+                    }
+                }
+            }
+            if count_carved(&first) > 0 {
+                carved_chunks += 1;
+            }
+        }
+        assert!(carved_chunks > 0, "expected some caves in four chunks");
+    }
+
+    #[test]
+    fn test_carving_can_be_disabled() {
+        let settings = WorldGenSettings {
+            seed: 1234,
+            cave_frequency: 0.0,
+            ravine_chance: 0.0,
+            cavern_density: 0.0,
+            ..default()
+        };
+        let mut data = solid_chunk();
+        carve_caves(
+            &mut data,
+            ChunkPosition::new(3, 7),
+            &settings,
+            &[[64; CHUNK_SIZE]; CHUNK_SIZE],
+        );
+        assert_eq!(count_carved(&data), 0);
+    }
+}
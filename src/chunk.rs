@@ -2,9 +2,13 @@
 // This module handles world chunking for efficient rendering and world generation
 
 use bevy::prelude::*;
-use std::collections::{HashMap, VecDeque};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 
+use crate::biome::BiomeId;
 use crate::block::BlockType;
+use crate::block_state::BlockState;
+use crate::chunk_section::{ChunkSection, SECTION_AREA, SECTION_SIZE};
+use crate::lighting::ChunkLight;
 
 /// Chunk priority for loading/unloading and processing
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
@@ -25,14 +29,94 @@ impl Default for ChunkPriority {
     }
 }
 
+/// Level of detail a chunk's mesh is built at
+/// Coarser levels mesh a grid of 2x2x2 or 4x4x4 block cells instead of single blocks, so far
+/// chunks cost a fraction of the triangles of a full-resolution mesh.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ChunkLod {
+    /// Every block is meshed
+    #[default]
+    Full,
+    /// Cells of 2x2x2 blocks
+    Half,
+    /// Cells of 4x4x4 blocks
+    Quarter,
+}
+
+impl ChunkLod {
+    /// Width of a cell in blocks
+    pub fn step(self) -> usize {
+        match self {
+            ChunkLod::Full => 1,
+            ChunkLod::Half => 2,
+            ChunkLod::Quarter => 4,
+        }
+    }
+
+    /// Level of detail for a chunk of the given priority
+    /// Far chunks run from half the render distance out to its edge; the inner half of that band
+    /// is meshed in half-size cells and the outer half in quarter-size ones.
+    pub fn for_priority(priority: ChunkPriority, distance: i32, render_distance: i32) -> Self {
+        let near_distance = render_distance / 2;
+        let far_band = render_distance - near_distance;
+        match priority {
+            ChunkPriority::Visible | ChunkPriority::Near => ChunkLod::Full,
+            ChunkPriority::Far if distance - near_distance <= (far_band + 1) / 2 => ChunkLod::Half,
+            ChunkPriority::Far | ChunkPriority::Unload => ChunkLod::Quarter,
+        }
+    }
+}
+
 /// Constants for chunk system
-pub const CHUNK_SIZE: usize = 16; // 16x16x16 chunks
-pub const CHUNK_HEIGHT: usize = 128; // Maximum height for chunks
+pub const CHUNK_SIZE: usize = 16; // 16x16 columns of 16x16x16 sections
 pub const CHUNK_AREA: usize = CHUNK_SIZE * CHUNK_SIZE;
-pub const CHUNK_VOLUME: usize = CHUNK_AREA * CHUNK_HEIGHT;
+
+/// Chunks loaded in each direction around the player unless the command line says otherwise
+/// Far chunks are meshed with less detail, which is what makes this many affordable.
+pub const DEFAULT_RENDER_DISTANCE: i32 = 6;
+
+/// Vertical extent of the world in blocks
+/// Both limits are kept on section boundaries; `max_y` is exclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct WorldHeight {
+    pub min_y: i32,
+    pub max_y: i32,
+}
+
+impl Default for WorldHeight {
+    fn default() -> Self {
+        Self {
+            min_y: -64,
+            max_y: 256,
+        }
+    }
+}
+
+impl WorldHeight {
+    /// Create a world height, widening the limits to whole sections
+    pub fn new(min_y: i32, max_y: i32) -> Self {
+        let section_size = SECTION_SIZE as i32;
+        let min_y = min_y.div_euclid(section_size) * section_size;
+        let max_y = (max_y + section_size - 1).div_euclid(section_size) * section_size;
+        Self {
+            min_y,
+            max_y: max_y.max(min_y + section_size),
+        }
+    }
+
+    /// Total height of the world in blocks
+    pub fn height(&self) -> usize {
+        (self.max_y - self.min_y) as usize
+    }
+
+    /// Number of sections stacked in each chunk column
+    pub fn section_count(&self) -> usize {
+        self.height() / SECTION_SIZE
+    }
+}
 
 /// Chunk position in world coordinates
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub struct ChunkPosition {
     pub x: i32,
     pub z: i32,
@@ -51,12 +135,12 @@ impl ChunkPosition {
     }
 
     /// Get the minimum block position for this chunk
+    /// The y component is always 0: chunk entities sit at y = 0 and blocks keep their world y.
     pub fn min_block_position(&self) -> IVec3 {
         IVec3::new(self.x * CHUNK_SIZE as i32, 0, self.z * CHUNK_SIZE as i32)
     }
 
     /// Get neighboring chunk positions (4 directions: N, S, E, W)
-    #[allow(dead_code)]
     pub fn neighbors(&self) -> [ChunkPosition; 4] {
         [
             ChunkPosition::new(self.x, self.z - 1), // North
@@ -82,64 +166,178 @@ impl ChunkPosition {
 }
 
 /// Chunk data structure containing block information
-#[derive(Debug, Default, Clone)]
+/// Blocks are stored in palette-compressed 16x16x16 sections stacked upwards from `min_y`.
+/// All y coordinates are world coordinates and may be negative.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct ChunkData {
-    pub blocks: Vec<Option<BlockType>>, // Using Option to represent air blocks
+    min_y: i32,
+    sections: Vec<ChunkSection>,
+}
+
+impl Default for ChunkData {
+    fn default() -> Self {
+        Self::new(WorldHeight::default())
+    }
 }
 
 impl ChunkData {
-    pub fn new() -> Self {
+    pub fn new(world_height: WorldHeight) -> Self {
         Self {
-            blocks: vec![None; CHUNK_VOLUME],
+            min_y: world_height.min_y,
+            sections: vec![ChunkSection::default(); world_height.section_count()],
         }
     }
 
-    /// Get block at local chunk coordinates
-    pub fn get_block(&self, local_x: usize, y: usize, local_z: usize) -> Option<BlockType> {
+    /// Check that chunk data read back from disk spans a world height and every section is
+    /// consistent, so reading any block is safe
+    pub fn is_valid_for(&self, world_height: WorldHeight) -> bool {
+        self.min_y == world_height.min_y
+            && self.sections.len() == world_height.section_count()
+            && self.sections.iter().all(ChunkSection::is_valid)
+    }
+
+    /// Lowest y coordinate stored in this chunk
+    pub fn min_y(&self) -> i32 {
+        self.min_y
+    }
+
+    /// One past the highest y coordinate stored in this chunk
+    pub fn max_y(&self) -> i32 {
+        self.min_y + (self.sections.len() * SECTION_SIZE) as i32
+    }
+
+    /// Number of vertical sections in this chunk
+    pub fn section_count(&self) -> usize {
+        self.sections.len()
+    }
+
+    /// Lowest y coordinate of a section
+    pub fn section_min_y(&self, section_index: usize) -> i32 {
+        self.min_y + (section_index * SECTION_SIZE) as i32
+    }
+
+    /// Check whether a section contains no blocks at all
+    pub fn is_section_empty(&self, section_index: usize) -> bool {
+        self.sections
+            .get(section_index)
+            .is_none_or(|section| section.is_empty())
+    }
+
+    /// Check whether a section may hold a block that passes a test, going by its palette
+    pub fn section_may_contain(
+        &self,
+        section_index: usize,
+        matches: impl Fn(BlockType, BlockState) -> bool,
+    ) -> bool {
+        self.sections
+            .get(section_index)
+            .is_some_and(|section| section.palette_contains(matches))
+    }
+
+    /// Check whether the section containing a y coordinate has no blocks
+    pub fn is_empty_at(&self, y: i32) -> bool {
+        y < self.min_y || self.is_section_empty(((y - self.min_y) as usize) / SECTION_SIZE)
+    }
+
+    /// Get block at local chunk coordinates and world y
+    pub fn get_block(&self, local_x: usize, y: i32, local_z: usize) -> Option<BlockType> {
         // Bounds checking to prevent overflow
-        if local_x >= CHUNK_SIZE || local_z >= CHUNK_SIZE || y >= CHUNK_HEIGHT {
+        if local_x >= CHUNK_SIZE || local_z >= CHUNK_SIZE || y < self.min_y || y >= self.max_y() {
             return None;
         }
-        let index = self.local_to_index(local_x, y, local_z);
-        self.blocks[index]
+        let (section_index, index) = self.local_to_index(local_x, y, local_z);
+        self.sections[section_index].get(index)
+    }
+
+    /// Get the state of the block at local chunk coordinates and world y
+    pub fn get_block_state(&self, local_x: usize, y: i32, local_z: usize) -> BlockState {
+        if local_x >= CHUNK_SIZE || local_z >= CHUNK_SIZE || y < self.min_y || y >= self.max_y() {
+            return BlockState::default();
+        }
+        let (section_index, index) = self.local_to_index(local_x, y, local_z);
+        self.sections[section_index].get_state(index)
     }
 
-    /// Set block at local chunk coordinates
-    pub fn set_block(&mut self, local_x: usize, y: usize, local_z: usize, block_type: BlockType) {
+    /// Set block at local chunk coordinates and world y
+    pub fn set_block(&mut self, local_x: usize, y: i32, local_z: usize, block_type: BlockType) {
+        self.set_block_with_state(local_x, y, local_z, block_type, BlockState::default());
+    }
+
+    /// Set block and its state at local chunk coordinates and world y
+    pub fn set_block_with_state(
+        &mut self,
+        local_x: usize,
+        y: i32,
+        local_z: usize,
+        block_type: BlockType,
+        state: BlockState,
+    ) {
         // Bounds checking to prevent overflow
-        if local_x >= CHUNK_SIZE || local_z >= CHUNK_SIZE || y >= CHUNK_HEIGHT {
+        if local_x >= CHUNK_SIZE || local_z >= CHUNK_SIZE || y < self.min_y || y >= self.max_y() {
             return;
         }
-        let index = self.local_to_index(local_x, y, local_z);
-        self.blocks[index] = Some(block_type);
+        let (section_index, index) = self.local_to_index(local_x, y, local_z);
+        self.sections[section_index].set_with_state(index, Some(block_type), state);
     }
 
-    /// Convert local coordinates to array index
-    fn local_to_index(&self, local_x: usize, y: usize, local_z: usize) -> usize {
-        y * CHUNK_AREA + local_z * CHUNK_SIZE + local_x
+    /// Remove the block at local chunk coordinates and world y, leaving empty space
+    pub fn clear_block(&mut self, local_x: usize, y: i32, local_z: usize) {
+        if local_x >= CHUNK_SIZE || local_z >= CHUNK_SIZE || y < self.min_y || y >= self.max_y() {
+            return;
+        }
+        let (section_index, index) = self.local_to_index(local_x, y, local_z);
+        self.sections[section_index].set(index, None);
+    }
+
+    /// Shrink every section's palette to the blocks it still contains
+    pub fn compact(&mut self) {
+        for section in &mut self.sections {
+            section.compact();
+        }
     }
 
-    /// Convert array index to local coordinates
+    /// Approximate memory used by the block storage in bytes
+    pub fn memory_usage(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self
+                .sections
+                .iter()
+                .map(|section| section.memory_usage())
+                .sum::<usize>()
+    }
+
+    /// Convert local coordinates to a section number and the index inside that section
+    fn local_to_index(&self, local_x: usize, y: i32, local_z: usize) -> (usize, usize) {
+        let offset_y = (y - self.min_y) as usize;
+        let section_y = offset_y % SECTION_SIZE;
+        (
+            offset_y / SECTION_SIZE,
+            section_y * SECTION_AREA + local_z * SECTION_SIZE + local_x,
+        )
+    }
+
+    /// Convert a section number and section index to local coordinates
     #[allow(dead_code)]
-    fn index_to_local(&self, index: usize) -> (usize, usize, usize) {
-        let y = index / CHUNK_AREA;
-        let remainder = index % CHUNK_AREA;
-        let local_z = remainder / CHUNK_SIZE;
-        let local_x = remainder % CHUNK_SIZE;
+    fn index_to_local(&self, section_index: usize, index: usize) -> (usize, i32, usize) {
+        let y = self.section_min_y(section_index) + (index / SECTION_AREA) as i32;
+        let remainder = index % SECTION_AREA;
+        let local_z = remainder / SECTION_SIZE;
+        let local_x = remainder % SECTION_SIZE;
         (local_x, y, local_z)
     }
 }
 
 /// Biome data for a single position in the chunk
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Copy, Default, serde::Serialize, serde::Deserialize)]
 pub struct BiomeData {
     pub temperature: f32,
     pub moisture: f32,
-    pub biome_type: String,
+    /// Biome in the registry
+    pub biome: BiomeId,
 }
 
 /// Chunk biome data storage
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, serde::Serialize, serde::Deserialize)]
 pub struct ChunkBiomeData {
     pub data: Vec<BiomeData>,
 }
@@ -158,14 +356,14 @@ impl ChunkBiomeData {
         local_z: usize,
         temperature: f32,
         moisture: f32,
-        biome_type: &str,
+        biome: BiomeId,
     ) {
         let index = local_z * CHUNK_SIZE + local_x;
         if index < self.data.len() {
             self.data[index] = BiomeData {
                 temperature,
                 moisture,
-                biome_type: biome_type.to_string(),
+                biome,
             };
         }
     }
@@ -174,7 +372,7 @@ impl ChunkBiomeData {
     pub fn get_biome_data(&self, local_x: usize, local_z: usize) -> Option<BiomeData> {
         let index = local_z * CHUNK_SIZE + local_x;
         if index < self.data.len() {
-            Some(self.data[index].clone())
+            Some(self.data[index])
         } else {
             None
         }
@@ -189,67 +387,94 @@ pub struct Chunk {
     pub biome_data: ChunkBiomeData,
     pub is_generated: bool,
     pub needs_mesh_update: bool,
-    #[allow(dead_code)]
+    /// Skylight and block light levels for every block in `data`
+    pub light: ChunkLight,
+    /// Whether the chunk's light has to be worked out from scratch
+    pub needs_light_update: bool,
+    /// World positions of blocks changed since light was last updated
+    pub light_updates: Vec<IVec3>,
     pub priority: ChunkPriority,
-    #[allow(dead_code)]
+    /// Level of detail the chunk is meshed at, chosen from its priority
+    pub lod: ChunkLod,
+    /// Whether the game camera can see the chunk, filled in by occlusion culling
     pub is_visible: bool,
 }
 
 impl Chunk {
-    pub fn new(position: ChunkPosition) -> Self {
+    pub fn new(position: ChunkPosition, world_height: WorldHeight) -> Self {
         Self {
             position,
-            data: ChunkData::new(),
+            data: ChunkData::new(world_height),
             biome_data: ChunkBiomeData::new(),
             is_generated: false,
             needs_mesh_update: true,
+            light: ChunkLight::new(world_height),
+            needs_light_update: true,
+            light_updates: Vec::new(),
             priority: ChunkPriority::Far,
+            lod: ChunkLod::Full,
             is_visible: false,
         }
     }
 
     /// Get block at world position relative to this chunk
+    /// Positions above or below the build limits have no block.
     pub fn get_block_world(&self, world_pos: IVec3) -> Option<BlockType> {
         let local_pos = self.world_to_local(world_pos);
-        self.data.get_block(
-            local_pos.x as usize,
-            local_pos.y as usize,
-            local_pos.z as usize,
-        )
+        self.data
+            .get_block(local_pos.x as usize, local_pos.y, local_pos.z as usize)
+    }
+
+    /// Get the state of the block at world position relative to this chunk
+    pub fn get_block_state_world(&self, world_pos: IVec3) -> BlockState {
+        let local_pos = self.world_to_local(world_pos);
+        self.data
+            .get_block_state(local_pos.x as usize, local_pos.y, local_pos.z as usize)
     }
 
     /// Set block at world position relative to this chunk
+    /// Positions above or below the build limits are ignored.
     pub fn set_block_world(&mut self, world_pos: IVec3, block_type: BlockType) {
+        self.set_block_with_state_world(world_pos, block_type, BlockState::default());
+    }
+
+    /// Set block and its state at world position relative to this chunk
+    pub fn set_block_with_state_world(
+        &mut self,
+        world_pos: IVec3,
+        block_type: BlockType,
+        state: BlockState,
+    ) {
         let local_pos = self.world_to_local(world_pos);
-        self.data.set_block(
+        self.data.set_block_with_state(
             local_pos.x as usize,
-            local_pos.y as usize,
+            local_pos.y,
             local_pos.z as usize,
             block_type,
+            state,
         );
         self.needs_mesh_update = true;
+        self.light_updates.push(world_pos);
     }
 
-    /// Convert world position to local chunk coordinates
+    /// Convert world position to local chunk coordinates, keeping the world y
     fn world_to_local(&self, world_pos: IVec3) -> IVec3 {
         IVec3::new(
             world_pos.x.rem_euclid(CHUNK_SIZE as i32),
-            world_pos.y.clamp(0, CHUNK_HEIGHT as i32 - 1),
+            world_pos.y,
             world_pos.z.rem_euclid(CHUNK_SIZE as i32),
         )
     }
 
     /// Check if a world position is within this chunk
-    #[allow(dead_code)]
     pub fn contains(&self, world_pos: IVec3) -> bool {
         let min_pos = self.position.min_block_position();
-        let max_pos =
-            min_pos + IVec3::new(CHUNK_SIZE as i32, CHUNK_HEIGHT as i32, CHUNK_SIZE as i32);
+        let max_pos = min_pos + IVec3::new(CHUNK_SIZE as i32, 0, CHUNK_SIZE as i32);
 
         world_pos.x >= min_pos.x
             && world_pos.x < max_pos.x
-            && world_pos.y >= min_pos.y
-            && world_pos.y < max_pos.y
+            && world_pos.y >= self.data.min_y()
+            && world_pos.y < self.data.max_y()
             && world_pos.z >= min_pos.z
             && world_pos.z < max_pos.z
     }
@@ -264,6 +489,10 @@ pub struct ChunkManager {
     /// This grid divides the world into regions to optimize chunk management
     pub spatial_grid: HashMap<(i32, i32), Vec<ChunkPosition>>, // Region coordinates -> chunk positions
     pub grid_region_size: i32, // Size of each grid region in chunks
+    /// Build limits used for every chunk in the world
+    pub world_height: WorldHeight,
+    /// Blocks that changed and updates scheduled for later ticks, across every loaded chunk
+    pub block_updates: BlockUpdates,
 
     /// Chunk cache for intelligent memory management
     /// This implements a simple LRU (Least Recently Used) cache for chunks
@@ -272,6 +501,87 @@ pub struct ChunkManager {
     max_cache_size: usize,                       // Maximum number of chunks to keep in cache
 }
 
+/// Block updates waiting to run, counted in ticks
+/// Updates are kept by world position rather than by chunk, so whatever they change can carry on
+/// across chunk borders. An update that needs a chunk that isn't loaded waits for it, kept by
+/// the chunk's position, and runs again once the chunk loads.
+#[derive(Debug, Default)]
+pub struct BlockUpdates {
+    tick: u64,
+    /// Positions due on each tick
+    due: BTreeMap<u64, Vec<IVec3>>,
+    /// Tick each position is next due on, so it's only queued once
+    pending: HashMap<IVec3, u64>,
+    /// Blocks that changed since the last tick, whose neighbours may need updating
+    changed: Vec<IVec3>,
+    /// Blocks whose updates wait for each chunk that isn't loaded
+    waiting: HashMap<ChunkPosition, HashSet<IVec3>>,
+}
+
+impl BlockUpdates {
+    /// Schedule an update of a block some ticks from now, unless one is already due sooner
+    pub fn schedule(&mut self, position: IVec3, delay: u32) {
+        let due = self.tick + delay.max(1) as u64;
+        if self
+            .pending
+            .get(&position)
+            .is_some_and(|&pending| pending <= due)
+        {
+            return;
+        }
+        self.pending.insert(position, due);
+        self.due.entry(due).or_default().push(position);
+    }
+
+    /// Record that a block changed, so its neighbours get a look on the next tick
+    pub fn block_changed(&mut self, position: IVec3) {
+        self.changed.push(position);
+    }
+
+    /// Take the blocks that changed since the last tick
+    pub fn take_changed(&mut self) -> Vec<IVec3> {
+        std::mem::take(&mut self.changed)
+    }
+
+    /// Hold the update of a block back until a chunk it reaches into is loaded
+    pub fn wait_for_chunk(&mut self, chunk_position: ChunkPosition, position: IVec3) {
+        self.waiting
+            .entry(chunk_position)
+            .or_default()
+            .insert(position);
+    }
+
+    /// Treat the blocks waiting for chunks that have loaded as changed, so their updates run again
+    pub fn resume_waiting(&mut self, is_loaded: impl Fn(ChunkPosition) -> bool) {
+        let loaded: Vec<ChunkPosition> = self
+            .waiting
+            .keys()
+            .copied()
+            .filter(|&chunk_position| is_loaded(chunk_position))
+            .collect();
+        for chunk_position in loaded {
+            if let Some(positions) = self.waiting.remove(&chunk_position) {
+                self.changed.extend(positions);
+            }
+        }
+    }
+
+    /// Move on to the next tick and take the updates due on it
+    pub fn advance(&mut self) -> Vec<IVec3> {
+        self.tick += 1;
+        let Some(due) = self.due.remove(&self.tick) else {
+            return Vec::new();
+        };
+        due.into_iter()
+            .filter(|position| {
+                // Updates moved to an earlier tick leave a stale entry behind
+                self.pending.get(position) == Some(&self.tick)
+                    && self.pending.remove(position).is_some()
+            })
+            .collect()
+    }
+}
+
 /// Data stored in the chunk cache
 #[derive(Debug, Clone)]
 pub struct CachedChunkData {
@@ -284,7 +594,7 @@ pub struct CachedChunkData {
 
 #[allow(dead_code)]
 impl ChunkManager {
-    pub fn new(render_distance: i32) -> Self {
+    pub fn new(render_distance: i32, world_height: WorldHeight) -> Self {
         // Set grid region size based on render distance for optimal performance
         let grid_region_size = (render_distance / 2).max(4).min(8);
         // Set cache size based on render distance - cache more chunks for larger worlds
@@ -295,6 +605,8 @@ impl ChunkManager {
             render_distance,
             spatial_grid: HashMap::new(),
             grid_region_size,
+            world_height,
+            block_updates: BlockUpdates::default(),
             chunk_cache: HashMap::new(),
             cache_access_order: VecDeque::new(),
             max_cache_size,
@@ -383,7 +695,7 @@ impl ChunkManager {
         chunk_pos: &ChunkPosition,
         neighbor_pos: &ChunkPosition,
         local_x: usize,
-        y: usize,
+        y: i32,
         local_z: usize,
     ) -> Option<BlockType> {
         // Check if the neighbor chunk exists
@@ -508,7 +820,6 @@ impl ChunkManager {
     }
 
     /// Calculate chunk priority based on distance from player and visibility
-    #[allow(dead_code)]
     pub fn calculate_chunk_priority(
         &self,
         chunk_pos: &ChunkPosition,
@@ -530,6 +841,19 @@ impl ChunkManager {
         }
     }
 
+    /// Level of detail a chunk should be meshed at, from its priority and distance to the player
+    pub fn calculate_chunk_lod(
+        &self,
+        chunk_pos: &ChunkPosition,
+        player_chunk_pos: &ChunkPosition,
+    ) -> ChunkLod {
+        let dx = (chunk_pos.x - player_chunk_pos.x).abs();
+        let dz = (chunk_pos.z - player_chunk_pos.z).abs();
+        let distance = dx.max(dz);
+        let priority = self.calculate_chunk_priority(chunk_pos, player_chunk_pos, false);
+        ChunkLod::for_priority(priority, distance, self.render_distance)
+    }
+
     /// Get chunks sorted by priority (highest first)
     pub fn get_chunks_sorted_by_priority(
         &self,
@@ -589,19 +913,35 @@ impl ChunkManager {
         self.loaded_chunks.len()
     }
 
-    /// Get memory usage estimate for loaded chunks
-    pub fn estimate_memory_usage(&self) -> usize {
-        // Estimate memory usage: each chunk has CHUNK_VOLUME blocks
-        // Each block is an Option<BlockType> which is roughly 1 byte (enum discriminant) + some overhead
-        // This is a rough estimate for monitoring purposes
-        self.loaded_chunks.len() * CHUNK_VOLUME
+    /// Get memory usage estimate for loaded and cached chunk block data
+    pub fn estimate_memory_usage(&self, chunks: &Query<&Chunk>) -> usize {
+        let loaded_memory: usize = self
+            .loaded_chunks
+            .values()
+            .filter_map(|&entity| chunks.get(entity).ok())
+            .map(|chunk| chunk.data.memory_usage())
+            .sum();
+        loaded_memory + self.cache_memory_usage()
+    }
+
+    /// Memory used by the block data held in the chunk cache
+    pub fn cache_memory_usage(&self) -> usize {
+        self.chunk_cache
+            .values()
+            .map(|cached| cached.data.memory_usage())
+            .sum()
     }
 
     /// Optimize cache based on current memory constraints
     pub fn optimize_cache(&mut self, available_memory_mb: usize) {
-        // Convert available memory to approximate chunk count
-        // Each chunk is roughly CHUNK_VOLUME bytes
-        let max_chunks_by_memory = (available_memory_mb * 1024 * 1024) / CHUNK_VOLUME;
+        // Convert available memory to approximate chunk count using the real size of the
+        // cached chunks; fall back to an uncompressed chunk when the cache is empty
+        let average_chunk_memory = if self.chunk_cache.is_empty() {
+            CHUNK_AREA * self.world_height.height()
+        } else {
+            (self.cache_memory_usage() / self.chunk_cache.len()).max(1)
+        };
+        let max_chunks_by_memory = (available_memory_mb * 1024 * 1024) / average_chunk_memory;
 
         // Adjust cache size based on available memory
         let target_cache_size = max_chunks_by_memory.min(self.max_cache_size);
@@ -635,11 +975,14 @@ impl ChunkManager {
     pub fn cache_chunk(
         &mut self,
         chunk_pos: ChunkPosition,
-        chunk_data: ChunkData,
+        mut chunk_data: ChunkData,
         biome_data: ChunkBiomeData,
         is_generated: bool,
         current_time: f64,
     ) {
+        // Cached chunks are not edited, so shrink their palettes while they wait
+        chunk_data.compact();
+
         // Remove from cache if it already exists to update access time
         self.cache_access_order.retain(|&pos| pos != chunk_pos);
 
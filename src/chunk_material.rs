@@ -0,0 +1,140 @@
+// Chunk material for Bevy Craft
+// This module draws chunk meshes with one material built on the block texture array
+//
+// Every vertex of a chunk mesh carries the texture array layer of its block, the tint of the biome
+// it stands in, its skylight and block light and its ambient occlusion. One material per render
+// layer is enough to draw every block type in a chunk with its own texture, so each layer of a
+// chunk is a single draw call. The brightness of the sky is a uniform of the materials, so day
+// turning to night only updates three materials and never touches the meshes.
+
+use bevy::pbr::{MaterialPipeline, MaterialPipelineKey};
+use bevy::prelude::*;
+use bevy::render::mesh::{MeshVertexAttribute, MeshVertexBufferLayoutRef};
+use bevy::render::render_resource::{
+    AsBindGroup, RenderPipelineDescriptor, ShaderRef, SpecializedMeshPipelineError, VertexFormat,
+};
+
+use crate::chunk_mesh::MeshLayer;
+
+/// Layer of the block texture array a vertex samples
+pub const ATTRIBUTE_TEXTURE_LAYER: MeshVertexAttribute =
+    MeshVertexAttribute::new("Vertex_TextureLayer", 820_116_371, VertexFormat::Uint32);
+
+/// Biome colour a vertex's texture is multiplied by
+pub const ATTRIBUTE_BIOME_TINT: MeshVertexAttribute =
+    MeshVertexAttribute::new("Vertex_BiomeTint", 820_116_372, VertexFormat::Float32x3);
+
+/// Skylight and block light of a vertex, as fractions of the brightest light level
+pub const ATTRIBUTE_LIGHT: MeshVertexAttribute =
+    MeshVertexAttribute::new("Vertex_Light", 820_116_373, VertexFormat::Float32x2);
+
+/// Material drawing one render layer of chunk meshes from the block texture array
+#[derive(Asset, TypePath, AsBindGroup, Debug, Clone)]
+#[bind_group_data(ChunkMaterialKey)]
+pub struct ChunkMaterial {
+    #[texture(0, dimension = "2d_array")]
+    #[sampler(1)]
+    pub block_textures: Handle<Image>,
+    /// Texels less opaque than this are cut out
+    #[uniform(2)]
+    pub alpha_cutoff: f32,
+    /// Opacity the faces are blended with
+    #[uniform(3)]
+    pub opacity: f32,
+    /// Brightness of skylight, from `SkyBrightness`
+    #[uniform(4)]
+    pub sky_brightness: f32,
+    pub alpha_mode: AlphaMode,
+    /// Draw the back of faces too, for blocks seen from inside
+    pub double_sided: bool,
+}
+
+impl ChunkMaterial {
+    /// Material for a render layer of chunk meshes
+    pub fn for_layer(layer: MeshLayer, block_textures: Handle<Image>) -> Self {
+        let (alpha_cutoff, opacity) = match layer {
+            MeshLayer::Opaque => (0.0, 1.0),
+            MeshLayer::Cutout => (0.5, 1.0),
+            MeshLayer::Translucent => (0.0, 0.7),
+        };
+        Self {
+            block_textures,
+            alpha_cutoff,
+            opacity,
+            sky_brightness: 1.0,
+            alpha_mode: layer.alpha_mode(),
+            // Leaves are seen from inside the canopy and the surface of water from below
+            double_sided: layer != MeshLayer::Opaque,
+        }
+    }
+}
+
+/// Part of a chunk material that changes its render pipeline
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ChunkMaterialKey {
+    double_sided: bool,
+}
+
+impl From<&ChunkMaterial> for ChunkMaterialKey {
+    fn from(material: &ChunkMaterial) -> Self {
+        Self {
+            double_sided: material.double_sided,
+        }
+    }
+}
+
+impl Material for ChunkMaterial {
+    fn vertex_shader() -> ShaderRef {
+        "shaders/chunk_material.wgsl".into()
+    }
+
+    fn fragment_shader() -> ShaderRef {
+        "shaders/chunk_material.wgsl".into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        self.alpha_mode
+    }
+
+    fn specialize(
+        _pipeline: &MaterialPipeline<Self>,
+        descriptor: &mut RenderPipelineDescriptor,
+        layout: &MeshVertexBufferLayoutRef,
+        key: MaterialPipelineKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        let vertex_layout = layout.0.get_layout(&[
+            Mesh::ATTRIBUTE_POSITION.at_shader_location(0),
+            Mesh::ATTRIBUTE_NORMAL.at_shader_location(1),
+            Mesh::ATTRIBUTE_UV_0.at_shader_location(2),
+            Mesh::ATTRIBUTE_COLOR.at_shader_location(3),
+            ATTRIBUTE_TEXTURE_LAYER.at_shader_location(4),
+            ATTRIBUTE_BIOME_TINT.at_shader_location(5),
+            ATTRIBUTE_LIGHT.at_shader_location(6),
+        ])?;
+        descriptor.vertex.buffers = vec![vertex_layout];
+        if key.bind_group_data.double_sided {
+            descriptor.primitive.cull_mode = None;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn test_shader_is_found_in_the_assets_directory() {
+        let assets = AssetPlugin::default().file_path;
+        for shader in [
+            ChunkMaterial::vertex_shader(),
+            ChunkMaterial::fragment_shader(),
+        ] {
+            let ShaderRef::Path(path) = shader else {
+                panic!("expected the shader to load from a file");
+            };
+            assert!(Path::new(&assets).join(path.path()).is_file());
+        }
+    }
+}
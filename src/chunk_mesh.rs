@@ -1,35 +1,70 @@
 // Chunk mesh system for Bevy Craft
 // This module handles efficient mesh generation and rendering for chunks
 //
-// Texture Atlas System:
-// The system uses a 4x2 texture atlas grid where each block type has unique UV coordinates.
-// This allows different block types to use different textures from a single texture atlas image.
+// Textures:
+// Every block face is drawn from its own layer of the block texture array, whatever cell of the
+// texture atlas image it has, so a face's UVs always cover the whole layer.
 //
-// Atlas Layout:
-// Row 0 (Top):    Grass | Dirt | Stone | Wood
-// Row 1 (Bottom): Leaves| Sand | Water | Bedrock
+// Greedy Meshing:
+// With `ChunkMeshSettings::greedy_meshing` on, coplanar faces of the same block type and texture
+// are merged into larger quads. The texture array uses a repeating sampler, so a merged quad's
+// UVs run from 0 to its size in blocks and the texture tiles once per block.
 //
-// Each texture cell is 0.25 units wide and 0.5 units tall in normalized UV space.
+// Lighting:
+// Each vertex carries its skylight and block light apart, with ambient occlusion as its vertex
+// colour. `ChunkMaterial` weighs skylight by the brightness of the sky as it draws, so meshes are
+// rebuilt when a chunk's light changes but not as the day goes by.
+//
+// Render Layers:
+// Faces are split into opaque, cutout (leaves, whose texture has gaps) and translucent (water)
+// sub-meshes, each drawn with the `ChunkMaterial` of its layer. Every vertex carries the texture
+// array layer of its block and the tint of its biome, so one sub-mesh draws all of its block
+// types. Translucent faces are sorted back to front as the camera moves, and faces between two
+// water blocks are left out so only the surface of a body of water is drawn.
+//
+// Block Shapes:
+// Blocks that don't fill their cell, such as slabs, stairs and fences, are drawn box by box from
+// their `BlockShape` and never merged. They don't hide the faces of their neighbours, and their
+// own faces are lit by the light inside their cell without ambient occlusion. Pillars such as logs
+// are full cubes that turn their top and bottom textures towards the axis in their state.
+// Flowing water and lava are drawn the same way, as one box up to the height of their surface,
+// and hide their sides only against the same fluid standing at least as high. Their level isn't
+// captured across chunk borders, so fluid in a neighbouring chunk counts as filling its cell.
+//
+// Level of Detail:
+// Far chunks are meshed at a coarser `ChunkLod`: their blocks are grouped into cells of 2x2x2 or
+// 4x4x4 blocks, each filled with the top block of the cell when at least half of it is solid, and
+// the cell grid is greedy meshed with flat shading so every cell face merges into large quads.
+// Where two chunks meet at different levels their surfaces don't line up, so border faces are
+// only culled between two full-detail chunks; everywhere else they are drawn as skirts that
+// close the gaps at the seam.
 
 use bevy::prelude::*;
 use bevy::render::mesh::{Indices, Mesh};
 use bevy::render::render_asset::RenderAssetUsages;
+use bevy::tasks::{block_on, futures_lite::future, AsyncComputeTaskPool, Task};
 use std::collections::HashMap;
 
+use crate::biome::BiomeRegistry;
 use crate::block::BlockType;
-use crate::chunk::Chunk;
+use crate::block_state::{BlockShape, BlockState};
+use crate::chunk::{
+    Chunk, ChunkBiomeData, ChunkData, ChunkLod, ChunkPosition, WorldHeight, CHUNK_AREA, CHUNK_SIZE,
+};
+use crate::chunk_material::{
+    ChunkMaterial, ATTRIBUTE_BIOME_TINT, ATTRIBUTE_LIGHT, ATTRIBUTE_TEXTURE_LAYER,
+};
+use crate::chunk_visibility::ChunkConnectivity;
+use crate::lighting::{light_brightness, ChunkLight, LightChannel, SkyBrightness, MAX_LIGHT};
 use crate::texture_atlas::{BlockFace, TextureAtlas};
 
-use crate::biome_material::{BiomeMaterial, SharedBiomeMaterialCache};
-use crate::biome_texture_cache::SharedBiomeTextureCache;
-
 /// Component that stores the mesh data for a chunk
 #[derive(Component, Debug)]
 pub struct ChunkMesh {
-    /// Handle to the mesh asset
-    pub mesh_handle: Handle<Mesh>,
-    /// Material handles for different block types in this chunk
-    pub material_handles: HashMap<BlockType, Handle<StandardMaterial>>,
+    /// Sub-meshes of the chunk, one for each render layer with visible faces
+    pub parts: Vec<ChunkMeshPart>,
+    /// Which faces of each section see each other, for occlusion culling
+    pub connectivity: ChunkConnectivity,
     /// Flag indicating if the mesh needs to be regenerated
     #[allow(dead_code)]
     pub needs_rebuild: bool,
@@ -38,165 +73,407 @@ pub struct ChunkMesh {
 impl ChunkMesh {
     pub fn new() -> Self {
         Self {
-            mesh_handle: Handle::default(),
-            material_handles: HashMap::new(),
+            parts: Vec::new(),
+            connectivity: ChunkConnectivity::default(),
             needs_rebuild: true,
         }
     }
 }
 
-/// Resource for managing chunk mesh materials
-#[derive(Resource, Default, Debug)]
-pub struct ChunkMeshMaterials {
-    /// Map of block types to their material handles
-    pub materials: HashMap<BlockType, Handle<StandardMaterial>>,
+/// Mesh holding the faces of one render layer in a chunk
+#[derive(Debug, Clone)]
+pub struct ChunkMeshPart {
+    pub layer: MeshLayer,
+    pub mesh_handle: Handle<Mesh>,
 }
 
-impl ChunkMeshMaterials {
-    /// Initialize default materials for all block types using texture atlas
-    pub fn initialize(
-        &mut self,
-        materials: &mut ResMut<Assets<StandardMaterial>>,
-        texture_atlas: &Res<TextureAtlas>,
-    ) {
-        // Check if texture atlas is loaded
-        if !texture_atlas.is_loaded() {
-            println!("⚠️  Texture atlas not loaded, falling back to solid colors");
-            // Fallback to solid colors if texture atlas is not available
-            for block_type in [
-                BlockType::Dirt,
-                BlockType::Stone,
-                BlockType::Grass,
-                BlockType::Wood,
-                BlockType::Leaves,
-                BlockType::Sand,
-                BlockType::Water,
-                BlockType::Bedrock,
-            ] {
-                let material = materials.add(StandardMaterial {
-                    base_color: block_type.color(),
-                    ..default()
-                });
-                self.materials.insert(block_type, material);
+/// Marker for the child entities drawing the parts of a chunk's mesh
+#[derive(Component, Debug)]
+pub struct ChunkSubMesh;
+
+/// Translucent sub-mesh whose faces are kept sorted back to front for the camera
+#[derive(Component, Debug, Default)]
+pub struct TranslucentChunkMesh {
+    /// Camera position, relative to the chunk, the faces were last sorted for
+    sorted_from: Option<Vec3>,
+}
+
+/// How the faces of a block are blended with what's behind them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MeshLayer {
+    /// Solid blocks that hide everything behind them
+    Opaque,
+    /// Blocks with fully see-through gaps in their texture, such as leaves
+    Cutout,
+    /// Blocks blended with what's behind them, such as water
+    Translucent,
+}
+
+impl MeshLayer {
+    /// Layer a block type's faces are drawn in
+    /// Transparent blocks the player can walk through, like water, are blended; transparent
+    /// solid ones, like leaves, are cut out. Blocks only see-through for not filling their cell
+    /// stay opaque.
+    pub fn of(block_type: BlockType) -> Self {
+        let block = block_type.definition();
+        match (block.transparent, block.solid) {
+            (false, _) => MeshLayer::Opaque,
+            (true, true) => MeshLayer::Cutout,
+            (true, false) => MeshLayer::Translucent,
+        }
+    }
+
+    /// Alpha mode of materials drawing this layer
+    pub fn alpha_mode(self) -> AlphaMode {
+        match self {
+            MeshLayer::Opaque => AlphaMode::Opaque,
+            MeshLayer::Cutout => AlphaMode::Mask(0.5),
+            MeshLayer::Translucent => AlphaMode::Blend,
+        }
+    }
+}
+
+/// Settings for building chunk meshes
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct ChunkMeshSettings {
+    /// Merge coplanar faces with the same texture into larger quads
+    pub greedy_meshing: bool,
+}
+
+impl Default for ChunkMeshSettings {
+    fn default() -> Self {
+        Self {
+            greedy_meshing: true,
+        }
+    }
+}
+
+/// Offsets of the horizontal neighbours whose border slices a snapshot keeps, in storage order
+const NEIGHBOUR_OFFSETS: [(i32, i32); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+
+/// Copy of a chunk and the edges of its neighbours, enough to mesh it off the main thread
+#[derive(Clone)]
+pub struct ChunkMeshSnapshot {
+    pub data: ChunkData,
+    pub biome_data: ChunkBiomeData,
+    /// Blocks of each neighbour's column slice touching this chunk, indexed by position along the
+    /// border and then height; empty where the neighbour isn't loaded
+    borders: [Vec<Option<BlockType>>; 4],
+    /// Light of the chunk, or None while it hasn't been lit and is drawn at full brightness
+    light: Option<ChunkLight>,
+    /// Skylight and block light of the border slices, laid out like `borders`
+    border_light: [Vec<(u8, u8)>; 4],
+    /// Tint of each column's biome, indexed by z and then x
+    biome_tints: Vec<[f32; 3]>,
+    /// Level of detail the chunk is meshed at
+    lod: ChunkLod,
+    /// Level of detail of each loaded neighbour, in the order of `borders`
+    neighbour_lods: [Option<ChunkLod>; 4],
+}
+
+impl ChunkMeshSnapshot {
+    /// Positions of the neighbours `capture` expects, in the same order
+    pub fn neighbour_positions(position: ChunkPosition) -> [ChunkPosition; 4] {
+        NEIGHBOUR_OFFSETS.map(|(dx, dz)| ChunkPosition::new(position.x + dx, position.z + dz))
+    }
+
+    /// Copy a chunk and the border slices of whichever neighbours are loaded
+    pub fn capture(chunk: &Chunk, neighbours: [Option<&Chunk>; 4]) -> Self {
+        let mut borders: [Vec<Option<BlockType>>; 4] = Default::default();
+        let mut border_light: [Vec<(u8, u8)>; 4] = Default::default();
+        let neighbour_lods = neighbours.map(|neighbour| neighbour.map(|neighbour| neighbour.lod));
+        for (side, neighbour) in neighbours.into_iter().enumerate() {
+            let Some(neighbour) = neighbour else {
+                continue;
+            };
+            let neighbour_lit = !neighbour.needs_light_update;
+            for along in 0..CHUNK_SIZE {
+                let (x, z) = match NEIGHBOUR_OFFSETS[side] {
+                    (1, _) => (0, along),
+                    (-1, _) => (CHUNK_SIZE - 1, along),
+                    (_, 1) => (along, 0),
+                    _ => (along, CHUNK_SIZE - 1),
+                };
+                for y in chunk.data.min_y()..chunk.data.max_y() {
+                    borders[side].push(neighbour.data.get_block(x, y, z));
+                    if neighbour_lit {
+                        border_light[side].push((
+                            neighbour.light.get(LightChannel::Sky, x, y, z),
+                            neighbour.light.get(LightChannel::Block, x, y, z),
+                        ));
+                    }
+                }
             }
-            return;
         }
 
-        // Create a single material that uses the texture atlas
-        // All blocks will share the same material but use different UV coordinates
-        let atlas_material = materials.add(StandardMaterial {
-            base_color: Color::WHITE, // Use white to show texture colors accurately
-            base_color_texture: Some(texture_atlas.texture_handle().clone()),
-            ..default()
-        });
+        Self {
+            data: chunk.data.clone(),
+            biome_data: chunk.biome_data.clone(),
+            borders,
+            light: (!chunk.needs_light_update).then(|| chunk.light.clone()),
+            border_light,
+            biome_tints: vec![[1.0; 3]; CHUNK_AREA],
+            lod: chunk.lod,
+            neighbour_lods,
+        }
+    }
 
-        // Store the same material for all block types
-        // The UV coordinates will be handled in the mesh generation
-        for block_type in [
-            BlockType::Dirt,
-            BlockType::Stone,
-            BlockType::Grass,
-            BlockType::Wood,
-            BlockType::Leaves,
-            BlockType::Sand,
-            BlockType::Water,
-            BlockType::Bedrock,
-        ] {
-            self.materials.insert(block_type, atlas_material.clone());
-        }
-
-        // If procedural textures are available, create separate materials for each block type
-        if texture_atlas.has_procedural_textures() {
-            println!("🎨 Creating procedural materials for block types...");
-            for block_type in [
-                BlockType::Dirt,
-                BlockType::Stone,
-                BlockType::Grass,
-                BlockType::Wood,
-                BlockType::Leaves,
-                BlockType::Sand,
-                BlockType::Water,
-                BlockType::Bedrock,
-            ] {
-                if let Some(procedural_texture) = texture_atlas.get_procedural_texture(block_type) {
-                    let procedural_material = materials.add(StandardMaterial {
-                        base_color: Color::WHITE,
-                        base_color_texture: Some(procedural_texture.clone()),
-                        ..default()
-                    });
-                    self.materials.insert(block_type, procedural_material);
-                    println!("  ✓ Created procedural material for {:?}", block_type);
+    /// Copy of the snapshot with its blocks replaced by the cell grid of its level of detail
+    fn downsampled(&self) -> Self {
+        let step = self.lod.step();
+        let mut cells = ChunkData::new(WorldHeight::new(self.data.min_y(), self.data.max_y()));
+        for section_index in 0..self.data.section_count() {
+            if self.data.is_section_empty(section_index) {
+                continue;
+            }
+            let section_min_y = self.data.section_min_y(section_index);
+            let section_max_y = section_min_y + crate::chunk_section::SECTION_SIZE as i32;
+
+            for cell_x in (0..CHUNK_SIZE).step_by(step) {
+                for cell_z in (0..CHUNK_SIZE).step_by(step) {
+                    for cell_y in (section_min_y..section_max_y).step_by(step) {
+                        let blocks = || {
+                            (cell_x..cell_x + step).flat_map(move |x| {
+                                (cell_z..cell_z + step).flat_map(move |z| {
+                                    (cell_y..cell_y + step as i32).map(move |y| (x, y, z))
+                                })
+                            })
+                        };
+
+                        // The top block is what the cell looks like from above and from afar
+                        let mut solid = 0;
+                        let mut top: Option<(i32, BlockType)> = None;
+                        for (x, y, z) in blocks() {
+                            if let Some(block) = self.data.get_block(x, y, z)
+                                && block != BlockType::Air
+                            {
+                                solid += 1;
+                                if top.is_none_or(|(top_y, _)| y > top_y) {
+                                    top = Some((y, block));
+                                }
+                            }
+                        }
+                        let Some((_, fill)) = top else {
+                            continue;
+                        };
+                        if solid * 2 < step * step * step {
+                            continue;
+                        }
+                        for (x, y, z) in blocks() {
+                            cells.set_block(x, y, z, fill);
+                        }
+                    }
                 }
             }
         }
+
+        Self {
+            data: cells,
+            ..self.clone()
+        }
     }
 
-    /// Get material handle for a block type
-    pub fn get_material(&self, block_type: BlockType) -> Option<Handle<StandardMaterial>> {
-        self.materials.get(&block_type).cloned()
+    /// Whether faces on one side of the chunk are drawn whatever lies across the border
+    /// Only two full-detail chunks share the same surface along their border; anywhere else the
+    /// faces are kept as a skirt so no gap opens up at the seam.
+    fn is_seam(&self, side: usize) -> bool {
+        self.lod != ChunkLod::Full || self.neighbour_lods[side].is_some_and(|lod| lod != self.lod)
     }
 
-    /// Get biome-specific material handle for a block type and biome parameters
-    pub fn get_biome_material(
-        &self,
-        block_type: BlockType,
-        biome_params: &crate::biome_textures::BiomeTextureParams,
-        biome_cache: &Res<SharedBiomeTextureCache>,
-        biome_material_cache: &Res<SharedBiomeMaterialCache>,
-        materials: &mut ResMut<Assets<StandardMaterial>>,
-        biome_materials: &mut ResMut<Assets<BiomeMaterial>>,
-        images: &mut ResMut<Assets<Image>>,
-    ) -> Option<Handle<StandardMaterial>> {
-        // Generate a unique key for this biome+block combination
-        let _texture_key =
-            crate::biome_textures::generate_texture_cache_key(&block_type, biome_params);
-        // Debug logging - comment out for production to reduce spam
-        // println!("🔑 Requesting texture for key: {}", texture_key);
-
-        // Try to get biome-specific texture from cache first
-        let mut texture_cache = biome_cache.cache.lock().unwrap();
-
-        // Use the full get_or_generate method which includes similarity matching
-        let texture_handle =
-            texture_cache.get_or_generate(&block_type, biome_params, images, |_params| {
-                // If cache miss and no similar texture found, fall back to enhanced textures
-                // If no texture available at all, use default material
-                println!(
-                    "⚠️  No texture available for {:?} at biome {}",
-                    block_type, biome_params.biome_type
-                );
-                (Handle::default(), crate::noise::NoiseSettings::default())
+    /// Tint the columns' blocks with the colours of their biomes
+    pub fn with_biome_tints(mut self, biomes: &BiomeRegistry) -> Self {
+        for local_z in 0..CHUNK_SIZE {
+            for local_x in 0..CHUNK_SIZE {
+                if let Some(biome_data) = self.biome_data.get_biome_data(local_x, local_z) {
+                    let (r, g, b) = biomes.get(biome_data.biome).tint;
+                    self.biome_tints[local_z * CHUNK_SIZE + local_x] = [r, g, b];
+                }
+            }
+        }
+        self
+    }
+
+    /// Block just across the border on one side; None for air or a neighbour that isn't loaded
+    fn border_block(&self, side: usize, along: usize, y: i32) -> Option<BlockType> {
+        let height = (self.data.max_y() - self.data.min_y()) as usize;
+        self.borders[side]
+            .get(along * height + (y - self.data.min_y()) as usize)
+            .copied()
+            .flatten()
+    }
+
+    /// Block at local coordinates that may reach one block past the chunk's sides; diagonal
+    /// neighbours aren't captured and count as empty
+    fn block(&self, [x, y, z]: [i32; 3]) -> Option<BlockType> {
+        if y < self.data.min_y() || y >= self.data.max_y() {
+            return None;
+        }
+        let inside = |v: i32| (0..CHUNK_SIZE as i32).contains(&v);
+        match (inside(x), inside(z)) {
+            (true, true) => self.data.get_block(x as usize, y, z as usize),
+            (false, true) => self.border_block(if x < 0 { 1 } else { 0 }, z as usize, y),
+            (true, false) => self.border_block(if z < 0 { 3 } else { 2 }, x as usize, y),
+            (false, false) => None,
+        }
+    }
+
+    /// Whether a block hides the faces next to it, given in local coordinates that may reach one
+    /// block past the chunk's sides
+    fn is_opaque(&self, position: [i32; 3]) -> bool {
+        self.block(position)
+            .is_some_and(|block| block != BlockType::Air && !block.is_transparent())
+            && !self.is_lowered_fluid(position)
+    }
+
+    /// Height of the surface of the fluid at local coordinates, or 1 for anything that fills its
+    /// cell; fluid past the chunk's sides always does, as its level isn't captured
+    fn fluid_height(&self, [x, y, z]: [i32; 3]) -> f32 {
+        let inside = |v: i32| (0..CHUNK_SIZE as i32).contains(&v);
+        if !inside(x) || !inside(z) {
+            return 1.0;
+        }
+        let (x, z) = (x as usize, z as usize);
+        self.data
+            .get_block(x, y, z)
+            .and_then(|block| block.fluid())
+            .map_or(1.0, |fluid| {
+                fluid.height(self.data.get_block_state(x, y, z))
+            })
+    }
+
+    /// Whether fluid at local coordinates has a surface below the top of its cell
+    fn is_lowered_fluid(&self, position: [i32; 3]) -> bool {
+        self.fluid_height(position) < 1.0
+    }
+
+    /// Skylight and block light at local coordinates that may reach one block past the chunk's
+    /// sides; None where the light isn't known
+    fn light(&self, [x, y, z]: [i32; 3]) -> Option<(u8, u8)> {
+        let light = self.light.as_ref()?;
+        if y >= self.data.max_y() {
+            return Some((MAX_LIGHT, 0));
+        }
+        if y < self.data.min_y() {
+            return None;
+        }
+        let inside = |v: i32| (0..CHUNK_SIZE as i32).contains(&v);
+        let (side, along) = match (inside(x), inside(z)) {
+            (true, true) => {
+                let (x, z) = (x as usize, z as usize);
+                return Some((
+                    light.get(LightChannel::Sky, x, y, z),
+                    light.get(LightChannel::Block, x, y, z),
+                ));
+            }
+            (false, true) => (if x < 0 { 1 } else { 0 }, z as usize),
+            (true, false) => (if z < 0 { 3 } else { 2 }, x as usize),
+            (false, false) => return None,
+        };
+        let height = (self.data.max_y() - self.data.min_y()) as usize;
+        self.border_light[side]
+            .get(along * height + (y - self.data.min_y()) as usize)
+            .copied()
+    }
+}
+
+/// A chunk mesh built from a snapshot, waiting to be uploaded
+pub struct BuiltChunkMesh {
+    /// A mesh for each render layer with visible faces
+    pub parts: Vec<(MeshLayer, Mesh)>,
+    /// Connectivity of the chunk's sections at full detail
+    pub connectivity: ChunkConnectivity,
+}
+
+/// Chunk meshes being built on the async compute task pool
+/// A chunk that changes while its mesh is being built gets a new task in place of the old one;
+/// dropping the old task cancels it, so a stale mesh is never uploaded.
+#[derive(Resource, Default)]
+pub struct ChunkMeshTasks {
+    tasks: HashMap<Entity, Task<BuiltChunkMesh>>,
+}
+
+impl ChunkMeshTasks {
+    /// Start meshing a snapshot, replacing any mesh still being built for the chunk
+    pub fn start(
+        &mut self,
+        entity: Entity,
+        snapshot: ChunkMeshSnapshot,
+        texture_atlas: TextureAtlas,
+        settings: ChunkMeshSettings,
+    ) {
+        let task = AsyncComputeTaskPool::get()
+            .spawn(async move { generate_chunk_mesh(&snapshot, &texture_atlas, &settings) });
+        self.tasks.insert(entity, task);
+    }
+
+    /// Number of meshes still being built
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+
+    /// Drop the tasks of chunks that no longer need them
+    pub fn retain(&mut self, mut keep: impl FnMut(Entity) -> bool) {
+        self.tasks.retain(|&entity, _| keep(entity));
+    }
+
+    /// Take every mesh that has finished building
+    pub fn take_finished(&mut self) -> Vec<(Entity, BuiltChunkMesh)> {
+        let mut finished = Vec::new();
+        self.tasks
+            .retain(|&entity, task| match block_on(future::poll_once(task)) {
+                Some(built) => {
+                    finished.push((entity, built));
+                    false
+                }
+                None => true,
             });
+        finished
+    }
+}
 
-        // Try to get biome-specific material from the enhanced biome material cache
-        let mut material_cache = biome_material_cache.cache.lock().unwrap();
-        let _biome_material_handle = material_cache.get_or_generate(
-            &block_type,
-            biome_params,
-            biome_materials,
-            images,
-            |params| {
-                // Generate biome-specific material properties
-                let config = crate::biome_material::BiomeMaterialConfig::new(block_type);
-                let material_properties = config.get_properties_for_biome(params);
-                (texture_handle.clone(), material_properties)
-            },
-        );
+/// Resource for managing chunk mesh materials
+#[derive(Resource, Default, Debug)]
+pub struct ChunkMeshMaterials {
+    /// Map of render layers to their material handles
+    pub materials: HashMap<MeshLayer, Handle<ChunkMaterial>>,
+}
 
-        // For now, we'll still use StandardMaterial for compatibility
-        // In future, we can switch to using BiomeMaterial directly
-        let biome_material = materials.add(StandardMaterial {
-            base_color: Color::WHITE,
-            base_color_texture: Some(texture_handle.clone()),
-            // Apply some biome-specific properties to standard material
-            perceptual_roughness: biome_params.temperature * 0.5 + 0.3,
-            metallic: biome_params.moisture * 0.2,
-            ..default()
-        });
+impl ChunkMeshMaterials {
+    /// Initialize a material for each render layer, all drawing from the block texture array
+    pub fn initialize(
+        &mut self,
+        materials: &mut Assets<ChunkMaterial>,
+        texture_atlas: &TextureAtlas,
+    ) {
+        for layer in [MeshLayer::Opaque, MeshLayer::Cutout, MeshLayer::Translucent] {
+            let material = materials.add(ChunkMaterial::for_layer(
+                layer,
+                texture_atlas.texture_array.clone(),
+            ));
+            self.materials.insert(layer, material);
+        }
+    }
 
-        // Reduce logging spam - only log when actually creating new materials
-        // println!("📊 Created biome-specific material for {:?} at biome {} with key {}", block_type, biome_params.biome_type, texture_key);
-        Some(biome_material)
+    /// Get material handle for a render layer
+    pub fn get_material(&self, layer: MeshLayer) -> Option<Handle<ChunkMaterial>> {
+        self.materials.get(&layer).cloned()
+    }
+}
+
+/// System to light chunks by the brightness of the sky as it changes
+/// Only the materials change; the light in the meshes is the same by day and by night.
+pub fn update_chunk_sky_brightness(
+    sky_brightness: Res<SkyBrightness>,
+    mesh_materials: Res<ChunkMeshMaterials>,
+    mut materials: ResMut<Assets<ChunkMaterial>>,
+) {
+    if !sky_brightness.is_changed() && !mesh_materials.is_changed() {
+        return;
+    }
+    for handle in mesh_materials.materials.values() {
+        if let Some(material) = materials.get_mut(handle) {
+            material.sky_brightness = sky_brightness.0;
+        }
     }
 }
 
@@ -219,96 +496,94 @@ impl FaceVisibility {
 
 /// Check face visibility for a block at given position
 fn check_face_visibility(
-    chunk_data: &crate::chunk::ChunkData,
-    chunk_pos: &crate::chunk::ChunkPosition,
-    chunk_manager: &crate::chunk::ChunkManager,
-    chunks: &Query<&crate::chunk::Chunk>,
+    snapshot: &ChunkMeshSnapshot,
     local_x: usize,
-    y: usize,
+    y: i32,
     local_z: usize,
+    block_type: BlockType,
 ) -> FaceVisibility {
+    let chunk_data = &snapshot.data;
     let mut visibility = FaceVisibility::default();
 
     // Helper function to check if a block should be rendered (is air or transparent)
-    let should_render_face = |block_type: Option<BlockType>| {
-        block_type.map_or(true, |bt| bt == BlockType::Air || bt.is_transparent())
+    // Faces between two translucent blocks of the same kind are inside a body of water and hidden
+    let should_render_face = |neighbour: Option<BlockType>| {
+        neighbour.is_none_or(|bt| {
+            bt == BlockType::Air
+                || (bt.is_transparent()
+                    && !(bt == block_type && MeshLayer::of(bt) == MeshLayer::Translucent))
+        })
+    };
+
+    // Fluid whose surface is lower than its cell shows the faces next to it whatever it is
+    let lowered = |dx: i32, dy: i32, dz: i32| {
+        snapshot.is_lowered_fluid([local_x as i32 + dx, y + dy, local_z as i32 + dz])
+    };
+
+    // Neighbour across a border, left out at a seam between levels of detail
+    let border_block = |side: usize, along: usize| {
+        if snapshot.is_seam(side) {
+            None
+        } else {
+            snapshot.border_block(side, along, y)
+        }
     };
 
     // Check front face (positive Z direction)
     if local_z == crate::chunk::CHUNK_SIZE - 1 {
-        // At chunk boundary, check neighbor chunk
-        let neighbor_pos = crate::chunk::ChunkPosition::new(chunk_pos.x, chunk_pos.z + 1);
-        visibility.front = chunk_manager
-            .get_neighbor_block(chunks, chunk_pos, &neighbor_pos, local_x, y, 0)
-            .map_or(true, |bt| bt == BlockType::Air || bt.is_transparent());
+        // At chunk boundary, check the neighbour's border slice
+        visibility.front = should_render_face(border_block(2, local_x));
     } else {
         // Within chunk, check adjacent block
-        visibility.front = should_render_face(chunk_data.get_block(local_x, y, local_z + 1));
+        visibility.front =
+            should_render_face(chunk_data.get_block(local_x, y, local_z + 1)) || lowered(0, 0, 1);
     }
 
     // Check back face (negative Z direction)
     if local_z == 0 {
-        // At chunk boundary, check neighbor chunk
-        let neighbor_pos = crate::chunk::ChunkPosition::new(chunk_pos.x, chunk_pos.z - 1);
-        visibility.back = chunk_manager
-            .get_neighbor_block(
-                chunks,
-                chunk_pos,
-                &neighbor_pos,
-                local_x,
-                y,
-                crate::chunk::CHUNK_SIZE - 1,
-            )
-            .map_or(true, |bt| bt == BlockType::Air || bt.is_transparent());
+        // At chunk boundary, check the neighbour's border slice
+        visibility.back = should_render_face(border_block(3, local_x));
     } else {
         // Within chunk, check adjacent block
-        visibility.back = should_render_face(chunk_data.get_block(local_x, y, local_z - 1));
+        visibility.back =
+            should_render_face(chunk_data.get_block(local_x, y, local_z - 1)) || lowered(0, 0, -1);
     }
 
     // Check right face (positive X direction)
     if local_x == crate::chunk::CHUNK_SIZE - 1 {
-        // At chunk boundary, check neighbor chunk
-        let neighbor_pos = crate::chunk::ChunkPosition::new(chunk_pos.x + 1, chunk_pos.z);
-        visibility.right = chunk_manager
-            .get_neighbor_block(chunks, chunk_pos, &neighbor_pos, 0, y, local_z)
-            .map_or(true, |bt| bt == BlockType::Air || bt.is_transparent());
+        // At chunk boundary, check the neighbour's border slice
+        visibility.right = should_render_face(border_block(0, local_z));
     } else {
         // Within chunk, check adjacent block
-        visibility.right = should_render_face(chunk_data.get_block(local_x + 1, y, local_z));
+        visibility.right =
+            should_render_face(chunk_data.get_block(local_x + 1, y, local_z)) || lowered(1, 0, 0);
     }
 
     // Check left face (negative X direction)
     if local_x == 0 {
-        // At chunk boundary, check neighbor chunk
-        let neighbor_pos = crate::chunk::ChunkPosition::new(chunk_pos.x - 1, chunk_pos.z);
-        visibility.left = chunk_manager
-            .get_neighbor_block(
-                chunks,
-                chunk_pos,
-                &neighbor_pos,
-                crate::chunk::CHUNK_SIZE - 1,
-                y,
-                local_z,
-            )
-            .map_or(true, |bt| bt == BlockType::Air || bt.is_transparent());
+        // At chunk boundary, check the neighbour's border slice
+        visibility.left = should_render_face(border_block(1, local_z));
     } else {
         // Within chunk, check adjacent block
-        visibility.left = should_render_face(chunk_data.get_block(local_x - 1, y, local_z));
+        visibility.left =
+            should_render_face(chunk_data.get_block(local_x - 1, y, local_z)) || lowered(-1, 0, 0);
     }
 
     // Check top face (positive Y direction)
-    if y < crate::chunk::CHUNK_HEIGHT - 1 {
+    if y < chunk_data.max_y() - 1 {
         // Within chunk, check adjacent block
-        visibility.top = should_render_face(chunk_data.get_block(local_x, y + 1, local_z));
+        visibility.top =
+            should_render_face(chunk_data.get_block(local_x, y + 1, local_z)) || lowered(0, 1, 0);
     } else {
         // At top of chunk, always render (no chunks above)
         visibility.top = true;
     }
 
     // Check bottom face (negative Y direction)
-    if y > 0 {
+    if y > chunk_data.min_y() {
         // Within chunk, check adjacent block
-        visibility.bottom = should_render_face(chunk_data.get_block(local_x, y - 1, local_z));
+        visibility.bottom =
+            should_render_face(chunk_data.get_block(local_x, y - 1, local_z)) || lowered(0, -1, 0);
     } else {
         // At bottom of chunk, always render (no chunks below)
         visibility.bottom = true;
@@ -317,87 +592,348 @@ fn check_face_visibility(
     visibility
 }
 
-/// Add a single face to the mesh
-fn add_face(
-    positions: &mut Vec<[f32; 3]>,
-    normals: &mut Vec<[f32; 3]>,
-    uvs: &mut Vec<[f32; 2]>,
-    indices: &mut Vec<u32>,
-    base_index: u32,
-    vertices: [[f32; 3]; 4],
-    normal: [f32; 3],
-    uv: (f32, f32, f32, f32),
+/// Brightness of a vertex for each ambient occlusion level, from fully enclosed to open
+const AO_BRIGHTNESS: [f32; 4] = [0.45, 0.65, 0.82, 1.0];
+
+/// Skylight and block light at a vertex, each as a fraction of the brightest light level
+type VertexLight = [f32; 2];
+
+/// Light of vertices whose light isn't known yet, drawn at full brightness
+const FULL_LIGHT: VertexLight = [1.0, 1.0];
+
+/// Light of a vertex from light levels, which may be fractional where several blocks' light is
+/// averaged
+fn vertex_light(sky_light: f32, block_light: f32) -> VertexLight {
+    [sky_light / MAX_LIGHT as f32, block_light / MAX_LIGHT as f32]
+}
+
+/// Vertex data of a chunk mesh while it's being built
+#[derive(Default)]
+struct MeshBuffers {
+    positions: Vec<[f32; 3]>,
+    normals: Vec<[f32; 3]>,
+    uvs: Vec<[f32; 2]>,
+    /// Ambient occlusion of each vertex, stored as a grey vertex colour
+    colors: Vec<[f32; 4]>,
+    lights: Vec<VertexLight>,
+    texture_layers: Vec<u32>,
+    biome_tints: Vec<[f32; 3]>,
+    indices: Vec<u32>,
+}
+
+impl MeshBuffers {
+    /// Add a single face to the mesh, with the ambient occlusion level and light of each corner
+    fn add_face(
+        &mut self,
+        vertices: [[f32; 3]; 4],
+        normal: [f32; 3],
+        texture: FaceTexture,
+        ao: [u8; 4],
+        light: [VertexLight; 4],
+    ) {
+        let base_index = self.positions.len() as u32;
+        self.positions.extend_from_slice(&vertices);
+        self.normals.extend_from_slice(&[normal; 4]);
+        self.texture_layers.extend_from_slice(&[texture.layer; 4]);
+        self.biome_tints.extend_from_slice(&[texture.tint; 4]);
+
+        let uv = texture.uv;
+        self.uvs
+            .extend_from_slice(&[[uv.0, uv.1], [uv.2, uv.1], [uv.2, uv.3], [uv.0, uv.3]]);
+        let shade = ao.map(|level| AO_BRIGHTNESS[level as usize]);
+        self.colors
+            .extend(shade.map(|shade| [shade, shade, shade, 1.0]));
+        self.lights.extend_from_slice(&light);
+
+        // Split the quad along its darker diagonal, as it looks by day; splitting along the
+        // brighter one makes the shading of a single corner bleed into a stripe across the face
+        let brightness: [f32; 4] = std::array::from_fn(|corner| {
+            let [sky_light, block_light] = light[corner];
+            shade[corner] * light_brightness(sky_light, block_light, SkyBrightness::default().0)
+        });
+        let [a, b, c, d] = brightness;
+        let order = if a + c > b + d {
+            [1, 2, 3, 1, 3, 0]
+        } else {
+            [0, 1, 2, 0, 2, 3]
+        };
+        self.indices.extend(order.map(|corner| base_index + corner));
+    }
+
+    /// Turn the buffers into a mesh
+    fn into_mesh(self) -> Mesh {
+        let mut mesh = Mesh::new(
+            bevy::render::mesh::PrimitiveTopology::TriangleList,
+            RenderAssetUsages::default(),
+        );
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, self.positions);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, self.normals);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, self.uvs);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, self.colors);
+        mesh.insert_attribute(ATTRIBUTE_LIGHT, self.lights);
+        mesh.insert_attribute(ATTRIBUTE_TEXTURE_LAYER, self.texture_layers);
+        mesh.insert_attribute(ATTRIBUTE_BIOME_TINT, self.biome_tints);
+        mesh.insert_indices(Indices::U32(self.indices));
+        mesh
+    }
+}
+
+/// Reorder a mesh's quads so the ones furthest from `viewer` come first
+/// Translucent faces blend with whatever was drawn before them, so they have to be drawn back to
+/// front to look right from where the camera is.
+pub fn sort_quads_back_to_front(mesh: &mut Mesh, viewer: Vec3) {
+    let Some(bevy::render::mesh::VertexAttributeValues::Float32x3(positions)) =
+        mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+    else {
+        return;
+    };
+    let Some(Indices::U32(indices)) = mesh.indices() else {
+        return;
+    };
+
+    // Every quad has its own four vertices, starting at the lowest index of its two triangles
+    let mut quads: Vec<(f32, [u32; 6])> = indices
+        .chunks_exact(6)
+        .map(|quad| {
+            let base = *quad.iter().min().unwrap_or(&0) as usize;
+            let center = positions[base..base + 4]
+                .iter()
+                .map(|&position| Vec3::from(position))
+                .sum::<Vec3>()
+                / 4.0;
+            let mut quad_indices = [0; 6];
+            quad_indices.copy_from_slice(quad);
+            (center.distance_squared(viewer), quad_indices)
+        })
+        .collect();
+    quads.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    let sorted = quads.into_iter().flat_map(|(_, quad)| quad).collect();
+    mesh.insert_indices(Indices::U32(sorted));
+}
+
+/// System to keep translucent chunk faces sorted back to front as the camera moves
+pub fn sort_translucent_chunk_meshes(
+    camera: Query<&GlobalTransform, With<Camera3d>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut sub_meshes: Query<(&Mesh3d, &GlobalTransform, &mut TranslucentChunkMesh)>,
 ) {
-    positions.extend_from_slice(&vertices);
-    normals.extend_from_slice(&[normal; 4]);
+    // Re-sorting uploads the mesh again, so wait until the camera has moved a little
+    const RESORT_DISTANCE: f32 = 1.0;
 
-    uvs.extend_from_slice(&[[uv.0, uv.1], [uv.2, uv.1], [uv.2, uv.3], [uv.0, uv.3]]);
+    let Ok(camera) = camera.get_single() else {
+        return;
+    };
+    for (mesh_handle, transform, mut translucent) in &mut sub_meshes {
+        let viewer = camera.translation() - transform.translation();
+        if translucent
+            .sorted_from
+            .is_some_and(|sorted_from| sorted_from.distance(viewer) < RESORT_DISTANCE)
+        {
+            continue;
+        }
+        if let Some(mesh) = meshes.get_mut(&mesh_handle.0) {
+            sort_quads_back_to_front(mesh, viewer);
+            translucent.sorted_from = Some(viewer);
+        }
+    }
+}
 
-    indices.extend_from_slice(&[
-        base_index,
-        base_index + 1,
-        base_index + 2,
-        base_index,
-        base_index + 2,
-        base_index + 3,
-    ]);
+/// Ambient occlusion level of each corner of a block's face, in the vertex order of its quad
+/// Each corner looks at the two blocks beside it and the one diagonal to it in the layer the face
+/// looks into: 3 when all are open, down to 0 when both sides are solid.
+fn face_ao(snapshot: &ChunkMeshSnapshot, direction: &FaceDirection, block: [i32; 3]) -> [u8; 4] {
+    let (_, corners) = face_corner_blocks(direction, block);
+    corners.map(|[side_u, side_v, corner]| {
+        let (side_u, side_v, corner) = (
+            snapshot.is_opaque(side_u),
+            snapshot.is_opaque(side_v),
+            snapshot.is_opaque(corner),
+        );
+        if side_u && side_v {
+            0
+        } else {
+            3 - (side_u as u8 + side_v as u8 + corner as u8)
+        }
+    })
+}
+
+/// Light of each corner of a block's face, in the vertex order of its quad
+/// Each corner averages the light of the block in front of the face and of the open blocks around
+/// the corner, so light fades smoothly across faces instead of changing block by block.
+fn face_light(
+    snapshot: &ChunkMeshSnapshot,
+    direction: &FaceDirection,
+    block: [i32; 3],
+) -> [VertexLight; 4] {
+    let (front, corners) = face_corner_blocks(direction, block);
+    corners.map(|[side_u, side_v, corner]| {
+        let (open_u, open_v) = (!snapshot.is_opaque(side_u), !snapshot.is_opaque(side_v));
+        let mut samples = vec![front];
+        if open_u {
+            samples.push(side_u);
+        }
+        if open_v {
+            samples.push(side_v);
+        }
+        // Light can't get round a corner that both sides close off
+        if (open_u || open_v) && !snapshot.is_opaque(corner) {
+            samples.push(corner);
+        }
+
+        let (mut sky, mut emitted, mut count) = (0.0, 0.0, 0.0);
+        for (sky_light, block_light) in samples.into_iter().filter_map(|at| snapshot.light(at)) {
+            sky += sky_light as f32;
+            emitted += block_light as f32;
+            count += 1.0;
+        }
+        if count == 0.0 {
+            return FULL_LIGHT;
+        }
+        vertex_light(sky / count, emitted / count)
+    })
+}
+
+/// Lowest block of the level of detail cell a block belongs to
+fn cell_origin(snapshot: &ChunkMeshSnapshot, [x, y, z]: [i32; 3]) -> [i32; 3] {
+    let step = snapshot.lod.step() as i32;
+    let min_y = snapshot.data.min_y();
+    [
+        x - x % step,
+        min_y + (y - min_y) / step * step,
+        z - z % step,
+    ]
+}
+
+/// Light in front of a cell's face, the same for all of the blocks the face covers
+fn cell_face_light(
+    snapshot: &ChunkMeshSnapshot,
+    direction: &FaceDirection,
+    block: [i32; 3],
+) -> VertexLight {
+    let mut front = cell_origin(snapshot, block);
+    front[direction.axis] = block[direction.axis] + if direction.positive { 1 } else { -1 };
+    snapshot
+        .light(front)
+        .map_or(FULL_LIGHT, |(sky_light, block_light)| {
+            vertex_light(sky_light as f32, block_light as f32)
+        })
+}
+
+/// Block in front of a face, and for each corner of its quad the blocks beside the corner along
+/// U and V and the one diagonal to it, all in the layer the face looks into
+fn face_corner_blocks(
+    direction: &FaceDirection,
+    block: [i32; 3],
+) -> ([i32; 3], [[[i32; 3]; 3]; 4]) {
+    let mut front = block;
+    front[direction.axis] += if direction.positive { 1 } else { -1 };
+
+    let corners = QUAD_CORNERS.map(|(u_high, v_high)| {
+        let step = |(axis, positive): (usize, bool), high: bool| {
+            let mut offset = [0; 3];
+            offset[axis] = if high == positive { 1 } else { -1 };
+            offset
+        };
+        let (du, dv) = (
+            step(direction.u_axis, u_high),
+            step(direction.v_axis, v_high),
+        );
+        let at = |offsets: &[[i32; 3]]| {
+            let mut position = front;
+            for offset in offsets {
+                for axis in 0..3 {
+                    position[axis] += offset[axis];
+                }
+            }
+            position
+        };
+        [at(&[du]), at(&[dv]), at(&[du, dv])]
+    });
+    (front, corners)
 }
 
 /// Generate mesh for a chunk with neighbor awareness
+/// Only reads the snapshot, so it can run on a task pool while the chunk keeps changing.
 pub fn generate_chunk_mesh(
-    chunk_data: &crate::chunk::ChunkData,
-    chunk_pos: &crate::chunk::ChunkPosition,
-    chunk_manager: &crate::chunk::ChunkManager,
-    chunks: &Query<&crate::chunk::Chunk>,
+    snapshot: &ChunkMeshSnapshot,
     texture_atlas: &TextureAtlas,
-    chunk: &Chunk,
-    biome_cache: &crate::biome_texture_cache::SharedBiomeTextureCache,
-    images: &mut ResMut<Assets<Image>>,
-) -> Mesh {
-    let mut mesh = Mesh::new(
-        bevy::render::mesh::PrimitiveTopology::TriangleList,
-        RenderAssetUsages::default(),
-    );
+    settings: &ChunkMeshSettings,
+) -> BuiltChunkMesh {
+    let connectivity = ChunkConnectivity::compute(&snapshot.data);
 
-    let mut positions = Vec::new();
-    let mut normals = Vec::new();
-    let mut uvs = Vec::new();
-    let mut indices = Vec::new();
-
-    // Iterate through all blocks in the chunk
-    for local_x in 0..crate::chunk::CHUNK_SIZE {
-        for local_z in 0..crate::chunk::CHUNK_SIZE {
-            for y in 0..crate::chunk::CHUNK_HEIGHT {
-                if let Some(block_type) = chunk_data.get_block(local_x, y, local_z) {
-                    if block_type != BlockType::Air {
-                        // Check which faces should be rendered
-                        let visibility = check_face_visibility(
-                            chunk_data,
-                            chunk_pos,
-                            chunk_manager,
-                            chunks,
-                            local_x,
-                            y,
-                            local_z,
-                        );
+    // Coarser levels of detail mesh their cell grid, always merging the cells' faces
+    let downsampled;
+    let snapshot = if snapshot.lod == ChunkLod::Full {
+        snapshot
+    } else {
+        downsampled = snapshot.downsampled();
+        &downsampled
+    };
+    let chunk_data = &snapshot.data;
+    let mut buffers: HashMap<MeshLayer, MeshBuffers> = HashMap::new();
+    let mut greedy_faces = (settings.greedy_meshing || snapshot.lod != ChunkLod::Full)
+        .then(|| GreedyFaces::new(chunk_data.min_y(), chunk_data.max_y()));
+
+    // Iterate through all blocks in the chunk, skipping sections that hold no blocks
+    for section_index in 0..chunk_data.section_count() {
+        if chunk_data.is_section_empty(section_index) {
+            continue;
+        }
+        let section_min_y = chunk_data.section_min_y(section_index);
 
-                        // If any face should be rendered, add the block mesh
-                        if visibility.any() {
-                            add_block_mesh(
-                                &mut positions,
-                                &mut normals,
-                                &mut uvs,
-                                &mut indices,
-                                local_x,
-                                y,
-                                local_z,
-                                &visibility,
+        for local_x in 0..crate::chunk::CHUNK_SIZE {
+            for local_z in 0..crate::chunk::CHUNK_SIZE {
+                for y in section_min_y..section_min_y + crate::chunk_section::SECTION_SIZE as i32 {
+                    if let Some(block_type) = chunk_data.get_block(local_x, y, local_z) {
+                        let state = chunk_data.get_block_state(local_x, y, local_z);
+
+                        // Blocks that don't fill their cell are drawn from their boxes, except in
+                        // the cell grid of a coarser level of detail
+                        let position = [local_x as i32, y, local_z as i32];
+                        if snapshot.lod == ChunkLod::Full
+                            && (!block_type.shape().is_full_cube()
+                                || snapshot.is_lowered_fluid(position))
+                        {
+                            add_shaped_block_mesh(
+                                buffers.entry(MeshLayer::of(block_type)).or_default(),
+                                position,
                                 block_type,
+                                state,
                                 texture_atlas,
-                                chunk,
-                                biome_cache,
-                                images,
+                                snapshot,
                             );
+                        } else if block_type != BlockType::Air {
+                            // Check which faces should be rendered
+                            let visibility =
+                                check_face_visibility(snapshot, local_x, y, local_z, block_type);
+
+                            // If any face should be rendered, add the block mesh
+                            if let Some(greedy_faces) = greedy_faces.as_mut() {
+                                // Faces are only collected here and merged once the chunk is done
+                                greedy_faces.collect(
+                                    local_x,
+                                    y,
+                                    local_z,
+                                    &visibility,
+                                    block_type,
+                                    state,
+                                    texture_atlas,
+                                    snapshot,
+                                );
+                            } else if visibility.any() {
+                                add_block_mesh(
+                                    buffers.entry(MeshLayer::of(block_type)).or_default(),
+                                    local_x,
+                                    y,
+                                    local_z,
+                                    &visibility,
+                                    block_type,
+                                    state,
+                                    texture_atlas,
+                                    snapshot,
+                                );
+                            }
                         }
                     }
                 }
@@ -405,35 +941,33 @@ pub fn generate_chunk_mesh(
         }
     }
 
-    // Insert mesh data
-    if !positions.is_empty() {
-        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
-        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
-        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
-        mesh.insert_indices(Indices::U32(indices));
+    if let Some(greedy_faces) = greedy_faces {
+        greedy_faces.merge(&mut buffers);
     }
 
-    mesh
+    BuiltChunkMesh {
+        parts: buffers
+            .into_iter()
+            .map(|(layer, buffers)| (layer, buffers.into_mesh()))
+            .collect(),
+        connectivity,
+    }
 }
 
 /// Add a block mesh with only the visible faces
+#[allow(clippy::too_many_arguments)]
 fn add_block_mesh(
-    positions: &mut Vec<[f32; 3]>,
-    normals: &mut Vec<[f32; 3]>,
-    uvs: &mut Vec<[f32; 2]>,
-    indices: &mut Vec<u32>,
+    buffers: &mut MeshBuffers,
     local_x: usize,
-    y: usize,
+    y: i32,
     local_z: usize,
     visibility: &FaceVisibility,
     block_type: BlockType,
+    state: BlockState,
     texture_atlas: &TextureAtlas,
-    chunk: &Chunk,
-    biome_cache: &crate::biome_texture_cache::SharedBiomeTextureCache,
-    images: &mut ResMut<Assets<Image>>,
+    snapshot: &ChunkMeshSnapshot,
 ) {
-    let base_index = positions.len() as u32;
-    let mut current_index = base_index;
+    let block = [local_x as i32, y, local_z as i32];
 
     // Front face (positive Z) - uses side texture
     if visibility.front {
@@ -444,28 +978,17 @@ fn add_block_mesh(
             [local_x as f32 + 1.0, y as f32 + 1.0, z],
             [local_x as f32, y as f32 + 1.0, z],
         ];
-        let uv = get_block_face_uv(
+        let texture = face_texture(
             block_type,
-            BlockFace::Side,
+            oriented_face(block_type, state, &FACE_DIRECTIONS[0]),
             texture_atlas,
-            chunk,
+            snapshot,
             local_x,
             local_z,
-            y,
-            biome_cache,
-            images,
         );
-        add_face(
-            positions,
-            normals,
-            uvs,
-            indices,
-            current_index,
-            vertices,
-            [0.0, 0.0, 1.0],
-            uv,
-        );
-        current_index += 4;
+        let ao = face_ao(snapshot, &FACE_DIRECTIONS[0], block);
+        let light = face_light(snapshot, &FACE_DIRECTIONS[0], block);
+        buffers.add_face(vertices, [0.0, 0.0, 1.0], texture, ao, light);
     }
 
     // Back face (negative Z) - uses side texture
@@ -477,28 +1000,17 @@ fn add_block_mesh(
             [local_x as f32, y as f32 + 1.0, z],
             [local_x as f32 + 1.0, y as f32 + 1.0, z],
         ];
-        let uv = get_block_face_uv(
+        let texture = face_texture(
             block_type,
-            BlockFace::Side,
+            oriented_face(block_type, state, &FACE_DIRECTIONS[1]),
             texture_atlas,
-            chunk,
+            snapshot,
             local_x,
             local_z,
-            y,
-            biome_cache,
-            images,
-        );
-        add_face(
-            positions,
-            normals,
-            uvs,
-            indices,
-            current_index,
-            vertices,
-            [0.0, 0.0, -1.0],
-            uv,
         );
-        current_index += 4;
+        let ao = face_ao(snapshot, &FACE_DIRECTIONS[1], block);
+        let light = face_light(snapshot, &FACE_DIRECTIONS[1], block);
+        buffers.add_face(vertices, [0.0, 0.0, -1.0], texture, ao, light);
     }
 
     // Right face (positive X) - uses side texture
@@ -510,28 +1022,17 @@ fn add_block_mesh(
             [x, y as f32 + 1.0, local_z as f32],
             [x, y as f32 + 1.0, local_z as f32 + 1.0],
         ];
-        let uv = get_block_face_uv(
+        let texture = face_texture(
             block_type,
-            BlockFace::Side,
+            oriented_face(block_type, state, &FACE_DIRECTIONS[2]),
             texture_atlas,
-            chunk,
+            snapshot,
             local_x,
             local_z,
-            y,
-            biome_cache,
-            images,
-        );
-        add_face(
-            positions,
-            normals,
-            uvs,
-            indices,
-            current_index,
-            vertices,
-            [1.0, 0.0, 0.0],
-            uv,
         );
-        current_index += 4;
+        let ao = face_ao(snapshot, &FACE_DIRECTIONS[2], block);
+        let light = face_light(snapshot, &FACE_DIRECTIONS[2], block);
+        buffers.add_face(vertices, [1.0, 0.0, 0.0], texture, ao, light);
     }
 
     // Left face (negative X) - uses side texture
@@ -543,28 +1044,17 @@ fn add_block_mesh(
             [x, y as f32 + 1.0, local_z as f32 + 1.0],
             [x, y as f32 + 1.0, local_z as f32],
         ];
-        let uv = get_block_face_uv(
+        let texture = face_texture(
             block_type,
-            BlockFace::Side,
+            oriented_face(block_type, state, &FACE_DIRECTIONS[3]),
             texture_atlas,
-            chunk,
+            snapshot,
             local_x,
             local_z,
-            y,
-            biome_cache,
-            images,
         );
-        add_face(
-            positions,
-            normals,
-            uvs,
-            indices,
-            current_index,
-            vertices,
-            [-1.0, 0.0, 0.0],
-            uv,
-        );
-        current_index += 4;
+        let ao = face_ao(snapshot, &FACE_DIRECTIONS[3], block);
+        let light = face_light(snapshot, &FACE_DIRECTIONS[3], block);
+        buffers.add_face(vertices, [-1.0, 0.0, 0.0], texture, ao, light);
     }
 
     // Top face (positive Y) - uses top texture
@@ -576,28 +1066,17 @@ fn add_block_mesh(
             [local_x as f32 + 1.0, y_top, local_z as f32],
             [local_x as f32, y_top, local_z as f32],
         ];
-        let uv = get_block_face_uv(
+        let texture = face_texture(
             block_type,
-            BlockFace::Top,
+            oriented_face(block_type, state, &FACE_DIRECTIONS[4]),
             texture_atlas,
-            chunk,
+            snapshot,
             local_x,
             local_z,
-            y,
-            biome_cache,
-            images,
-        );
-        add_face(
-            positions,
-            normals,
-            uvs,
-            indices,
-            current_index,
-            vertices,
-            [0.0, 1.0, 0.0],
-            uv,
         );
-        current_index += 4;
+        let ao = face_ao(snapshot, &FACE_DIRECTIONS[4], block);
+        let light = face_light(snapshot, &FACE_DIRECTIONS[4], block);
+        buffers.add_face(vertices, [0.0, 1.0, 0.0], texture, ao, light);
     }
 
     // Bottom face (negative Y) - uses bottom texture
@@ -609,34 +1088,452 @@ fn add_block_mesh(
             [local_x as f32 + 1.0, y_bottom, local_z as f32 + 1.0],
             [local_x as f32, y_bottom, local_z as f32 + 1.0],
         ];
-        let uv = get_block_face_uv(
+        let texture = face_texture(
             block_type,
-            BlockFace::Bottom,
+            oriented_face(block_type, state, &FACE_DIRECTIONS[5]),
             texture_atlas,
-            chunk,
+            snapshot,
             local_x,
             local_z,
-            y,
-            biome_cache,
-            images,
-        );
-        add_face(
-            positions,
-            normals,
-            uvs,
-            indices,
-            current_index,
-            vertices,
-            [0.0, -1.0, 0.0],
-            uv,
         );
+        let ao = face_ao(snapshot, &FACE_DIRECTIONS[5], block);
+        let light = face_light(snapshot, &FACE_DIRECTIONS[5], block);
+        buffers.add_face(vertices, [0.0, -1.0, 0.0], texture, ao, light);
     }
 }
 
+/// Texture a face of a block is drawn with
+/// Pillars turn their top and bottom textures towards the axis they lie along.
+fn oriented_face(block_type: BlockType, state: BlockState, direction: &FaceDirection) -> BlockFace {
+    if block_type.shape() != BlockShape::Pillar {
+        return direction.face;
+    }
+    match (direction.axis == state.axis().index(), direction.positive) {
+        (false, _) => BlockFace::Side,
+        (true, true) => BlockFace::Top,
+        (true, false) => BlockFace::Bottom,
+    }
+}
+
+/// Add the boxes of a block that doesn't fill its cell
+/// Box faces on the edge of the cell are left out against opaque neighbours, and fluid faces
+/// against the same fluid standing at least as high; the rest are lit by the light inside the cell.
+fn add_shaped_block_mesh(
+    buffers: &mut MeshBuffers,
+    block: [i32; 3],
+    block_type: BlockType,
+    state: BlockState,
+    texture_atlas: &TextureAtlas,
+    snapshot: &ChunkMeshSnapshot,
+) {
+    let offset = |by: IVec3| [block[0] + by.x, block[1] + by.y, block[2] + by.z];
+    let boxes = block_type.boxes(state, |facing| snapshot.block(offset(facing.offset())));
+    let light = snapshot
+        .light(block)
+        .map_or(FULL_LIGHT, |(sky_light, block_light)| {
+            vertex_light(sky_light as f32, block_light as f32)
+        });
+
+    for shape_box in boxes {
+        let (min, max) = (shape_box.min.to_array(), shape_box.max.to_array());
+        for direction in &FACE_DIRECTIONS {
+            let axis = direction.axis;
+            let on_edge = if direction.positive {
+                max[axis] >= 1.0
+            } else {
+                min[axis] <= 0.0
+            };
+            let mut neighbour = block;
+            neighbour[axis] += if direction.positive { 1 } else { -1 };
+            let same_fluid = block_type.fluid().is_some()
+                && snapshot.block(neighbour) == Some(block_type)
+                && (axis == 1 || snapshot.fluid_height(neighbour) >= max[1]);
+            if on_edge && (snapshot.is_opaque(neighbour) || same_fluid) {
+                continue;
+            }
+
+            let (u_axis, v_axis) = (direction.u_axis.0, direction.v_axis.0);
+            let mut origin = [block[0] as f32, block[1] as f32, block[2] as f32];
+            origin[axis] += if direction.positive {
+                max[axis]
+            } else {
+                min[axis]
+            };
+            origin[u_axis] += min[u_axis];
+            origin[v_axis] += min[v_axis];
+            let key = FaceKey {
+                block_type,
+                texture: face_texture(
+                    block_type,
+                    direction.face,
+                    texture_atlas,
+                    snapshot,
+                    block[0] as usize,
+                    block[2] as usize,
+                ),
+                ao: [3; 4],
+                light: [light; 4],
+            };
+            add_merged_face(
+                buffers,
+                direction,
+                origin,
+                (max[u_axis] - min[u_axis], max[v_axis] - min[v_axis]),
+                key,
+            );
+        }
+    }
+}
+
+/// Where a face's texture comes from and how it's tinted
+#[derive(Clone, Copy, PartialEq)]
+struct FaceTexture {
+    /// UVs at the low and high corners of the face, one unit per block
+    uv: (f32, f32, f32, f32),
+    /// Layer of the block texture array
+    layer: u32,
+    /// Colour of the biome the block stands in
+    tint: [f32; 3],
+}
+
+/// Texture of a block's face in the column at the given local coordinates
+fn face_texture(
+    block_type: BlockType,
+    face: BlockFace,
+    texture_atlas: &TextureAtlas,
+    snapshot: &ChunkMeshSnapshot,
+    local_x: usize,
+    local_z: usize,
+) -> FaceTexture {
+    // Only the blocks biome textures used to be generated for take on the biome's colour
+    let tint = match block_type {
+        BlockType::Grass | BlockType::Dirt | BlockType::Stone | BlockType::Sand => {
+            snapshot.biome_tints[local_z * CHUNK_SIZE + local_x]
+        }
+        _ => [1.0; 3],
+    };
+    FaceTexture {
+        uv: (0.0, 0.0, 1.0, 1.0),
+        layer: texture_atlas.texture_layer(block_type, face),
+        tint,
+    }
+}
+
+/// Texture and shading a face is drawn with; only faces with equal keys can be merged
+#[derive(Clone, Copy, PartialEq)]
+struct FaceKey {
+    block_type: BlockType,
+    texture: FaceTexture,
+    ao: [u8; 4],
+    light: [VertexLight; 4],
+}
+
+impl FaceKey {
+    /// Whether the face can be part of a larger quad: its corners must be equally occluded and lit
+    /// so the shading doesn't stretch
+    fn merges(&self) -> bool {
+        self.ao.iter().all(|&level| level == self.ao[0])
+            && self.light.iter().all(|&light| light == self.light[0])
+    }
+}
+
+/// How the faces of one direction are laid out, in the same vertex order as `add_block_mesh`
+struct FaceDirection {
+    normal: [f32; 3],
+    /// Axis the face points along (0 = x, 1 = y, 2 = z) and whether it faces the positive way
+    axis: usize,
+    positive: bool,
+    /// Axes the quad's UVs run along, and whether each runs the positive way
+    u_axis: (usize, bool),
+    v_axis: (usize, bool),
+    face: BlockFace,
+}
+
+/// Front, back, right, left, top and bottom, matching the quads of `add_block_mesh`
+const FACE_DIRECTIONS: [FaceDirection; 6] = [
+    FaceDirection {
+        normal: [0.0, 0.0, 1.0],
+        axis: 2,
+        positive: true,
+        u_axis: (0, true),
+        v_axis: (1, true),
+        face: BlockFace::Side,
+    },
+    FaceDirection {
+        normal: [0.0, 0.0, -1.0],
+        axis: 2,
+        positive: false,
+        u_axis: (0, false),
+        v_axis: (1, true),
+        face: BlockFace::Side,
+    },
+    FaceDirection {
+        normal: [1.0, 0.0, 0.0],
+        axis: 0,
+        positive: true,
+        u_axis: (2, false),
+        v_axis: (1, true),
+        face: BlockFace::Side,
+    },
+    FaceDirection {
+        normal: [-1.0, 0.0, 0.0],
+        axis: 0,
+        positive: false,
+        u_axis: (2, true),
+        v_axis: (1, true),
+        face: BlockFace::Side,
+    },
+    FaceDirection {
+        normal: [0.0, 1.0, 0.0],
+        axis: 1,
+        positive: true,
+        u_axis: (0, true),
+        v_axis: (2, false),
+        face: BlockFace::Top,
+    },
+    FaceDirection {
+        normal: [0.0, -1.0, 0.0],
+        axis: 1,
+        positive: false,
+        u_axis: (0, true),
+        v_axis: (2, true),
+        face: BlockFace::Bottom,
+    },
+];
+
+/// Visible faces of a chunk, gathered per direction so coplanar ones can be merged
+struct GreedyFaces {
+    min_y: i32,
+    height: usize,
+    /// One entry per block and direction, indexed by `index`
+    faces: [Vec<Option<FaceKey>>; 6],
+}
+
+impl GreedyFaces {
+    fn new(min_y: i32, max_y: i32) -> Self {
+        let height = (max_y - min_y) as usize;
+        Self {
+            min_y,
+            height,
+            faces: std::array::from_fn(|_| vec![None; CHUNK_SIZE * height * CHUNK_SIZE]),
+        }
+    }
+
+    /// Size of the chunk along an axis
+    fn extent(&self, axis: usize) -> usize {
+        if axis == 1 {
+            self.height
+        } else {
+            CHUNK_SIZE
+        }
+    }
+
+    /// Index of a block given as (x, y - min_y, z)
+    fn index(&self, cell: [usize; 3]) -> usize {
+        (cell[0] * self.height + cell[1]) * CHUNK_SIZE + cell[2]
+    }
+
+    /// Record the visible faces of a block
+    #[allow(clippy::too_many_arguments)]
+    fn collect(
+        &mut self,
+        local_x: usize,
+        y: i32,
+        local_z: usize,
+        visibility: &FaceVisibility,
+        block_type: BlockType,
+        state: BlockState,
+        texture_atlas: &TextureAtlas,
+        snapshot: &ChunkMeshSnapshot,
+    ) {
+        let index = self.index([local_x, (y - self.min_y) as usize, local_z]);
+        let visible = [
+            visibility.front,
+            visibility.back,
+            visibility.right,
+            visibility.left,
+            visibility.top,
+            visibility.bottom,
+        ];
+        for (direction_index, (direction, visible)) in
+            FACE_DIRECTIONS.iter().zip(visible).enumerate()
+        {
+            if !visible {
+                continue;
+            }
+            let block = [local_x as i32, y, local_z as i32];
+            self.faces[direction_index][index] = Some(if snapshot.lod == ChunkLod::Full {
+                FaceKey {
+                    block_type,
+                    texture: face_texture(
+                        block_type,
+                        oriented_face(block_type, state, direction),
+                        texture_atlas,
+                        snapshot,
+                        local_x,
+                        local_z,
+                    ),
+                    ao: face_ao(snapshot, direction, block),
+                    light: face_light(snapshot, direction, block),
+                }
+            } else {
+                // Every face of a cell is shaded and tinted alike so it merges into one quad
+                let cell = cell_origin(snapshot, block);
+                FaceKey {
+                    block_type,
+                    texture: face_texture(
+                        block_type,
+                        direction.face,
+                        texture_atlas,
+                        snapshot,
+                        cell[0] as usize,
+                        cell[2] as usize,
+                    ),
+                    ao: [3; 4],
+                    light: [cell_face_light(snapshot, direction, block); 4],
+                }
+            });
+        }
+    }
+
+    /// Merge the collected faces slice by slice and add the resulting quads to the meshes of
+    /// their render layers
+    fn merge(mut self, buffers: &mut HashMap<MeshLayer, MeshBuffers>) {
+        for (direction_index, direction) in FACE_DIRECTIONS.iter().enumerate() {
+            let (u_axis, v_axis) = (direction.u_axis.0, direction.v_axis.0);
+            let (u_size, v_size) = (self.extent(u_axis), self.extent(v_axis));
+
+            for layer in 0..self.extent(direction.axis) {
+                let cell = |u: usize, v: usize| {
+                    let mut cell = [0; 3];
+                    cell[direction.axis] = layer;
+                    cell[u_axis] = u;
+                    cell[v_axis] = v;
+                    cell
+                };
+
+                for v in 0..v_size {
+                    let mut u = 0;
+                    while u < u_size {
+                        let start = self.index(cell(u, v));
+                        let Some(key) = self.faces[direction_index][start] else {
+                            u += 1;
+                            continue;
+                        };
+
+                        // Grow the quad along u, then along v while whole rows match
+                        let matches = |faces: &[Option<FaceKey>], index: usize| {
+                            key.merges() && faces[index] == Some(key)
+                        };
+                        let mut width = 1;
+                        while u + width < u_size
+                            && matches(&self.faces[direction_index], self.index(cell(u + width, v)))
+                        {
+                            width += 1;
+                        }
+                        let mut height = 1;
+                        while v + height < v_size
+                            && (u..u + width).all(|u| {
+                                matches(
+                                    &self.faces[direction_index],
+                                    self.index(cell(u, v + height)),
+                                )
+                            })
+                        {
+                            height += 1;
+                        }
+
+                        // Clear the merged faces so they aren't emitted twice
+                        for dv in 0..height {
+                            for du in 0..width {
+                                let index = self.index(cell(u + du, v + dv));
+                                self.faces[direction_index][index] = None;
+                            }
+                        }
+
+                        let mut origin = [0.0; 3];
+                        origin[direction.axis] =
+                            layer as f32 + if direction.positive { 1.0 } else { 0.0 };
+                        origin[u_axis] = u as f32;
+                        origin[v_axis] = v as f32;
+                        origin[1] += self.min_y as f32;
+                        add_merged_face(
+                            buffers.entry(MeshLayer::of(key.block_type)).or_default(),
+                            direction,
+                            origin,
+                            (width as f32, height as f32),
+                            key,
+                        );
+
+                        u += width;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Corners of a quad in the order (0, 0), (1, 0), (1, 1), (0, 1) of its UV space, as whether each
+/// lies on the high end of the U and V ranges
+const QUAD_CORNERS: [(bool, bool); 4] =
+    [(false, false), (true, false), (true, true), (false, true)];
+
+/// Add a quad covering `size` blocks from the low corner `origin`, tiling its texture once per block
+fn add_merged_face(
+    buffers: &mut MeshBuffers,
+    direction: &FaceDirection,
+    origin: [f32; 3],
+    size: (f32, f32),
+    key: FaceKey,
+) {
+    let vertices = QUAD_CORNERS.map(|(u_high, v_high)| {
+        let mut vertex = origin;
+        let (u_axis, u_positive) = direction.u_axis;
+        let (v_axis, v_positive) = direction.v_axis;
+        if u_high == u_positive {
+            vertex[u_axis] += size.0;
+        }
+        if v_high == v_positive {
+            vertex[v_axis] += size.1;
+        }
+        vertex
+    });
+
+    let uv = key.texture.uv;
+    buffers.add_face(
+        vertices,
+        direction.normal,
+        FaceTexture {
+            uv: (
+                uv.0,
+                uv.1,
+                uv.0 + (uv.2 - uv.0) * size.0,
+                uv.1 + (uv.3 - uv.1) * size.1,
+            ),
+            ..key.texture
+        },
+        key.ao,
+        key.light,
+    );
+}
+
 /// Test function to verify face-specific texture atlas UV coordinates
 #[cfg(test)]
 mod tests {
     use super::*;
+    use bevy::render::mesh::VertexAttributeValues;
+
+    /// Vertex positions of every part of a built mesh
+    fn positions(built: &BuiltChunkMesh) -> Vec<[f32; 3]> {
+        built
+            .parts
+            .iter()
+            .flat_map(|(_, mesh)| match mesh.attribute(Mesh::ATTRIBUTE_POSITION) {
+                Some(VertexAttributeValues::Float32x3(positions)) => positions.clone(),
+                _ => Vec::new(),
+            })
+            .collect()
+    }
 
     #[test]
     fn test_face_specific_texture_uv_coordinates() {
@@ -676,87 +1573,562 @@ mod tests {
         let unknown_uv = texture_atlas.get_uv(BlockType::Air, BlockFace::Top);
         assert_eq!(unknown_uv, (0.0, 0.0, 1.0, 1.0)); // Should return default fallback
     }
-}
 
-/// Get UV coordinates for a block face, using full texture UVs if procedural textures are enabled
-fn get_block_face_uv(
-    block_type: BlockType,
-    face: BlockFace,
-    texture_atlas: &TextureAtlas,
-    chunk: &Chunk,
-    local_x: usize,
-    local_z: usize,
-    y: usize,
-    biome_cache: &crate::biome_texture_cache::SharedBiomeTextureCache,
-    images: &mut ResMut<Assets<Image>>,
-) -> (f32, f32, f32, f32) {
-    // Enhanced biome-based texture selection logic with detailed debugging
-    if texture_atlas.has_procedural_textures() {
-        if let Some(biome_data) = chunk.biome_data.get_biome_data(local_x, local_z) {
-            let biome_params = crate::biome_textures::BiomeTextureParams::new(
-                biome_data.biome_type.clone(),
-                biome_data.temperature,
-                biome_data.moisture,
-                y as f32,
-                y as f32 / 100.0, // Use fixed max height for now
-            );
+    #[test]
+    fn test_snapshot_borders_hide_faces_against_neighbours() {
+        use crate::chunk::WorldHeight;
 
-            // Generate texture key for debugging
-            let texture_key =
-                crate::biome_textures::generate_texture_cache_key(&block_type, &biome_params);
+        let position = ChunkPosition::new(0, 0);
+        let mut chunk = Chunk::new(position, WorldHeight::default());
+        chunk
+            .data
+            .set_block(CHUNK_SIZE - 1, 10, 5, BlockType::Stone);
+        let vertex_count = |built: &BuiltChunkMesh| positions(built).len();
 
-            // Debug: Show biome parameters for this block
-            debug!(
-                "🔍 Biome texture selection for {:?} at ({}, {}, {}) - Biome: {}, Temp: {:.2}, Moist: {:.2}, Height: {}",
-                block_type,
-                local_x,
-                y,
-                local_z,
-                biome_data.biome_type,
-                biome_data.temperature,
-                biome_data.moisture,
-                y
-            );
+        // Without a neighbour loaded the border face is drawn
+        let atlas = TextureAtlas::default();
+        let alone = ChunkMeshSnapshot::capture(&chunk, [None; 4]);
+        let naive = ChunkMeshSettings {
+            greedy_meshing: false,
+        };
+        assert_eq!(
+            vertex_count(&generate_chunk_mesh(&alone, &atlas, &naive)),
+            24
+        );
 
-            // Try to get biome-specific texture first
-            if texture_atlas
-                .get_biome_texture(block_type, &biome_params, biome_cache, images)
-                .is_some()
-            {
-                debug!(
-                    "✓ Using biome-specific texture for {:?} - Key: {}",
-                    block_type, texture_key
-                );
-                // For biome-specific procedural textures, use the entire texture space
-                return (0.0, 0.0, 1.0, 1.0);
-            } else {
-                println!(
-                    "⚠️  No biome-specific texture found for {:?} - Key: {}",
-                    block_type, texture_key
-                );
+        // A solid block across the border hides it, even after the neighbour changes again
+        let [east, ..] = ChunkMeshSnapshot::neighbour_positions(position);
+        let mut neighbour = Chunk::new(east, WorldHeight::default());
+        neighbour.data.set_block(0, 10, 5, BlockType::Stone);
+        let snapshot = ChunkMeshSnapshot::capture(&chunk, [Some(&neighbour), None, None, None]);
+        neighbour.data.set_block(0, 10, 5, BlockType::Air);
+        let built = generate_chunk_mesh(&snapshot, &atlas, &naive);
+        assert_eq!(vertex_count(&built), 20);
+        assert!(matches!(built.parts[..], [(MeshLayer::Opaque, _)]));
+    }
+
+    #[test]
+    fn test_ambient_occlusion_darkens_corners_against_walls() {
+        use crate::chunk::WorldHeight;
+
+        let mut chunk = Chunk::new(ChunkPosition::new(0, 0), WorldHeight::default());
+        chunk.data.set_block(5, 10, 5, BlockType::Stone);
+        let top = &FACE_DIRECTIONS[4];
+        let top_ao = |chunk: &Chunk| {
+            let snapshot = ChunkMeshSnapshot::capture(chunk, [None; 4]);
+            face_ao(&snapshot, top, [5, 10, 5])
+        };
+        assert_eq!(top_ao(&chunk), [3; 4]);
+
+        // A wall on the +x side darkens the two corners along it
+        chunk.data.set_block(6, 11, 5, BlockType::Stone);
+        assert_eq!(top_ao(&chunk), [3, 2, 2, 3]);
+
+        // A block only touching a corner darkens it a little, two walls meeting close it off
+        chunk.data.set_block(6, 11, 6, BlockType::Stone);
+        assert_eq!(top_ao(&chunk), [3, 1, 2, 3]);
+        chunk.data.set_block(5, 11, 6, BlockType::Stone);
+        assert_eq!(top_ao(&chunk), [2, 0, 2, 3]);
+
+        // The face is split along its darker diagonal and carries the shading as vertex colours
+        let mut buffers = MeshBuffers::default();
+        buffers.add_face(
+            [[0.0; 3]; 4],
+            top.normal,
+            FaceTexture {
+                uv: (0.0, 0.0, 1.0, 1.0),
+                layer: 0,
+                tint: [1.0; 3],
+            },
+            [3, 1, 2, 3],
+            [FULL_LIGHT; 4],
+        );
+        assert_eq!(buffers.indices, vec![1, 2, 3, 1, 3, 0]);
+        buffers.add_face(
+            [[0.0; 3]; 4],
+            top.normal,
+            FaceTexture {
+                uv: (0.0, 0.0, 1.0, 1.0),
+                layer: 0,
+                tint: [1.0; 3],
+            },
+            [1, 3, 3, 2],
+            [FULL_LIGHT; 4],
+        );
+        assert_eq!(buffers.indices[6..], [4, 5, 6, 4, 6, 7]);
+        assert_eq!(
+            buffers.colors[1],
+            [AO_BRIGHTNESS[1], AO_BRIGHTNESS[1], AO_BRIGHTNESS[1], 1.0]
+        );
+    }
+
+    #[test]
+    fn test_greedy_meshing_merges_faces_of_representative_chunks() {
+        use crate::chunk::WorldHeight;
+
+        let naive = ChunkMeshSettings {
+            greedy_meshing: false,
+        };
+        let greedy = ChunkMeshSettings::default();
+        let mesh_vertices = |chunk: &Chunk, atlas: &TextureAtlas, settings: &ChunkMeshSettings| {
+            let snapshot = ChunkMeshSnapshot::capture(chunk, [None; 4]);
+            let built = generate_chunk_mesh(&snapshot, atlas, settings);
+            let area = |positions: &[[f32; 3]]| {
+                // Total area of the quads, which both meshers must agree on
+                positions
+                    .chunks(4)
+                    .map(|quad| {
+                        let a = Vec3::from(quad[1]) - Vec3::from(quad[0]);
+                        let b = Vec3::from(quad[3]) - Vec3::from(quad[0]);
+                        a.cross(b).length()
+                    })
+                    .sum::<f32>()
+            };
+            let positions = positions(&built);
+            (positions.len(), area(&positions))
+        };
+
+        // A flat plain of grass over dirt: each side of the slab becomes a single quad
+        let mut plain = Chunk::new(ChunkPosition::new(0, 0), WorldHeight::default());
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                plain.data.set_block(x, 60, z, BlockType::Dirt);
+                plain.data.set_block(x, 61, z, BlockType::Grass);
             }
+        }
+        let atlas = TextureAtlas::default();
+        let (naive_count, naive_area) = mesh_vertices(&plain, &atlas, &naive);
+        let (greedy_count, greedy_area) = mesh_vertices(&plain, &atlas, &greedy);
+        assert_eq!(naive_count, (256 * 2 + 16 * 4 * 2) * 4);
+        // Top, bottom and four sides for each of the two layers
+        assert_eq!(greedy_count, (2 + 4 * 2) * 4);
+        assert_eq!(naive_area, greedy_area);
 
-            // If biome-specific texture not found, try regular procedural texture
-            if texture_atlas.get_procedural_texture(block_type).is_some() {
-                println!("ℹ Using regular procedural texture for {:?}", block_type);
-                // For procedural textures, use the entire texture space
-                return (0.0, 0.0, 1.0, 1.0);
-            } else {
-                println!("⚠️  No procedural texture found for {:?}", block_type);
+        // The merged top repeats the texture once per block
+        let snapshot = ChunkMeshSnapshot::capture(&plain, [None; 4]);
+        let built = generate_chunk_mesh(&snapshot, &atlas, &greedy);
+        let (_, opaque) = &built.parts[0];
+        let Some(VertexAttributeValues::Float32x2(uvs)) = opaque.attribute(Mesh::ATTRIBUTE_UV_0)
+        else {
+            panic!("mesh has no UVs");
+        };
+        assert!(uvs.contains(&[CHUNK_SIZE as f32, CHUNK_SIZE as f32]));
+
+        // Uneven terrain with mixed blocks still merges, and covers the same surface
+        let mut hills = Chunk::new(ChunkPosition::new(0, 0), WorldHeight::default());
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                let top = 60 + ((x / 4 + z / 5) % 3) as i32;
+                for y in 50..top {
+                    hills.data.set_block(x, y, z, BlockType::Stone);
+                }
+                let surface = if x < 8 {
+                    BlockType::Grass
+                } else {
+                    BlockType::Sand
+                };
+                hills.data.set_block(x, top, z, surface);
             }
-        } else {
-            println!(
-                "⚠️  No biome data available at ({}, {}, {})",
-                local_x, y, local_z
-            );
         }
+        let (naive_count, naive_area) = mesh_vertices(&hills, &atlas, &naive);
+        let (greedy_count, greedy_area) = mesh_vertices(&hills, &atlas, &greedy);
+        assert!(
+            greedy_count * 4 < naive_count,
+            "{greedy_count} vs {naive_count}"
+        );
+        assert_eq!(naive_area, greedy_area);
+
+        // Faces with a cell of the atlas image still sample their whole layer, so they merge too
+        let mut atlas_cells = TextureAtlas::default();
+        let mut grass_uvs = std::collections::HashMap::new();
+        for face in [BlockFace::Top, BlockFace::Side, BlockFace::Bottom] {
+            grass_uvs.insert(face, (0.0, 0.0, 0.25, 0.5));
+        }
+        atlas_cells
+            .block_face_uvs
+            .insert(BlockType::Grass, grass_uvs);
+        let (naive_count, _) = mesh_vertices(&plain, &atlas_cells, &naive);
+        let (greedy_count, _) = mesh_vertices(&plain, &atlas_cells, &greedy);
+        assert_eq!(naive_count, (256 * 2 + 16 * 4 * 2) * 4);
+        assert_eq!(greedy_count, (2 + 4 * 2) * 4);
     }
 
-    // For atlas textures, use the original UV mapping with face-specific coordinates
-    let uv = texture_atlas.get_uv(block_type, face);
-    println!(
-        "ℹ Using atlas texture for {:?} - Face: {:?}, UV: ({:.2}, {:.2}, {:.2}, {:.2})",
-        block_type, face, uv.0, uv.1, uv.2, uv.3
-    );
-    uv
+    #[test]
+    fn test_water_and_leaves_get_their_own_sorted_layers() {
+        use crate::chunk::WorldHeight;
+
+        // A 2x2x2 pool of water on a stone floor, and a leaf block beside it
+        let mut chunk = Chunk::new(ChunkPosition::new(0, 0), WorldHeight::default());
+        for x in 4..6 {
+            for z in 4..6 {
+                chunk.data.set_block(x, 9, z, BlockType::Stone);
+                chunk.data.set_block(x, 10, z, BlockType::Water);
+                chunk.data.set_block(x, 11, z, BlockType::Water);
+            }
+        }
+        chunk.data.set_block(6, 10, 4, BlockType::Leaves);
+        let snapshot = ChunkMeshSnapshot::capture(&chunk, [None; 4]);
+        let naive = ChunkMeshSettings {
+            greedy_meshing: false,
+        };
+        let built = generate_chunk_mesh(&snapshot, &TextureAtlas::default(), &naive);
+
+        let mut layers: Vec<_> = built.parts.iter().map(|(layer, _)| *layer).collect();
+        layers.sort_by_key(|layer| *layer as u8);
+        assert_eq!(
+            layers,
+            [MeshLayer::Opaque, MeshLayer::Cutout, MeshLayer::Translucent]
+        );
+
+        // Only the outside of the pool is drawn: no faces between water blocks or against the floor,
+        // but the water still shows through the leaves
+        let (_, water) = built
+            .parts
+            .iter()
+            .find(|(layer, _)| *layer == MeshLayer::Translucent)
+            .unwrap();
+        assert_eq!(water.count_vertices(), (4 + 4 * 4) * 4);
+
+        // Sorting puts the quads furthest from the viewer first
+        let mut water = water.clone();
+        let viewer = Vec3::new(5.0, 30.0, 5.0);
+        sort_quads_back_to_front(&mut water, viewer);
+        let Some(VertexAttributeValues::Float32x3(positions)) =
+            water.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            panic!("mesh has no positions");
+        };
+        let Some(Indices::U32(indices)) = water.indices() else {
+            panic!("mesh has no indices");
+        };
+        let distances: Vec<f32> = indices
+            .chunks(6)
+            .map(|quad| Vec3::from(positions[quad[0] as usize]).distance(viewer))
+            .collect();
+        assert!(distances.first() > distances.last());
+        assert_eq!(positions[indices[indices.len() - 1] as usize][1], 12.0);
+    }
+
+    #[test]
+    fn test_slabs_and_logs_follow_their_state() {
+        use crate::block_state::{Axis, Half};
+        use crate::chunk::WorldHeight;
+
+        // A top slab between a stone floor and a stone wall, and a log lying along x
+        let slab = BlockType::from_key("CobblestoneSlab").unwrap();
+        let mut chunk = Chunk::new(ChunkPosition::new(0, 0), WorldHeight::default());
+        chunk.data.set_block(4, 9, 4, BlockType::Stone);
+        chunk.data.set_block(5, 10, 4, BlockType::Stone);
+        let top_half = BlockState::default().with_half(Half::Top);
+        chunk.data.set_block_with_state(4, 10, 4, slab, top_half);
+        let along_x = BlockState::default().with_axis(Axis::X);
+        chunk
+            .data
+            .set_block_with_state(8, 10, 8, BlockType::Wood, along_x);
+        let snapshot = ChunkMeshSnapshot::capture(&chunk, [None; 4]);
+        let mut atlas = TextureAtlas::default();
+        for (face, layer) in [
+            (BlockFace::Top, 5),
+            (BlockFace::Side, 6),
+            (BlockFace::Bottom, 7),
+        ] {
+            atlas.texture_layers.insert((BlockType::Wood, face), layer);
+        }
+        let built = generate_chunk_mesh(&snapshot, &atlas, &ChunkMeshSettings::default());
+
+        // Every block here is opaque, so they share one mesh
+        let [(MeshLayer::Opaque, mesh)] = &built.parts[..] else {
+            panic!("expected a single opaque mesh");
+        };
+        let Some(VertexAttributeValues::Float32x3(positions)) =
+            mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            panic!("mesh has no positions");
+        };
+        let Some(VertexAttributeValues::Float32x3(normals)) =
+            mesh.attribute(Mesh::ATTRIBUTE_NORMAL)
+        else {
+            panic!("mesh has no normals");
+        };
+        let Some(VertexAttributeValues::Uint32(layers)) = mesh.attribute(ATTRIBUTE_TEXTURE_LAYER)
+        else {
+            panic!("mesh has no texture layers");
+        };
+
+        // The slab's underside floats half a block up: its bottom face and the lower edges of its
+        // three open sides; the side against the wall is hidden, but the wall still shows
+        let slab_bottom = positions
+            .iter()
+            .filter(|p| p[1] == 10.5 && (4.0..=5.0).contains(&p[0]))
+            .count();
+        assert_eq!(slab_bottom, 4 + 3 * 2);
+        assert!(positions
+            .iter()
+            .zip(normals)
+            .any(|(p, normal)| p[0] == 5.0 && p[2] == 4.0 && *normal == [-1.0, 0.0, 0.0]));
+
+        // The log's ends face along x
+        for (normal, layer) in normals.iter().zip(layers).filter(|(_, layer)| **layer >= 5) {
+            let expected = match normal {
+                [1.0, 0.0, 0.0] => 5,
+                [-1.0, 0.0, 0.0] => 7,
+                _ => 6,
+            };
+            assert_eq!(*layer, expected);
+        }
+    }
+
+    #[test]
+    fn test_flowing_fluid_surface_drops_with_its_level() {
+        use crate::chunk::WorldHeight;
+
+        // A water source on a stone floor, flowing three blocks along x
+        let mut chunk = Chunk::new(ChunkPosition::new(0, 0), WorldHeight::default());
+        for x in 4..8 {
+            chunk.data.set_block(x, 9, 4, BlockType::Stone);
+            let state = BlockState::default().with_fluid_level(x as u8 - 4);
+            chunk
+                .data
+                .set_block_with_state(x, 10, 4, BlockType::Water, state);
+        }
+        let snapshot = ChunkMeshSnapshot::capture(&chunk, [None; 4]);
+        let naive = ChunkMeshSettings {
+            greedy_meshing: false,
+        };
+        let built = generate_chunk_mesh(&snapshot, &TextureAtlas::default(), &naive);
+        let (_, water) = built
+            .parts
+            .iter()
+            .find(|(layer, _)| *layer == MeshLayer::Translucent)
+            .unwrap();
+        let Some(VertexAttributeValues::Float32x3(positions)) =
+            water.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            panic!("mesh has no positions");
+        };
+        let Some(VertexAttributeValues::Float32x3(normals)) =
+            water.attribute(Mesh::ATTRIBUTE_NORMAL)
+        else {
+            panic!("mesh has no normals");
+        };
+
+        // Each block's surface is an eighth lower than the one before
+        let top_of = |x: f32| {
+            positions
+                .chunks(4)
+                .zip(normals.chunks(4))
+                .find(|(quad, n)| n[0][1] == 1.0 && quad[0][0].min(quad[2][0]) == x)
+                .map(|(quad, _)| quad[0][1])
+        };
+        assert_eq!(top_of(4.0), Some(11.0));
+        assert_eq!(top_of(5.0), Some(10.875));
+        assert_eq!(top_of(7.0), Some(10.625));
+
+        // The source shows its side above the lower water next to it, which hides its own side
+        // against the source
+        let facing_x: Vec<_> = positions
+            .iter()
+            .zip(normals)
+            .filter(|(p, n)| n[0] != 0.0 && p[0] == 5.0)
+            .map(|(p, n)| (p[1], n[0]))
+            .collect();
+        assert!(facing_x.contains(&(11.0, 1.0)));
+        assert!(!facing_x.iter().any(|&(_, normal)| normal < 0.0));
+    }
+
+    #[test]
+    fn test_vertices_carry_texture_layer_and_biome_tint() {
+        use crate::biome::BiomeRegistry;
+        use crate::chunk::WorldHeight;
+
+        // Grass next to wood, in columns of a tinted biome
+        let biomes = BiomeRegistry::default();
+        let biome = biomes
+            .iter()
+            .find(|biome| biome.tint != (1.0, 1.0, 1.0))
+            .expect("a biome with a tint");
+        let mut chunk = Chunk::new(ChunkPosition::new(0, 0), WorldHeight::default());
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                chunk.biome_data.set_biome_data(x, z, 0.5, 0.5, biome.id);
+            }
+        }
+        chunk.data.set_block(3, 10, 3, BlockType::Grass);
+        chunk.data.set_block(4, 10, 3, BlockType::Wood);
+        let snapshot = ChunkMeshSnapshot::capture(&chunk, [None; 4]).with_biome_tints(&biomes);
+        let mut atlas = TextureAtlas::default();
+        for face in [BlockFace::Top, BlockFace::Side, BlockFace::Bottom] {
+            atlas.texture_layers.insert((BlockType::Grass, face), 2);
+            atlas.texture_layers.insert((BlockType::Wood, face), 3);
+        }
+        let built = generate_chunk_mesh(&snapshot, &atlas, &ChunkMeshSettings::default());
+
+        // Both blocks share one mesh, each vertex naming its own layer and tint
+        let [(MeshLayer::Opaque, mesh)] = &built.parts[..] else {
+            panic!("expected a single opaque mesh");
+        };
+        let Some(VertexAttributeValues::Uint32(layers)) = mesh.attribute(ATTRIBUTE_TEXTURE_LAYER)
+        else {
+            panic!("mesh has no texture layers");
+        };
+        let Some(VertexAttributeValues::Float32x3(tints)) = mesh.attribute(ATTRIBUTE_BIOME_TINT)
+        else {
+            panic!("mesh has no biome tints");
+        };
+        let (r, g, b) = biome.tint;
+        for (layer, tint) in layers.iter().zip(tints) {
+            match layer {
+                2 => assert_eq!(*tint, [r, g, b]),
+                3 => assert_eq!(*tint, [1.0; 3]),
+                _ => panic!("unexpected texture layer {layer}"),
+            }
+        }
+        assert_eq!(layers.iter().filter(|&&layer| layer == 2).count(), 5 * 4);
+    }
+
+    #[test]
+    fn test_daylight_is_applied_by_the_material_not_the_mesh() {
+        use crate::chunk::WorldHeight;
+        use bevy::ecs::system::RunSystemOnce;
+
+        // A lone block under dim skylight and a little block light
+        let mut chunk = Chunk::new(ChunkPosition::new(0, 0), WorldHeight::default());
+        chunk.data.set_block(3, 10, 3, BlockType::Stone);
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                chunk.light.set(LightChannel::Sky, x, 11, z, 12);
+                chunk.light.set(LightChannel::Block, x, 11, z, 5);
+            }
+        }
+        chunk.needs_light_update = false;
+        let snapshot = ChunkMeshSnapshot::capture(&chunk, [None; 4]);
+        let built = generate_chunk_mesh(&snapshot, &TextureAtlas::default(), &default());
+
+        // Skylight and block light reach the vertices apart, untouched by the time of day
+        let (_, mesh) = &built.parts[0];
+        let Some(VertexAttributeValues::Float32x2(lights)) = mesh.attribute(ATTRIBUTE_LIGHT) else {
+            panic!("mesh has no light");
+        };
+        let top = vertex_light(12.0, 5.0);
+        assert_eq!(lights.iter().filter(|&&light| light == top).count(), 4);
+
+        // Nightfall only changes the chunk materials
+        let mut world = World::new();
+        let mut materials = Assets::<ChunkMaterial>::default();
+        let mut mesh_materials = ChunkMeshMaterials::default();
+        mesh_materials.initialize(&mut materials, &TextureAtlas::default());
+        world.insert_resource(materials);
+        world.insert_resource(mesh_materials);
+        world.insert_resource(SkyBrightness(0.2));
+        world.run_system_once(update_chunk_sky_brightness).unwrap();
+        let materials = world.resource::<Assets<ChunkMaterial>>();
+        assert_eq!(materials.len(), 3);
+        assert!(materials
+            .iter()
+            .all(|(_, material)| material.sky_brightness == 0.2));
+    }
+
+    #[test]
+    fn test_far_chunks_mesh_a_coarser_cell_grid() {
+        use crate::chunk::{ChunkManager, ChunkPriority, WorldHeight, DEFAULT_RENDER_DISTANCE};
+
+        // Near chunks keep every block; far ones drop to cells, coarser towards the edge
+        let manager = ChunkManager::new(8, WorldHeight::default());
+        let player = ChunkPosition::new(0, 0);
+        assert_eq!(
+            manager.calculate_chunk_lod(&ChunkPosition::new(3, -2), &player),
+            ChunkLod::Full
+        );
+        assert_eq!(
+            manager.calculate_chunk_lod(&ChunkPosition::new(-6, 1), &player),
+            ChunkLod::Half
+        );
+        assert_eq!(
+            manager.calculate_chunk_lod(&ChunkPosition::new(2, 8), &player),
+            ChunkLod::Quarter
+        );
+        assert_eq!(
+            ChunkLod::for_priority(ChunkPriority::Visible, 8, 8),
+            ChunkLod::Full
+        );
+
+        // At the game's render distance every level shows up, out to the edge
+        let manager = ChunkManager::new(DEFAULT_RENDER_DISTANCE, WorldHeight::default());
+        let lods: Vec<ChunkLod> = (0..=DEFAULT_RENDER_DISTANCE)
+            .map(|distance| manager.calculate_chunk_lod(&ChunkPosition::new(distance, 0), &player))
+            .collect();
+        assert_eq!(
+            lods,
+            [
+                ChunkLod::Full,
+                ChunkLod::Full,
+                ChunkLod::Full,
+                ChunkLod::Full,
+                ChunkLod::Half,
+                ChunkLod::Half,
+                ChunkLod::Quarter
+            ]
+        );
+
+        // Flat ground with a single block standing on it
+        let mut chunk = Chunk::new(ChunkPosition::new(0, 0), WorldHeight::default());
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                for y in 0..10 {
+                    chunk.data.set_block(x, y, z, BlockType::Stone);
+                }
+            }
+        }
+        chunk.data.set_block(3, 10, 3, BlockType::Stone);
+        let atlas = TextureAtlas::default();
+        let settings = ChunkMeshSettings::default();
+        let full = positions(&generate_chunk_mesh(
+            &ChunkMeshSnapshot::capture(&chunk, [None; 4]),
+            &atlas,
+            &settings,
+        ));
+
+        chunk.lod = ChunkLod::Half;
+        let half = positions(&generate_chunk_mesh(
+            &ChunkMeshSnapshot::capture(&chunk, [None; 4]),
+            &atlas,
+            &settings,
+        ));
+        assert!(half.len() < full.len());
+        // The lone block fills too little of its cell to survive, and every face lies on cells
+        assert!(half.iter().all(|position| position[1] <= 10.0));
+        assert!(half
+            .iter()
+            .flatten()
+            .all(|&coordinate| coordinate.rem_euclid(2.0) == 0.0));
+    }
+
+    #[test]
+    fn test_border_faces_stay_as_skirts_between_levels_of_detail() {
+        use crate::chunk::WorldHeight;
+
+        let position = ChunkPosition::new(0, 0);
+        let mut chunk = Chunk::new(position, WorldHeight::default());
+        chunk
+            .data
+            .set_block(CHUNK_SIZE - 1, 10, 5, BlockType::Stone);
+        let [east, ..] = ChunkMeshSnapshot::neighbour_positions(position);
+        let mut neighbour = Chunk::new(east, WorldHeight::default());
+        neighbour.data.set_block(0, 10, 5, BlockType::Stone);
+        let atlas = TextureAtlas::default();
+        let naive = ChunkMeshSettings {
+            greedy_meshing: false,
+        };
+        let vertex_count = |chunk: &Chunk, neighbour: &Chunk| {
+            let snapshot = ChunkMeshSnapshot::capture(chunk, [Some(neighbour), None, None, None]);
+            positions(&generate_chunk_mesh(&snapshot, &atlas, &naive)).len()
+        };
+
+        // Two full-detail chunks hide the faces between them
+        assert_eq!(vertex_count(&chunk, &neighbour), 20);
+
+        // Once the neighbour is meshed coarser, its surface no longer matches along the border
+        neighbour.lod = ChunkLod::Half;
+        assert_eq!(vertex_count(&chunk, &neighbour), 24);
+    }
 }
@@ -0,0 +1,351 @@
+// Palette-compressed block storage for Bevy Craft
+// This module stores a 16x16x16 section of a chunk as a palette plus packed indices
+//
+// Most sections only contain a handful of distinct blocks, so instead of one entry per block
+// each section keeps a small palette of the blocks it contains and a bit-packed array of
+// palette indices. A section with a single entry (all air, all stone, ...) needs no index
+// array at all. Palette entries hold a block together with its state, so two stairs facing
+// different ways are two entries.
+
+use crate::block::BlockType;
+use crate::block_state::BlockState;
+
+/// Edge length of a section in blocks
+pub const SECTION_SIZE: usize = 16;
+pub const SECTION_AREA: usize = SECTION_SIZE * SECTION_SIZE;
+pub const SECTION_VOLUME: usize = SECTION_AREA * SECTION_SIZE;
+
+/// Smallest index width used once a section holds more than one palette entry.
+/// Starting at 4 bits avoids repacking the section for each of the first few new blocks.
+const MIN_BITS_PER_BLOCK: u32 = 4;
+
+/// Widest index a section can hold; palettes never come anywhere near 2^32 entries
+const MAX_BITS_PER_BLOCK: u32 = 32;
+
+/// A block and its state as stored in a palette; `None` is an empty (air) position
+type PaletteEntry = Option<(BlockType, BlockState)>;
+
+/// A 16x16x16 block section stored as a palette and packed palette indices
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ChunkSection {
+    /// Distinct block values in this section
+    palette: Vec<PaletteEntry>,
+    /// Width of each packed palette index; 0 means every block is `palette[0]`
+    bits_per_block: u32,
+    /// Packed palette indices; entries never straddle two words
+    data: Vec<u64>,
+}
+
+impl Default for ChunkSection {
+    fn default() -> Self {
+        Self::filled(None)
+    }
+}
+
+impl ChunkSection {
+    /// Create a section where every position holds the same value
+    pub fn filled(block: Option<BlockType>) -> Self {
+        Self::filled_with(block.map(|block| (block, BlockState::default())))
+    }
+
+    fn filled_with(entry: PaletteEntry) -> Self {
+        Self {
+            palette: vec![entry],
+            bits_per_block: 0,
+            data: Vec::new(),
+        }
+    }
+
+    /// Get the value stored at a section index
+    pub fn get(&self, index: usize) -> Option<BlockType> {
+        self.get_entry(index).map(|(block, _)| block)
+    }
+
+    /// Get the state of the block at a section index; empty positions have the default state
+    pub fn get_state(&self, index: usize) -> BlockState {
+        self.get_entry(index)
+            .map_or(BlockState::default(), |(_, state)| state)
+    }
+
+    fn get_entry(&self, index: usize) -> PaletteEntry {
+        if self.bits_per_block == 0 {
+            return self.palette[0];
+        }
+        self.palette[self.read_index(index)]
+    }
+
+    /// Store a value at a section index in its default state, growing the palette if needed
+    pub fn set(&mut self, index: usize, block: Option<BlockType>) {
+        self.set_with_state(index, block, BlockState::default());
+    }
+
+    /// Store a value and its state at a section index, growing the palette if needed
+    pub fn set_with_state(&mut self, index: usize, block: Option<BlockType>, state: BlockState) {
+        let entry = block.map(|block| (block, state));
+        let palette_index = match self.palette.iter().position(|&existing| existing == entry) {
+            Some(palette_index) => palette_index,
+            None => {
+                self.palette.push(entry);
+                let needed_bits = bits_for_palette_len(self.palette.len());
+                if needed_bits > self.bits_per_block {
+                    self.repack(needed_bits);
+                }
+                self.palette.len() - 1
+            }
+        };
+
+        // Writing palette index 0 into a single-value section is a no-op
+        if self.bits_per_block == 0 {
+            return;
+        }
+        self.write_index(index, palette_index);
+    }
+
+    /// Check that a section read back from disk is consistent, so every block in it can be read
+    pub fn is_valid(&self) -> bool {
+        if self.palette.is_empty() || self.bits_per_block > MAX_BITS_PER_BLOCK {
+            return false;
+        }
+        if self.bits_per_block == 0 {
+            return true;
+        }
+        self.data.len() == words_for_bits(self.bits_per_block)
+            && (0..SECTION_VOLUME).all(|index| self.read_index(index) < self.palette.len())
+    }
+
+    /// Check whether every position in the section is empty
+    pub fn is_empty(&self) -> bool {
+        self.bits_per_block == 0 && self.palette[0].is_none()
+    }
+
+    /// Check whether any block in the palette passes a test, before looking at every block
+    /// Blocks that have since been replaced stay in the palette until the section is compacted.
+    pub fn palette_contains(&self, matches: impl Fn(BlockType, BlockState) -> bool) -> bool {
+        self.palette
+            .iter()
+            .flatten()
+            .any(|&(block, state)| matches(block, state))
+    }
+
+    /// Number of entries in the palette
+    #[allow(dead_code)]
+    pub fn palette_len(&self) -> usize {
+        self.palette.len()
+    }
+
+    /// Drop palette entries that are no longer referenced and shrink the index array.
+    /// Sections that end up with a single value fall back to the fast path with no indices.
+    pub fn compact(&mut self) {
+        if self.bits_per_block == 0 {
+            return;
+        }
+
+        let mut used = vec![false; self.palette.len()];
+        for index in 0..SECTION_VOLUME {
+            used[self.read_index(index)] = true;
+        }
+        if used.iter().all(|&is_used| is_used) {
+            return;
+        }
+
+        let blocks: Vec<PaletteEntry> = (0..SECTION_VOLUME).map(|i| self.get_entry(i)).collect();
+        let palette: Vec<PaletteEntry> = self
+            .palette
+            .iter()
+            .zip(&used)
+            .filter(|(_, is_used)| **is_used)
+            .map(|(entry, _)| *entry)
+            .collect();
+
+        if palette.len() == 1 {
+            *self = Self::filled_with(palette[0]);
+            return;
+        }
+
+        let bits_per_block = bits_for_palette_len(palette.len());
+        *self = Self {
+            palette,
+            bits_per_block,
+            data: vec![0; words_for_bits(bits_per_block)],
+        };
+        for (index, block) in blocks.into_iter().enumerate() {
+            let palette_index = self
+                .palette
+                .iter()
+                .position(|&entry| entry == block)
+                .unwrap();
+            self.write_index(index, palette_index);
+        }
+    }
+
+    /// Approximate heap and inline memory used by this section in bytes
+    pub fn memory_usage(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.palette.capacity() * std::mem::size_of::<PaletteEntry>()
+            + self.data.capacity() * std::mem::size_of::<u64>()
+    }
+
+    /// Re-encode the packed indices with a wider index width
+    fn repack(&mut self, bits_per_block: u32) {
+        let old_section =
+            std::mem::replace(&mut self.data, vec![0; words_for_bits(bits_per_block)]);
+        let old_bits = self.bits_per_block;
+        self.bits_per_block = bits_per_block;
+
+        // A single-value section implicitly stores palette index 0 everywhere
+        if old_bits == 0 {
+            return;
+        }
+
+        let old_per_word = 64 / old_bits as usize;
+        let old_mask = (1u64 << old_bits) - 1;
+        for index in 0..SECTION_VOLUME {
+            let word = old_section[index / old_per_word];
+            let shift = (index % old_per_word) as u32 * old_bits;
+            self.write_index(index, ((word >> shift) & old_mask) as usize);
+        }
+    }
+
+    /// Read the packed palette index at a section index
+    fn read_index(&self, index: usize) -> usize {
+        let per_word = 64 / self.bits_per_block as usize;
+        let shift = (index % per_word) as u32 * self.bits_per_block;
+        let mask = (1u64 << self.bits_per_block) - 1;
+        ((self.data[index / per_word] >> shift) & mask) as usize
+    }
+
+    /// Write a packed palette index at a section index
+    fn write_index(&mut self, index: usize, palette_index: usize) {
+        let per_word = 64 / self.bits_per_block as usize;
+        let shift = (index % per_word) as u32 * self.bits_per_block;
+        let mask = (1u64 << self.bits_per_block) - 1;
+        let word = &mut self.data[index / per_word];
+        *word = (*word & !(mask << shift)) | ((palette_index as u64 & mask) << shift);
+    }
+}
+
+/// Index width needed to address a palette of the given length
+fn bits_for_palette_len(palette_len: usize) -> u32 {
+    if palette_len <= 1 {
+        return 0;
+    }
+    let bits = usize::BITS - (palette_len - 1).leading_zeros();
+    bits.max(MIN_BITS_PER_BLOCK)
+}
+
+/// Number of words needed to store a section's indices at the given width
+fn words_for_bits(bits_per_block: u32) -> usize {
+    let per_word = 64 / bits_per_block as usize;
+    SECTION_VOLUME.div_ceil(per_word)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_single_value_section_uses_no_indices() {
+        let mut section = ChunkSection::filled(Some(BlockType::Stone));
+        assert!(!section.is_empty());
+        assert_eq!(section.get(1234), Some(BlockType::Stone));
+
+        // Writing the value that is already everywhere keeps the fast path
+        section.set(42, Some(BlockType::Stone));
+        assert_eq!(section.palette_len(), 1);
+        assert!(section.data.is_empty());
+
+        assert!(ChunkSection::default().is_empty());
+    }
+
+    #[test]
+    fn test_palette_grows_and_round_trips_blocks() {
+        let blocks = [
+            None,
+            Some(BlockType::Stone),
+            Some(BlockType::Dirt),
+            Some(BlockType::Grass),
+            Some(BlockType::Sand),
+            Some(BlockType::Water),
+            Some(BlockType::Wood),
+            Some(BlockType::Leaves),
+            Some(BlockType::Bedrock),
+            Some(BlockType::Lava),
+            Some(BlockType::Air),
+        ];
+
+        let mut section = ChunkSection::default();
+        for index in 0..SECTION_VOLUME {
+            section.set(index, blocks[(index * 7) % blocks.len()]);
+        }
+
+        assert_eq!(section.palette_len(), blocks.len());
+        for index in 0..SECTION_VOLUME {
+            assert_eq!(section.get(index), blocks[(index * 7) % blocks.len()]);
+        }
+
+        // Packed storage is far smaller than one Option<BlockType> per position
+        assert!(section.memory_usage() < SECTION_VOLUME * std::mem::size_of::<Option<BlockType>>());
+    }
+
+    #[test]
+    fn test_block_states_get_their_own_palette_entries() {
+        use crate::block_state::{Facing, Half};
+
+        let upside_down = BlockState::default()
+            .with_facing(Facing::East)
+            .with_half(Half::Top);
+        let mut section = ChunkSection::default();
+        section.set(0, Some(BlockType::Stone));
+        section.set_with_state(1, Some(BlockType::Stone), upside_down);
+        assert_eq!(section.palette_len(), 3);
+        assert_eq!(section.get(1), Some(BlockType::Stone));
+        assert_eq!(section.get_state(0), BlockState::default());
+        assert_eq!(section.get_state(1), upside_down);
+
+        // Compacting keeps the state of every block
+        section.set(0, None);
+        section.compact();
+        assert_eq!(section.palette_len(), 2);
+        assert_eq!(section.get_state(1), upside_down);
+    }
+
+    #[test]
+    fn test_compact_drops_unused_palette_entries() {
+        let mut section = ChunkSection::default();
+        section.set(0, Some(BlockType::Stone));
+        section.set(1, Some(BlockType::Dirt));
+        section.set(1, None);
+        section.compact();
+        assert_eq!(section.palette_len(), 2);
+        assert_eq!(section.get(0), Some(BlockType::Stone));
+        assert_eq!(section.get(1), None);
+
+        // Clearing the last block collapses back to a single-value section
+        section.set(0, None);
+        section.compact();
+        assert!(section.is_empty());
+    }
+
+    #[test]
+    fn test_damaged_sections_are_not_valid() {
+        // Sections as they'd be decoded from a damaged save
+        let decode = |palette: Vec<PaletteEntry>, bits_per_block: u32, data: Vec<u64>| {
+            let bytes = bincode::serialize(&(palette, bits_per_block, data)).unwrap();
+            bincode::deserialize::<ChunkSection>(&bytes).unwrap()
+        };
+        let stone = Some((BlockType::Stone, BlockState::default()));
+
+        assert!(decode(vec![stone], 0, Vec::new()).is_valid());
+        assert!(!decode(Vec::new(), 0, Vec::new()).is_valid());
+        assert!(!decode(vec![None, stone], 64, vec![0; 4096]).is_valid());
+        assert!(!decode(vec![None, stone], 4, vec![0; 10]).is_valid());
+        let mut data = vec![0; words_for_bits(4)];
+        assert!(decode(vec![None, stone], 4, data.clone()).is_valid());
+        data[7] = 0x20;
+        assert!(!decode(vec![None, stone], 4, data).is_valid());
+
+        let mut section = ChunkSection::default();
+        section.set(100, Some(BlockType::Dirt));
+        assert!(section.is_valid());
+    }
+}
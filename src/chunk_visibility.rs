@@ -0,0 +1,380 @@
+// Chunk visibility for Bevy Craft
+// This module decides which loaded chunks are drawn each frame
+//
+// Every section of a chunk records which of its six faces are joined by a path through open
+// (non-opaque) blocks. Starting from the camera's section, a breadth-first walk steps from section
+// to section through faces joined inside the section it crosses, only into sections inside the
+// camera frustum and never back against a direction it has already moved in. Chunks the walk never
+// reaches are either outside the view or hidden behind terrain, and are not drawn.
+
+use bevy::math::Affine3A;
+use bevy::prelude::*;
+use bevy::render::primitives::{Aabb, Frustum};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::block::BlockType;
+use crate::camera::GameCamera;
+use crate::chunk::{Chunk, ChunkData, ChunkManager, ChunkPosition, CHUNK_SIZE};
+use crate::chunk_mesh::ChunkMesh;
+use crate::chunk_section::SECTION_SIZE;
+
+/// Directions of the six faces of a section: +x, -x, +y, -y, +z, -z
+/// Opposite faces sit next to each other, so `face ^ 1` is the opposite face.
+const FACE_DIRECTIONS: [IVec3; 6] = [
+    IVec3::X,
+    IVec3::NEG_X,
+    IVec3::Y,
+    IVec3::NEG_Y,
+    IVec3::Z,
+    IVec3::NEG_Z,
+];
+
+/// Which pairs of a section's faces can see each other through open blocks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SectionConnectivity(u64);
+
+impl SectionConnectivity {
+    /// A section every face of which sees every other, such as an empty one
+    pub fn open() -> Self {
+        Self(u64::MAX)
+    }
+
+    /// A section no face of which sees any other, such as a solid one
+    pub fn closed() -> Self {
+        Self(0)
+    }
+
+    /// Whether a path of open blocks leads from one face to the other
+    pub fn connects(self, from: usize, to: usize) -> bool {
+        self.0 & (1 << (from * 6 + to)) != 0
+    }
+
+    /// Join every pair of the faces in a mask
+    fn join(&mut self, faces: u8) {
+        for from in 0..6 {
+            for to in 0..6 {
+                if faces & (1 << from) != 0 && faces & (1 << to) != 0 {
+                    self.0 |= 1 << (from * 6 + to);
+                }
+            }
+        }
+    }
+
+    /// Flood fill the open blocks of a section and join the faces each open region touches
+    fn compute(data: &ChunkData, section_index: usize) -> Self {
+        if data.is_section_empty(section_index) {
+            return Self::open();
+        }
+        let min_y = data.section_min_y(section_index);
+        let index = |x: usize, y: usize, z: usize| (y * CHUNK_SIZE + z) * CHUNK_SIZE + x;
+        let is_open = |x: usize, y: usize, z: usize| {
+            data.get_block(x, min_y + y as i32, z)
+                .is_none_or(|block| block == BlockType::Air || block.is_transparent())
+        };
+
+        let mut connectivity = Self::closed();
+        let mut visited = vec![false; CHUNK_SIZE * SECTION_SIZE * CHUNK_SIZE];
+        let mut queue = Vec::new();
+        for start_y in 0..SECTION_SIZE {
+            for start_z in 0..CHUNK_SIZE {
+                for start_x in 0..CHUNK_SIZE {
+                    if visited[index(start_x, start_y, start_z)]
+                        || !is_open(start_x, start_y, start_z)
+                    {
+                        continue;
+                    }
+
+                    // Walk one open region, noting the faces it reaches
+                    let mut faces = 0u8;
+                    visited[index(start_x, start_y, start_z)] = true;
+                    queue.push([start_x, start_y, start_z]);
+                    while let Some(block) = queue.pop() {
+                        for (face, direction) in FACE_DIRECTIONS.iter().enumerate() {
+                            let extent = if face / 2 == 1 {
+                                SECTION_SIZE
+                            } else {
+                                CHUNK_SIZE
+                            };
+                            let axis = face / 2;
+                            let next = block[axis] as i32 + direction[axis];
+                            if next < 0 || next >= extent as i32 {
+                                faces |= 1 << face;
+                                continue;
+                            }
+                            let mut neighbour = block;
+                            neighbour[axis] = next as usize;
+                            let [x, y, z] = neighbour;
+                            if !visited[index(x, y, z)] && is_open(x, y, z) {
+                                visited[index(x, y, z)] = true;
+                                queue.push(neighbour);
+                            }
+                        }
+                    }
+                    connectivity.join(faces);
+                }
+            }
+        }
+        connectivity
+    }
+}
+
+/// Connectivity of every section of a chunk, from the bottom of the world up
+#[derive(Debug, Clone, Default)]
+pub struct ChunkConnectivity {
+    sections: Vec<SectionConnectivity>,
+}
+
+impl ChunkConnectivity {
+    /// Work out the connectivity of a chunk's blocks
+    pub fn compute(data: &ChunkData) -> Self {
+        Self {
+            sections: (0..data.section_count())
+                .map(|section_index| SectionConnectivity::compute(data, section_index))
+                .collect(),
+        }
+    }
+
+    /// Connectivity of a section; sections that haven't been worked out are treated as open
+    pub fn section(&self, section_index: usize) -> SectionConnectivity {
+        self.sections
+            .get(section_index)
+            .copied()
+            .unwrap_or(SectionConnectivity::open())
+    }
+}
+
+/// Number of loaded chunks and how many of them were drawn in the last frame
+#[derive(Resource, Debug, Default)]
+pub struct ChunkVisibilityStats {
+    pub loaded: usize,
+    pub drawn: usize,
+}
+
+/// Chunks with at least one section reached by walking out from the camera's section
+/// `connectivity` gives the loaded chunks the walk may enter, and `in_view` whether a section
+/// is inside the frustum.
+pub fn walk_visible_chunks<'a>(
+    camera_chunk: ChunkPosition,
+    camera_section: usize,
+    section_count: usize,
+    connectivity: impl Fn(ChunkPosition) -> Option<&'a ChunkConnectivity>,
+    in_view: impl Fn(ChunkPosition, usize) -> bool,
+) -> HashSet<ChunkPosition> {
+    let mut visible = HashSet::new();
+    let mut visited = HashSet::new();
+    // Each step remembers the face it came in through and every direction moved in so far
+    let mut queue = VecDeque::new();
+    if connectivity(camera_chunk).is_none() {
+        return visible;
+    }
+    visited.insert((camera_chunk, camera_section));
+    queue.push_back((camera_chunk, camera_section, None::<usize>, 0u8));
+
+    while let Some((position, section, entered, travelled)) = queue.pop_front() {
+        visible.insert(position);
+        let Some(chunk_connectivity) = connectivity(position) else {
+            continue;
+        };
+        let section_connectivity = chunk_connectivity.section(section);
+
+        for (face, direction) in FACE_DIRECTIONS.iter().enumerate() {
+            if travelled & (1 << (face ^ 1)) != 0 {
+                continue;
+            }
+            if entered.is_some_and(|entered| !section_connectivity.connects(entered, face)) {
+                continue;
+            }
+            let next_section = section as i32 + direction.y;
+            if next_section < 0 || next_section >= section_count as i32 {
+                continue;
+            }
+            let next_section = next_section as usize;
+            let next_position =
+                ChunkPosition::new(position.x + direction.x, position.z + direction.z);
+            if connectivity(next_position).is_none()
+                || visited.contains(&(next_position, next_section))
+                || !in_view(next_position, next_section)
+            {
+                continue;
+            }
+            visited.insert((next_position, next_section));
+            queue.push_back((
+                next_position,
+                next_section,
+                Some(face ^ 1),
+                travelled | (1 << face),
+            ));
+        }
+    }
+    visible
+}
+
+/// System to work out which chunks the game camera can see and hide the rest
+/// Fills in `Chunk::is_visible`, and hides the chunk entity so none of its sub-meshes are drawn.
+pub fn update_chunk_visibility(
+    camera: Query<(&GlobalTransform, &Frustum), With<GameCamera>>,
+    chunk_manager: Res<ChunkManager>,
+    mut chunks: Query<(&mut Chunk, Option<&ChunkMesh>, Option<&mut Visibility>)>,
+    mut stats: ResMut<ChunkVisibilityStats>,
+) {
+    let Ok((camera_transform, frustum)) = camera.get_single() else {
+        return;
+    };
+    let world_height = chunk_manager.world_height;
+    let section_count = world_height.section_count();
+
+    let section_in_view = |position: ChunkPosition, section: usize| {
+        let min = position.min_block_position().as_vec3()
+            + Vec3::Y * (world_height.min_y + (section * SECTION_SIZE) as i32) as f32;
+        let max = min + Vec3::new(CHUNK_SIZE as f32, SECTION_SIZE as f32, CHUNK_SIZE as f32);
+        frustum.intersects_obb(
+            &Aabb::from_min_max(min, max),
+            &Affine3A::IDENTITY,
+            true,
+            true,
+        )
+    };
+
+    // Chunks that haven't been meshed yet are walked through as if they were open
+    let unmeshed = ChunkConnectivity::default();
+    let connectivity: HashMap<ChunkPosition, &ChunkConnectivity> = chunks
+        .iter()
+        .map(|(chunk, mesh, _)| {
+            (
+                chunk.position,
+                mesh.map_or(&unmeshed, |mesh| &mesh.connectivity),
+            )
+        })
+        .collect();
+
+    let camera_position = camera_transform.translation();
+    let camera_chunk = ChunkPosition::from_block_position(camera_position.floor().as_ivec3());
+    let camera_section = ((camera_position.y.floor() as i32 - world_height.min_y)
+        / SECTION_SIZE as i32)
+        .clamp(0, section_count as i32 - 1) as usize;
+    let visible = if connectivity.contains_key(&camera_chunk) {
+        walk_visible_chunks(
+            camera_chunk,
+            camera_section,
+            section_count,
+            |position| connectivity.get(&position).copied(),
+            section_in_view,
+        )
+    } else {
+        // Without the camera's own chunk there is nothing to walk from, so only the frustum counts
+        connectivity
+            .keys()
+            .copied()
+            .filter(|&position| {
+                (0..section_count).any(|section| section_in_view(position, section))
+            })
+            .collect()
+    };
+
+    stats.loaded = 0;
+    stats.drawn = 0;
+    for (mut chunk, _, visibility) in &mut chunks {
+        let is_visible = visible.contains(&chunk.position);
+        stats.loaded += 1;
+        if is_visible {
+            stats.drawn += 1;
+        }
+        if chunk.is_visible != is_visible {
+            chunk.is_visible = is_visible;
+        }
+        if let Some(mut visibility) = visibility {
+            let wanted = if is_visible {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            };
+            if *visibility != wanted {
+                *visibility = wanted;
+            }
+        }
+    }
+}
+
+/// System to report how many of the loaded chunks are drawn
+pub fn display_chunk_visibility_stats(stats: Res<ChunkVisibilityStats>, time: Res<Time>) {
+    // Only display stats every 5 seconds to avoid spam
+    if time.elapsed_secs_f64() % 5.0 > 0.1 {
+        return;
+    }
+    println!("👁️  Chunks drawn: {}/{} loaded", stats.drawn, stats.loaded);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::WorldHeight;
+
+    /// Chunk data one section tall, filled with stone where `solid` says so
+    fn section(solid: impl Fn(usize, usize, usize) -> bool) -> ChunkData {
+        let mut data = ChunkData::new(WorldHeight::new(0, SECTION_SIZE as i32));
+        for x in 0..CHUNK_SIZE {
+            for y in 0..SECTION_SIZE {
+                for z in 0..CHUNK_SIZE {
+                    if solid(x, y, z) {
+                        data.set_block(x, y as i32, z, BlockType::Stone);
+                    }
+                }
+            }
+        }
+        data
+    }
+
+    #[test]
+    fn test_tunnel_connects_only_the_faces_it_runs_between() {
+        // Solid stone with a tunnel running along x
+        let tunnel = section(|_, y, z| !(y == 5 && z == 5));
+        let connectivity = ChunkConnectivity::compute(&tunnel).section(0);
+        assert!(connectivity.connects(0, 1));
+        assert!(!connectivity.connects(0, 2));
+        assert!(!connectivity.connects(4, 5));
+
+        assert_eq!(
+            ChunkConnectivity::compute(&section(|_, _, _| true)).section(0),
+            SectionConnectivity::closed()
+        );
+        assert_eq!(
+            ChunkConnectivity::compute(&section(|_, _, _| false)).section(0),
+            SectionConnectivity::open()
+        );
+    }
+
+    #[test]
+    fn test_chunks_behind_solid_terrain_are_not_reached() {
+        // Open chunks on either side of a solid one
+        let open = ChunkConnectivity::compute(&section(|_, _, _| false));
+        let solid = ChunkConnectivity::compute(&section(|_, _, _| true));
+        let chunks: HashMap<ChunkPosition, &ChunkConnectivity> = [
+            (ChunkPosition::new(0, 0), &open),
+            (ChunkPosition::new(1, 0), &solid),
+            (ChunkPosition::new(2, 0), &open),
+            (ChunkPosition::new(0, 1), &open),
+        ]
+        .into_iter()
+        .collect();
+
+        let visible = walk_visible_chunks(
+            ChunkPosition::new(0, 0),
+            0,
+            1,
+            |position| chunks.get(&position).copied(),
+            |_, _| true,
+        );
+        assert!(visible.contains(&ChunkPosition::new(1, 0)));
+        assert!(!visible.contains(&ChunkPosition::new(2, 0)));
+
+        // Sections outside the frustum are never entered
+        let visible = walk_visible_chunks(
+            ChunkPosition::new(0, 0),
+            0,
+            1,
+            |position| chunks.get(&position).copied(),
+            |position, _| position.z == 0,
+        );
+        assert!(!visible.contains(&ChunkPosition::new(0, 1)));
+    }
+}
@@ -0,0 +1,167 @@
+// Command line options for Bevy Craft
+// This module parses the startup arguments that select which world to play and its seed
+//
+// Usage: bevy-craft [--world <name>] [--seed <seed>] [--min-y <y>] [--max-y <y>]
+//                   [--render-distance <chunks>]
+//
+// Numeric seeds are used as-is; any other text is hashed, so `--seed "my map"` works too.
+// Seed and build limits only apply when a world is created; existing worlds keep their own.
+// The render distance is how many chunks are loaded in each direction around the player.
+
+use crate::chunk::{WorldHeight, DEFAULT_RENDER_DISTANCE};
+use crate::world_save::DEFAULT_WORLD_NAME;
+
+/// Options given on the command line at startup
+#[derive(Debug, Clone, PartialEq)]
+pub struct LaunchOptions {
+    /// Name of the world directory inside the saves directory
+    pub world_name: String,
+    /// Seed requested for a new world
+    pub seed: Option<u32>,
+    /// Lowest build height requested for a new world
+    pub min_y: Option<i32>,
+    /// Highest build height (exclusive) requested for a new world
+    pub max_y: Option<i32>,
+    /// Chunks loaded in each direction around the player
+    pub render_distance: i32,
+}
+
+impl Default for LaunchOptions {
+    fn default() -> Self {
+        Self {
+            world_name: DEFAULT_WORLD_NAME.to_string(),
+            seed: None,
+            min_y: None,
+            max_y: None,
+            render_distance: DEFAULT_RENDER_DISTANCE,
+        }
+    }
+}
+
+impl LaunchOptions {
+    /// Parse the options of the running process
+    pub fn from_env() -> Self {
+        Self::parse(std::env::args().skip(1))
+    }
+
+    /// Parse options from a list of arguments, ignoring anything unknown
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Self {
+        let mut options = Self::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            // Accept both `--flag value` and `--flag=value`
+            let (flag, inline_value) = match arg.split_once('=') {
+                Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+                None => (arg, None),
+            };
+
+            match flag.as_str() {
+                "--seed" => match inline_value.or_else(|| args.next()) {
+                    Some(value) => options.seed = Some(parse_seed(&value)),
+                    None => println!("⚠️  --seed needs a value"),
+                },
+                "--world" => match inline_value.or_else(|| args.next()) {
+                    Some(value) if !value.is_empty() => options.world_name = value,
+                    _ => println!("⚠️  --world needs a name"),
+                },
+                "--min-y" => match inline_value.or_else(|| args.next()).map(|v| v.parse()) {
+                    Some(Ok(y)) => options.min_y = Some(y),
+                    _ => println!("⚠️  --min-y needs a whole number"),
+                },
+                "--max-y" => match inline_value.or_else(|| args.next()).map(|v| v.parse()) {
+                    Some(Ok(y)) => options.max_y = Some(y),
+                    _ => println!("⚠️  --max-y needs a whole number"),
+                },
+                "--render-distance" => {
+                    match inline_value.or_else(|| args.next()).map(|v| v.parse()) {
+                        Some(Ok(chunks)) if chunks >= 1 => options.render_distance = chunks,
+                        _ => println!("⚠️  --render-distance needs a number of chunks"),
+                    }
+                }
+                _ => println!("⚠️  Ignoring unknown argument: {}", flag),
+            }
+        }
+
+        options
+    }
+
+    /// Build limits requested on the command line, if any were given
+    pub fn world_height(&self) -> Option<WorldHeight> {
+        if self.min_y.is_none() && self.max_y.is_none() {
+            return None;
+        }
+        let default_height = WorldHeight::default();
+        Some(WorldHeight::new(
+            self.min_y.unwrap_or(default_height.min_y),
+            self.max_y.unwrap_or(default_height.max_y),
+        ))
+    }
+}
+
+/// Turn a seed argument into a numeric seed
+pub fn parse_seed(value: &str) -> u32 {
+    if let Ok(seed) = value.parse::<u32>() {
+        return seed;
+    }
+    if let Ok(seed) = value.parse::<i64>() {
+        return seed as u32;
+    }
+
+    // FNV-1a hash of the text, so named seeds are stable across runs and platforms
+    let mut hash: u32 = 0x811C9DC5;
+    for byte in value.bytes() {
+        hash ^= byte as u32;
+        hash = hash.wrapping_mul(0x01000193);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> LaunchOptions {
+        LaunchOptions::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_world_seed_and_build_limits_are_parsed() {
+        assert_eq!(parse(&[]), LaunchOptions::default());
+        assert_eq!(parse(&[]).world_height(), None);
+
+        let options = parse(&["--world", "island", "--seed=42", "--min-y", "-32"]);
+        assert_eq!(options.world_name, "island");
+        assert_eq!(options.seed, Some(42));
+        assert_eq!(
+            options.world_height(),
+            Some(WorldHeight::new(-32, WorldHeight::default().max_y))
+        );
+        assert_eq!(parse(&["--max-y=128"]).max_y, Some(128));
+        assert_eq!(parse(&["--render-distance", "10"]).render_distance, 10);
+
+        // Missing or malformed values and unknown flags are skipped
+        let options = parse(&[
+            "--world",
+            "",
+            "--min-y",
+            "low",
+            "--fly",
+            "--render-distance=0",
+            "--seed",
+        ]);
+        assert_eq!(options, LaunchOptions::default());
+        assert_eq!(parse(&["--world"]).world_name, DEFAULT_WORLD_NAME);
+    }
+
+    #[test]
+    fn test_text_seeds_are_hashed_with_fnv() {
+        assert_eq!(parse_seed("12345"), 12345);
+        assert_eq!(parse_seed("-1"), u32::MAX);
+        // FNV-1a of the empty string is its offset basis, and of "a" a published test vector
+        assert_eq!(parse_seed(""), 0x811C9DC5);
+        assert_eq!(parse_seed("a"), 0xE40C292C);
+        assert_eq!(parse_seed("my map"), parse_seed("my map"));
+        assert_ne!(parse_seed("my map"), parse_seed("my map 2"));
+    }
+}
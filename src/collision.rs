@@ -1,6 +1,6 @@
 use bevy::prelude::*;
 
-use crate::block::Block;
+use crate::block::{Block, BlockType};
 use crate::chunk::{Chunk, ChunkManager, ChunkPosition};
 use crate::player::Player;
 
@@ -130,7 +130,7 @@ pub fn collision_detection_system(
                             IVec3::new(entity_position.x as i32, y, entity_position.z as i32);
                         if let Some(block_type) = chunk.data.get_block(
                             (test_pos.x.rem_euclid(crate::chunk::CHUNK_SIZE as i32)) as usize,
-                            test_pos.y as usize,
+                            test_pos.y,
                             (test_pos.z.rem_euclid(crate::chunk::CHUNK_SIZE as i32)) as usize,
                         ) {
                             if block_type.is_solid() {
@@ -278,38 +278,43 @@ fn check_chunk_ground_collision(ground_aabb: (Vec3, Vec3), chunk: &Chunk) -> boo
 
     // Convert AABB to chunk-local coordinates
     let chunk_min_pos = chunk.position.min_block_position();
-    let local_min = (ground_min - chunk_min_pos.as_vec3()).as_ivec3();
-    let local_max = (ground_max - chunk_min_pos.as_vec3()).as_ivec3();
+    let local_min = (ground_min - chunk_min_pos.as_vec3()).floor().as_ivec3();
+    let local_max = (ground_max - chunk_min_pos.as_vec3()).floor().as_ivec3();
 
-    // Clamp to chunk boundaries
+    // Clamp to chunk boundaries and build limits
     let start_x = local_min.x.max(0).min(crate::chunk::CHUNK_SIZE as i32 - 1);
     let end_x = local_max.x.max(0).min(crate::chunk::CHUNK_SIZE as i32 - 1);
     let start_y = local_min
         .y
-        .max(0)
-        .min(crate::chunk::CHUNK_HEIGHT as i32 - 1);
+        .max(chunk.data.min_y())
+        .min(chunk.data.max_y() - 1);
     let end_y = local_max
         .y
-        .max(0)
-        .min(crate::chunk::CHUNK_HEIGHT as i32 - 1);
+        .max(chunk.data.min_y())
+        .min(chunk.data.max_y() - 1);
     let start_z = local_min.z.max(0).min(crate::chunk::CHUNK_SIZE as i32 - 1);
     let end_z = local_max.z.max(0).min(crate::chunk::CHUNK_SIZE as i32 - 1);
 
     // Check all blocks in the overlapping region
     for x in start_x..=end_x {
         for y in start_y..=end_y {
+            // Sections without blocks can't collide with anything
+            if chunk.data.is_empty_at(y) {
+                continue;
+            }
             for z in start_z..=end_z {
-                if let Some(block_type) = chunk.data.get_block(x as usize, y as usize, z as usize) {
+                if let Some(block_type) = chunk.data.get_block(x as usize, y, z as usize) {
                     if block_type.is_solid() {
                         let block_world_pos = chunk_min_pos + IVec3::new(x, y, z);
-                        let block_min = block_world_pos.as_vec3();
-                        let block_max = block_world_pos.as_vec3() + Vec3::ONE;
-
-                        if check_aabb_collision(ground_min, ground_max, block_min, block_max) {
-                            // Check if the collision is significant enough
-                            let penetration_y = block_max.y - ground_min.y;
-                            if penetration_y > GROUND_DETECTION_EPSILON {
-                                return true;
+                        for (block_min, block_max) in
+                            block_boxes(chunk, block_world_pos, block_type)
+                        {
+                            if check_aabb_collision(ground_min, ground_max, block_min, block_max) {
+                                // Check if the collision is significant enough
+                                let penetration_y = block_max.y - ground_min.y;
+                                if penetration_y > GROUND_DETECTION_EPSILON {
+                                    return true;
+                                }
                             }
                         }
                     }
@@ -327,38 +332,43 @@ fn check_chunk_block_collisions(entity_aabb: (Vec3, Vec3), chunk: &Chunk) -> Opt
 
     // Convert AABB to chunk-local coordinates
     let chunk_min_pos = chunk.position.min_block_position();
-    let local_min = (entity_min - chunk_min_pos.as_vec3()).as_ivec3();
-    let local_max = (entity_max - chunk_min_pos.as_vec3()).as_ivec3();
+    let local_min = (entity_min - chunk_min_pos.as_vec3()).floor().as_ivec3();
+    let local_max = (entity_max - chunk_min_pos.as_vec3()).floor().as_ivec3();
 
-    // Clamp to chunk boundaries
+    // Clamp to chunk boundaries and build limits
     let start_x = local_min.x.max(0).min(crate::chunk::CHUNK_SIZE as i32 - 1);
     let end_x = local_max.x.max(0).min(crate::chunk::CHUNK_SIZE as i32 - 1);
     let start_y = local_min
         .y
-        .max(0)
-        .min(crate::chunk::CHUNK_HEIGHT as i32 - 1);
+        .max(chunk.data.min_y())
+        .min(chunk.data.max_y() - 1);
     let end_y = local_max
         .y
-        .max(0)
-        .min(crate::chunk::CHUNK_HEIGHT as i32 - 1);
+        .max(chunk.data.min_y())
+        .min(chunk.data.max_y() - 1);
     let start_z = local_min.z.max(0).min(crate::chunk::CHUNK_SIZE as i32 - 1);
     let end_z = local_max.z.max(0).min(crate::chunk::CHUNK_SIZE as i32 - 1);
 
     // Check all blocks in the overlapping region
     for x in start_x..=end_x {
         for y in start_y..=end_y {
+            // Sections without blocks can't collide with anything
+            if chunk.data.is_empty_at(y) {
+                continue;
+            }
             for z in start_z..=end_z {
-                if let Some(block_type) = chunk.data.get_block(x as usize, y as usize, z as usize) {
+                if let Some(block_type) = chunk.data.get_block(x as usize, y, z as usize) {
                     if block_type.is_solid() {
                         let block_world_pos = chunk_min_pos + IVec3::new(x, y, z);
-                        let block_min = block_world_pos.as_vec3();
-                        let block_max = block_world_pos.as_vec3() + Vec3::ONE;
-
-                        if check_aabb_collision(entity_min, entity_max, block_min, block_max) {
-                            if let Some(resolved_pos) =
-                                resolve_aabb_collision(entity_min, entity_max, block_min, block_max)
-                            {
-                                return Some(resolved_pos);
+                        for (block_min, block_max) in
+                            block_boxes(chunk, block_world_pos, block_type)
+                        {
+                            if check_aabb_collision(entity_min, entity_max, block_min, block_max) {
+                                if let Some(resolved_pos) = resolve_aabb_collision(
+                                    entity_min, entity_max, block_min, block_max,
+                                ) {
+                                    return Some(resolved_pos);
+                                }
                             }
                         }
                     }
@@ -370,6 +380,24 @@ fn check_chunk_block_collisions(entity_aabb: (Vec3, Vec3), chunk: &Chunk) -> Opt
     None
 }
 
+/// World space boxes of the block at a position in a chunk, from the shape of its state
+/// Fences only reach out to neighbours within the same chunk.
+fn block_boxes(chunk: &Chunk, world_pos: IVec3, block_type: BlockType) -> Vec<(Vec3, Vec3)> {
+    let state = chunk.get_block_state_world(world_pos);
+    let corner = world_pos.as_vec3();
+    block_type
+        .boxes(state, |facing| {
+            let neighbour = world_pos + facing.offset();
+            chunk
+                .contains(neighbour)
+                .then(|| chunk.get_block_world(neighbour))
+                .flatten()
+        })
+        .into_iter()
+        .map(|shape_box| (corner + shape_box.min, corner + shape_box.max))
+        .collect()
+}
+
 /// Unified collision handling function
 fn handle_collision(
     current_position: Vec3,
@@ -635,7 +663,7 @@ fn find_highest_solid_ground(
             );
 
             // Check from top down to find the highest solid block
-            for y in (0..crate::chunk::CHUNK_HEIGHT).rev() {
+            for y in (chunk.data.min_y()..chunk.data.max_y()).rev() {
                 if let Some(block_type) = chunk.data.get_block(local_x, y, local_z) {
                     if block_type.is_solid() {
                         return y as f32; // Return immediately when we find the highest solid block
@@ -725,7 +753,7 @@ fn check_block_at_position(
         if let Ok(chunk) = chunks.get(chunk_entity) {
             if let Some(block_type) = chunk.data.get_block(
                 (position.x.rem_euclid(crate::chunk::CHUNK_SIZE as i32)) as usize,
-                position.y as usize,
+                position.y,
                 (position.z.rem_euclid(crate::chunk::CHUNK_SIZE as i32)) as usize,
             ) {
                 if block_type.is_solid() {
@@ -756,7 +784,7 @@ fn find_ground_level_in_chunks(
             let local_z = (block_z.rem_euclid(crate::chunk::CHUNK_SIZE as i32)) as usize;
 
             // Check from top down to find the highest solid block
-            for y in (0..crate::chunk::CHUNK_HEIGHT).rev() {
+            for y in (chunk.data.min_y()..chunk.data.max_y()).rev() {
                 if let Some(block_type) = chunk.data.get_block(local_x, y, local_z) {
                     if block_type.is_solid() {
                         return y as f32;
@@ -815,3 +843,39 @@ fn find_empty_space_near_position(
 
     best_position
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block_state::BlockState;
+    use crate::chunk::WorldHeight;
+
+    #[test]
+    fn test_entities_collide_with_the_shape_of_blocks() {
+        let mut chunk = Chunk::new(ChunkPosition::new(0, 0), WorldHeight::default());
+        let slab = BlockType::from_key("CobblestoneSlab").unwrap();
+        chunk
+            .data
+            .set_block_with_state(2, 5, 2, slab, BlockState::default());
+        let size = Vec3::new(0.6, 1.8, 0.6);
+        let at = |min: Vec3| (min, min + size);
+
+        // Standing on a bottom slab is free; sinking into it pushes the entity back up
+        assert_eq!(
+            check_chunk_block_collisions(at(Vec3::new(2.2, 5.55, 2.2)), &chunk),
+            None
+        );
+        let resolved = check_chunk_block_collisions(at(Vec3::new(2.2, 5.4, 2.2)), &chunk)
+            .expect("entity sinking into the slab collides");
+        assert!(resolved.y > 5.4 + size.y / 2.0);
+
+        // A fence post only blocks its middle
+        let fence = BlockType::from_key("WoodFence").unwrap();
+        chunk.data.set_block(6, 5, 6, fence);
+        assert_eq!(
+            check_chunk_block_collisions(at(Vec3::new(6.65, 5.0, 6.0)), &chunk),
+            None
+        );
+        assert!(check_chunk_block_collisions(at(Vec3::new(6.2, 5.0, 6.2)), &chunk).is_some());
+    }
+}
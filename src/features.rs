@@ -0,0 +1,332 @@
+// Feature placement for Bevy Craft
+// This module grows trees and other decorations on top of freshly generated terrain
+//
+// Features are rooted in the chunk being generated but may reach into its neighbours. Blocks
+// that land in a neighbour are queued in `PendingFeatureBlocks` and written once that chunk is
+// generated and loaded, so a tree on a chunk border keeps all of its leaves. The queue is stored
+// with the world save, so blocks waiting for chunks nobody has visited yet survive a restart.
+// Feature blocks only fill empty space, except that trunks grow through leaves, so a chunk ends
+// up the same whichever order its neighbours are generated in.
+
+use bevy::prelude::*;
+use rand::Rng;
+use std::collections::HashMap;
+
+use crate::biome::BiomeRegistry;
+use crate::block::BlockType;
+use crate::caves::chunk_rng;
+use crate::chunk::{Chunk, ChunkPosition, CHUNK_SIZE};
+use crate::world_gen::WorldGenSettings;
+
+/// Seed channel for tree placement
+const TREE_SEED_CHANNEL: u32 = 30;
+
+/// Trees keep at least this many blocks between trunks
+const TREE_SPACING: i32 = 3;
+
+/// Blocks generated for chunks that weren't ready when a neighbouring feature was placed
+#[derive(Resource, Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PendingFeatureBlocks {
+    /// Blocks waiting for each chunk, as world positions
+    pub blocks: HashMap<ChunkPosition, Vec<(IVec3, BlockType)>>,
+}
+
+impl PendingFeatureBlocks {
+    /// Queue a block for a chunk that isn't generated yet
+    pub fn push(&mut self, position: IVec3, block_type: BlockType) {
+        self.blocks
+            .entry(ChunkPosition::from_block_position(position))
+            .or_default()
+            .push((position, block_type));
+    }
+
+    /// Write every block waiting for a chunk into it
+    /// Returns true if anything was waiting.
+    pub fn apply_to_chunk(&mut self, chunk: &mut Chunk) -> bool {
+        let Some(blocks) = self.blocks.remove(&chunk.position) else {
+            return false;
+        };
+        for (position, block_type) in blocks {
+            place_feature_block(chunk, position, block_type);
+        }
+        true
+    }
+}
+
+/// Shape of tree grown in a biome
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum TreeKind {
+    /// Short trunk with a round canopy
+    Oak,
+    /// Tall trunk with narrow, layered leaves
+    Pine,
+}
+
+/// Grow trees on the grass of a freshly generated chunk
+/// Which trees grow and how often comes from each column's biome. Blocks that fall outside the
+/// chunk are queued in `pending` for the neighbour they belong to.
+pub fn place_trees(
+    chunk: &mut Chunk,
+    settings: &WorldGenSettings,
+    biomes: &BiomeRegistry,
+    pending: &mut PendingFeatureBlocks,
+) {
+    if settings.tree_density <= 0.0 {
+        return;
+    }
+
+    let mut rng = chunk_rng(settings.seed, TREE_SEED_CHANNEL, chunk.position);
+    let chunk_min = chunk.position.min_block_position();
+    let mut trunks: Vec<IVec3> = Vec::new();
+
+    for local_x in 0..CHUNK_SIZE {
+        for local_z in 0..CHUNK_SIZE {
+            // Roll for every column so the layout doesn't depend on earlier columns' terrain
+            let roll: f32 = rng.gen_range(0.0..1.0);
+            let trunk_height = rng.gen_range(4..=6);
+
+            let Some(biome_data) = chunk.biome_data.get_biome_data(local_x, local_z) else {
+                continue;
+            };
+            let Some((kind, chance)) = biomes.get(biome_data.biome).trees() else {
+                continue;
+            };
+            if roll >= chance * settings.tree_density {
+                continue;
+            }
+
+            let Some(surface_y) = grass_surface(chunk, local_x, local_z) else {
+                continue;
+            };
+            let base = chunk_min + IVec3::new(local_x as i32, surface_y + 1, local_z as i32);
+            if trunks.iter().any(|trunk| {
+                (trunk.x - base.x).abs() < TREE_SPACING && (trunk.z - base.z).abs() < TREE_SPACING
+            }) {
+                continue;
+            }
+
+            let trunk_height = match kind {
+                TreeKind::Oak => trunk_height,
+                TreeKind::Pine => trunk_height + 2,
+            };
+            if base.y + trunk_height + 2 >= chunk.data.max_y() {
+                continue;
+            }
+
+            // Trees grow out of dirt, not grass
+            chunk
+                .data
+                .set_block(local_x, surface_y, local_z, BlockType::Dirt);
+            for (position, block_type) in tree_blocks(kind, base, trunk_height) {
+                if ChunkPosition::from_block_position(position) == chunk.position {
+                    place_feature_block(chunk, position, block_type);
+                } else {
+                    pending.push(position, block_type);
+                }
+            }
+            trunks.push(base);
+        }
+    }
+}
+
+/// Height of the topmost block in a column if it is grass
+fn grass_surface(chunk: &Chunk, local_x: usize, local_z: usize) -> Option<i32> {
+    (chunk.data.min_y()..chunk.data.max_y())
+        .rev()
+        .find_map(|y| match chunk.data.get_block(local_x, y, local_z) {
+            None | Some(BlockType::Air) => None,
+            Some(block_type) => Some((y, block_type)),
+        })
+        .and_then(|(y, block_type)| (block_type == BlockType::Grass).then_some(y))
+}
+
+/// Blocks making up a tree whose trunk starts at `base`
+/// Trunk blocks come first so leaves never replace them.
+fn tree_blocks(kind: TreeKind, base: IVec3, trunk_height: i32) -> Vec<(IVec3, BlockType)> {
+    let mut blocks: Vec<(IVec3, BlockType)> = (0..trunk_height)
+        .map(|dy| (base + IVec3::Y * dy, BlockType::Wood))
+        .collect();
+    let top = base.y + trunk_height;
+
+    match kind {
+        TreeKind::Oak => {
+            // Two wide layers around the top of the trunk, then two narrow ones above it
+            for y in (top - 2)..=(top + 1) {
+                let radius: i32 = if y < top { 2 } else { 1 };
+                for dx in -radius..=radius {
+                    for dz in -radius..=radius {
+                        // Trim the corners of all but the lowest layer for a rounder canopy
+                        if dx.abs() == radius && dz.abs() == radius && (radius == 1 || y > top - 2)
+                        {
+                            continue;
+                        }
+                        blocks.push((IVec3::new(base.x + dx, y, base.z + dz), BlockType::Leaves));
+                    }
+                }
+            }
+        }
+        TreeKind::Pine => {
+            // Layers shrink towards the top, alternating wide and narrow
+            for (layer, y) in ((base.y + 2)..=(top + 1)).enumerate() {
+                let remaining = top + 1 - y;
+                let radius: i32 = if remaining == 0 {
+                    0
+                } else if layer % 2 == 0 {
+                    (remaining / 2).clamp(1, 2)
+                } else {
+                    1
+                };
+                for dx in -radius..=radius {
+                    for dz in -radius..=radius {
+                        if radius > 1 && dx.abs() == radius && dz.abs() == radius {
+                            continue;
+                        }
+                        blocks.push((IVec3::new(base.x + dx, y, base.z + dz), BlockType::Leaves));
+                    }
+                }
+            }
+        }
+    }
+
+    blocks
+}
+
+/// Write a feature block into a chunk, only filling empty positions or replacing leaves with wood
+fn place_feature_block(chunk: &mut Chunk, position: IVec3, block_type: BlockType) {
+    let local = position - chunk.position.min_block_position();
+    let (local_x, local_z) = (local.x as usize, local.z as usize);
+    let replaceable = match chunk.data.get_block(local_x, position.y, local_z) {
+        None | Some(BlockType::Air) => true,
+        Some(BlockType::Leaves) => block_type == BlockType::Wood,
+        Some(_) => false,
+    };
+    if replaceable && position.y >= chunk.data.min_y() && position.y < chunk.data.max_y() {
+        chunk
+            .data
+            .set_block(local_x, position.y, local_z, block_type);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::WorldHeight;
+
+    /// Flat chunk of one biome with grass at y = 10
+    fn flat_chunk(position: ChunkPosition, biome: &str) -> Chunk {
+        let biome = BiomeRegistry::builtin().by_name(biome).unwrap().id;
+        let mut chunk = Chunk::new(position, WorldHeight::default());
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                for y in 0..10 {
+                    chunk.data.set_block(x, y, z, BlockType::Dirt);
+                }
+                chunk.data.set_block(x, 10, z, BlockType::Grass);
+                chunk.biome_data.set_biome_data(x, z, 0.5, 0.8, biome);
+            }
+        }
+        chunk
+    }
+
+    #[test]
+    fn test_trees_grow_in_forest_and_spill_into_neighbours() {
+        let settings = WorldGenSettings {
+            seed: 7,
+            tree_density: 4.0,
+            ..default()
+        };
+        let mut pending = PendingFeatureBlocks::default();
+        let mut chunk = flat_chunk(ChunkPosition::new(0, 0), "forest");
+        place_trees(
+            &mut chunk,
+            &settings,
+            &BiomeRegistry::builtin(),
+            &mut pending,
+        );
+
+        let mut wood = 0;
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                if chunk.data.get_block(x, 11, z) == Some(BlockType::Wood) {
+                    wood += 1;
+                    // The grass under a trunk turns to dirt
+                    assert_eq!(chunk.data.get_block(x, 10, z), Some(BlockType::Dirt));
+                }
+            }
+        }
+        assert!(wood > 0, "expected trees in a dense forest");
+
+        // Leaves reaching past the chunk edge wait for the neighbour to generate
+        assert!(!pending.blocks.is_empty());
+        assert!(!pending.blocks.contains_key(&chunk.position));
+        let (&neighbour_pos, blocks) = pending.blocks.iter().next().unwrap();
+        let waiting = blocks.len();
+        let mut neighbour = Chunk::new(neighbour_pos, WorldHeight::default());
+        assert!(pending.apply_to_chunk(&mut neighbour));
+        assert!(!pending.blocks.contains_key(&neighbour_pos));
+
+        let placed = blocks_in(&neighbour);
+        assert!(placed > 0 && placed <= waiting);
+    }
+
+    #[test]
+    fn test_spilled_blocks_land_the_same_in_any_order() {
+        // Leaves from one neighbour and a trunk from another reaching the same cell
+        let position = IVec3::new(20, 40, 3);
+        let mut leaves = PendingFeatureBlocks::default();
+        leaves.push(position, BlockType::Leaves);
+        leaves.push(position + IVec3::Y, BlockType::Leaves);
+        let mut trunk = PendingFeatureBlocks::default();
+        trunk.push(position, BlockType::Wood);
+
+        let chunk_pos = ChunkPosition::from_block_position(position);
+        let mut leaves_first = Chunk::new(chunk_pos, WorldHeight::default());
+        let mut trunk_first = Chunk::new(chunk_pos, WorldHeight::default());
+        for blocks in [&leaves, &trunk] {
+            assert!(blocks.clone().apply_to_chunk(&mut leaves_first));
+        }
+        for blocks in [&trunk, &leaves] {
+            assert!(blocks.clone().apply_to_chunk(&mut trunk_first));
+        }
+
+        let local = position - chunk_pos.min_block_position();
+        for chunk in [&leaves_first, &trunk_first] {
+            let block = |y| chunk.data.get_block(local.x as usize, y, local.z as usize);
+            assert_eq!(block(position.y), Some(BlockType::Wood));
+            assert_eq!(block(position.y + 1), Some(BlockType::Leaves));
+        }
+    }
+
+    #[test]
+    fn test_no_trees_in_desert() {
+        let settings = WorldGenSettings {
+            seed: 7,
+            tree_density: 4.0,
+            ..default()
+        };
+        let mut pending = PendingFeatureBlocks::default();
+        let mut chunk = flat_chunk(ChunkPosition::new(3, 1), "desert");
+        place_trees(
+            &mut chunk,
+            &settings,
+            &BiomeRegistry::builtin(),
+            &mut pending,
+        );
+        assert_eq!(blocks_in(&chunk), CHUNK_SIZE * CHUNK_SIZE * 11);
+        assert!(pending.blocks.is_empty());
+    }
+
+    fn blocks_in(chunk: &Chunk) -> usize {
+        let mut count = 0;
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                for y in chunk.data.min_y()..chunk.data.max_y() {
+                    if chunk.data.get_block(x, y, z).is_some() {
+                        count += 1;
+                    }
+                }
+            }
+        }
+        count
+    }
+}
@@ -0,0 +1,245 @@
+// Fluid system for Bevy Craft
+// This module makes water and lava flow
+//
+// A fluid block is either a source, which stays put until something replaces it, or flowing fluid
+// fed by the fluid around it. Flowing fluid keeps how far it is from a source as its level in the
+// block's state and runs one block further each step, until it reaches the `spread` of its
+// registry entry. Fluid pours straight down wherever it can, as falling fluid, and only spreads
+// sideways once it lands. When nothing feeds flowing fluid any more it dries up, step by step.
+//
+// Steps are block updates scheduled through `ChunkManager`, `tick_delay` ticks apart, so water
+// runs quickly and lava slowly. Updates are kept by world position, so a flow carries on across
+// chunk borders, and waits at the edge of the loaded world until the chunk beyond it loads; see
+// `block_update`.
+//
+// Where water touches lava, a lava source hardens into stone and flowing lava into cobblestone;
+// lava running into water turns the water into stone.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::block::BlockType;
+use crate::block_state::{BlockState, Facing};
+use crate::block_update::BlockWorld;
+
+/// How a fluid block flows, from its entry in the block registry
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FluidDefinition {
+    /// How many blocks the fluid runs across flat ground from a source
+    pub spread: u8,
+    /// Ticks each step of the flow takes
+    pub tick_delay: u32,
+}
+
+impl FluidDefinition {
+    /// Height of the fluid's surface within its cell, from 0 to 1
+    /// Sources and falling fluid fill their cell; flowing fluid drops lower the further it runs.
+    pub fn height(&self, state: BlockState) -> f32 {
+        if state.is_falling() {
+            return 1.0;
+        }
+        1.0 - state.fluid_level() as f32 / (self.spread as f32 + 1.0)
+    }
+}
+
+/// Take one step of the flow of the fluid at a position
+/// Returns the positions whose blocks changed.
+pub fn update_fluid(world: &mut impl BlockWorld, position: IVec3) -> Vec<IVec3> {
+    let mut changed = Vec::new();
+    let Some((block_type, state)) = world.block(position) else {
+        return changed;
+    };
+    let Some(fluid) = block_type.fluid() else {
+        return changed;
+    };
+
+    // Water touching lava from the side or above hardens it
+    if block_type == BlockType::Lava && touches_water(world, position) {
+        let hardened = if state.is_fluid_source() {
+            BlockType::Stone
+        } else {
+            BlockType::Cobblestone
+        };
+        world.set_block(position, hardened, BlockState::default());
+        changed.push(position);
+        return changed;
+    }
+
+    // Flowing fluid takes the level the fluid around it feeds it, and dries up when nothing does
+    let state = if state.is_fluid_source() {
+        state
+    } else {
+        match fed_state(world, position, block_type, fluid) {
+            None => {
+                world.set_block(position, BlockType::Air, BlockState::default());
+                changed.push(position);
+                return changed;
+            }
+            Some(fed) => {
+                if fed != state {
+                    world.set_block(position, block_type, fed);
+                    changed.push(position);
+                }
+                fed
+            }
+        }
+    };
+
+    // Fluid pours down wherever it can, and only spreads sideways once it lands
+    let below = position - IVec3::Y;
+    match world.block(below) {
+        None => return changed,
+        Some((below_type, _)) if below_type == BlockType::Air => {
+            world.set_block(below, block_type, BlockState::default().with_falling(true));
+            changed.push(below);
+            return changed;
+        }
+        Some((below_type, _)) if below_type.fluid().is_some() => {
+            if block_type == BlockType::Lava && below_type == BlockType::Water {
+                world.set_block(below, BlockType::Stone, BlockState::default());
+                changed.push(below);
+            }
+            return changed;
+        }
+        Some(_) => {}
+    }
+
+    let level = if state.is_falling() {
+        0
+    } else {
+        state.fluid_level()
+    };
+    if level >= fluid.spread {
+        return changed;
+    }
+    let flowing = BlockState::default().with_fluid_level(level + 1);
+    for facing in Facing::ALL {
+        let side = position + facing.offset();
+        match world.block(side) {
+            Some((side_type, _)) if side_type == BlockType::Air => {
+                world.set_block(side, block_type, flowing);
+                changed.push(side);
+            }
+            Some((side_type, _))
+                if block_type == BlockType::Lava && side_type == BlockType::Water =>
+            {
+                world.set_block(side, BlockType::Stone, BlockState::default());
+                changed.push(side);
+            }
+            _ => {}
+        }
+    }
+    changed
+}
+
+/// Whether water touches a block from the side or from above
+fn touches_water(world: &impl BlockWorld, position: IVec3) -> bool {
+    Facing::ALL
+        .iter()
+        .map(|facing| facing.offset())
+        .chain([IVec3::Y])
+        .any(|by| {
+            world
+                .block(position + by)
+                .is_some_and(|(block_type, _)| block_type == BlockType::Water)
+        })
+}
+
+/// State the fluid around a flowing block feeds it, or None if nothing does
+/// Fluid above makes it fall; otherwise it's one level further than the closest neighbour that
+/// spreads sideways, which takes something other than air or the same fluid under it.
+fn fed_state(
+    world: &impl BlockWorld,
+    position: IVec3,
+    block_type: BlockType,
+    fluid: FluidDefinition,
+) -> Option<BlockState> {
+    if world
+        .block(position + IVec3::Y)
+        .is_some_and(|(above, _)| above == block_type)
+    {
+        return Some(BlockState::default().with_falling(true));
+    }
+
+    Facing::ALL
+        .into_iter()
+        .filter_map(|facing| {
+            let side = position + facing.offset();
+            let (side_type, side_state) = world.block(side)?;
+            let spreads = world
+                .block(side - IVec3::Y)
+                .is_some_and(|(below, _)| below != BlockType::Air && below != block_type);
+            (side_type == block_type && spreads).then_some(if side_state.is_falling() {
+                0
+            } else {
+                side_state.fluid_level()
+            })
+        })
+        .min()
+        .map(|level| level + 1)
+        .filter(|&level| level <= fluid.spread)
+        .map(|level| BlockState::default().with_fluid_level(level))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block_update::TestWorld;
+
+    #[test]
+    fn test_water_spreads_from_its_source_and_dries_up_without_it() {
+        let mut world = TestWorld::default();
+        let source = IVec3::new(0, 1, 0);
+        world.place_and_settle(source, BlockType::Water);
+
+        let water = BlockType::Water.fluid().unwrap();
+        for distance in 1..=water.spread as i32 {
+            let (block_type, state) = world.block(source + IVec3::X * distance).unwrap();
+            assert_eq!(block_type, BlockType::Water);
+            assert_eq!(state.fluid_level(), distance as u8);
+            assert!(water.height(state) < 1.0);
+        }
+        assert_eq!(
+            world.block_type(source + IVec3::X * (water.spread as i32 + 1)),
+            BlockType::Air
+        );
+        // Diagonals are reached around the corner, one level further each step
+        let (_, corner) = world.block(source + IVec3::new(2, 0, 3)).unwrap();
+        assert_eq!(corner.fluid_level(), 5);
+
+        // Lava doesn't run as far
+        let lava = BlockType::Lava.fluid().unwrap();
+        assert!(lava.spread < water.spread && lava.tick_delay > water.tick_delay);
+
+        world.place_and_settle(source, BlockType::Air);
+        assert!(world
+            .blocks
+            .values()
+            .all(|&(block_type, _)| block_type == BlockType::Air));
+    }
+
+    #[test]
+    fn test_water_and_lava_meet_as_stone() {
+        let mut world = TestWorld::default();
+        let lava = IVec3::new(0, 1, 0);
+        world.place_and_settle(lava, BlockType::Lava);
+        assert_eq!(world.block_type(lava + IVec3::X * 3), BlockType::Lava);
+        assert_eq!(world.block_type(lava + IVec3::X * 4), BlockType::Air);
+
+        // Water poured onto the lava hardens the flowing lava under it into cobblestone, then
+        // spreads over it to the source, which turns to stone
+        world.place_and_settle(IVec3::new(0, 2, 1), BlockType::Water);
+        assert_eq!(world.block_type(lava + IVec3::Z), BlockType::Cobblestone);
+        assert_eq!(world.block_type(lava), BlockType::Stone);
+        assert!(world
+            .blocks
+            .values()
+            .all(|&(block_type, _)| block_type != BlockType::Lava));
+
+        // Lava poured onto water turns it to stone
+        let mut world = TestWorld::default();
+        world.place_and_settle(IVec3::new(0, 1, 0), BlockType::Water);
+        world.place_and_settle(IVec3::new(0, 3, 0), BlockType::Lava);
+        assert_eq!(world.block_type(IVec3::new(0, 1, 0)), BlockType::Stone);
+    }
+}
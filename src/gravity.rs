@@ -0,0 +1,324 @@
+// Falling blocks for Bevy Craft
+// This module lets sand and other blocks with gravity fall
+//
+// Blocks marked with `gravity` in the block registry need something solid under them; air and
+// fluids don't hold them up. A block update that finds one unsupported takes it out of its chunk
+// and spawns it as a `FallingBlock` entity, which falls with its own velocity until it meets a
+// solid block and then settles back into the chunk in the cell above, or the nearest free cell
+// over it if something has filled that in the meantime. Taking the block out is a change like any
+// other, so the block resting on it is checked on a following tick and a whole column comes down
+// one block after another.
+//
+// Falling blocks aren't saved themselves: when the game exits, each one still in the air is put
+// straight into the cell it would land in, before the chunks are saved.
+
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+use crate::block::{BlockRegistry, BlockType};
+use crate::block_state::BlockState;
+use crate::block_update::{BlockWorld, LoadedChunks};
+use crate::chunk::{Chunk, ChunkManager};
+
+/// Acceleration of falling blocks, in blocks per second squared, the same as the player's
+const FALLING_BLOCK_GRAVITY: f32 = 20.0;
+
+/// Fastest a block falls, in blocks per second
+const MAX_FALL_SPEED: f32 = 40.0;
+
+/// Furthest a falling block moves before checking what's under it again, in blocks
+const MAX_FALL_STEP: f32 = 0.5;
+
+/// A block with gravity on its way down
+#[derive(Component, Debug, Clone)]
+pub struct FallingBlock {
+    pub block_type: BlockType,
+    pub state: BlockState,
+    /// Low corner of the block's cell as it falls
+    pub position: Vec3,
+    /// Speed downwards, in blocks per second
+    pub velocity: f32,
+}
+
+/// Mesh and materials falling blocks are drawn with
+#[derive(Resource)]
+pub struct FallingBlockAssets {
+    mesh: Handle<Mesh>,
+    materials: HashMap<BlockType, Handle<StandardMaterial>>,
+}
+
+impl FromWorld for FallingBlockAssets {
+    fn from_world(world: &mut World) -> Self {
+        let mesh = world
+            .resource_mut::<Assets<Mesh>>()
+            .add(Cuboid::new(1.0, 1.0, 1.0));
+        let mut material_assets = world.resource_mut::<Assets<StandardMaterial>>();
+        let materials = BlockRegistry::global()
+            .iter()
+            .map(|definition| definition.block_type())
+            .filter(|block_type| block_type.has_gravity())
+            .map(|block_type| {
+                let material = material_assets.add(StandardMaterial {
+                    base_color: block_type.color(),
+                    ..default()
+                });
+                (block_type, material)
+            })
+            .collect();
+        Self { mesh, materials }
+    }
+}
+
+/// Whether a block holds up a block with gravity resting on it
+fn supports(block_type: BlockType) -> bool {
+    block_type.is_solid() && block_type.fluid().is_none()
+}
+
+/// Nearest cell from a position upwards that a block can settle in, which is air or fluid
+/// None when there's no room before the top of the world.
+fn free_cell_above(world: &impl BlockWorld, mut cell: IVec3) -> Option<IVec3> {
+    loop {
+        let (block_type, _) = world.block(cell)?;
+        if block_type == BlockType::Air || block_type.fluid().is_some() {
+            return Some(cell);
+        }
+        cell += IVec3::Y;
+    }
+}
+
+/// Cell a falling block settles in if it drops straight down from where it is
+fn resting_cell(world: &impl BlockWorld, block: &FallingBlock) -> Option<IVec3> {
+    let mut cell = block.position.floor().as_ivec3();
+    while let Some((below, _)) = world.block(cell - IVec3::Y) {
+        if supports(below) {
+            break;
+        }
+        cell -= IVec3::Y;
+    }
+    free_cell_above(world, cell)
+}
+
+/// Check whether a block with gravity still has support, and take it out of the world if not
+/// Returns the block and its state when it starts to fall.
+pub fn update_gravity_block(
+    world: &mut impl BlockWorld,
+    position: IVec3,
+) -> Option<(BlockType, BlockState)> {
+    let (block_type, state) = world.block(position)?;
+    if !block_type.has_gravity() {
+        return None;
+    }
+    let (below, _) = world.block(position - IVec3::Y)?;
+    if supports(below) {
+        return None;
+    }
+    world.set_block(position, BlockType::Air, BlockState::default());
+    Some((block_type, state))
+}
+
+/// Spawn the entity of a block that has started to fall from a cell
+pub fn spawn_falling_block(
+    commands: &mut Commands,
+    assets: &FallingBlockAssets,
+    position: IVec3,
+    block_type: BlockType,
+    state: BlockState,
+) {
+    let position = position.as_vec3();
+    commands.spawn((
+        FallingBlock {
+            block_type,
+            state,
+            position,
+            velocity: 0.0,
+        },
+        Mesh3d(assets.mesh.clone()),
+        MeshMaterial3d(
+            assets
+                .materials
+                .get(&block_type)
+                .cloned()
+                .unwrap_or_default(),
+        ),
+        Transform::from_translation(position + Vec3::splat(0.5)),
+    ));
+}
+
+/// Move a falling block down for a frame
+/// Returns the cell it settles in once it meets a solid block. Blocks over chunks that aren't
+/// loaded wait in the air until they are.
+pub fn fall(world: &impl BlockWorld, block: &mut FallingBlock, delta: f32) -> Option<IVec3> {
+    block.velocity = (block.velocity + FALLING_BLOCK_GRAVITY * delta).min(MAX_FALL_SPEED);
+    let mut remaining = block.velocity * delta;
+    while remaining > 0.0 {
+        let step = remaining.min(MAX_FALL_STEP);
+        remaining -= step;
+        let next_y = block.position.y - step;
+        let entered = IVec3::new(
+            block.position.x as i32,
+            next_y.floor() as i32,
+            block.position.z as i32,
+        );
+        let (block_type, _) = world.block(entered)?;
+        if supports(block_type) {
+            return Some(entered + IVec3::Y);
+        }
+        block.position.y = next_y;
+    }
+    None
+}
+
+/// System that moves falling blocks and settles them back into their chunk when they land
+/// A block landing in a cell something else has filled in the meantime settles on top of it.
+pub fn falling_block_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut chunk_manager: ResMut<ChunkManager>,
+    mut chunks: Query<&mut Chunk>,
+    mut falling_blocks: Query<(Entity, &mut FallingBlock, &mut Transform)>,
+) {
+    let chunk_manager = &mut *chunk_manager;
+    let mut world = LoadedChunks {
+        loaded_chunks: &chunk_manager.loaded_chunks,
+        chunks: &mut chunks,
+    };
+    // A long frame shouldn't carry blocks through the floor
+    let delta = time.delta_secs().min(0.1);
+
+    for (entity, mut block, mut transform) in &mut falling_blocks {
+        let Some(landing) = fall(&world, &mut block, delta) else {
+            transform.translation = block.position + Vec3::splat(0.5);
+            continue;
+        };
+        // With no room anywhere above, the block waits where it is
+        let Some(landing) = free_cell_above(&world, landing) else {
+            continue;
+        };
+        world.set_block(landing, block.block_type, block.state);
+        chunk_manager.block_updates.block_changed(landing);
+        commands.entity(entity).despawn();
+    }
+}
+
+/// System that puts blocks still falling when the game exits into the cells they'd land in
+/// Runs before the world is saved, so no block is lost mid-fall.
+pub fn settle_falling_blocks_on_exit_system(
+    mut exit_events: EventReader<AppExit>,
+    chunk_manager: Res<ChunkManager>,
+    mut chunks: Query<&mut Chunk>,
+    falling_blocks: Query<&FallingBlock>,
+) {
+    if exit_events.read().next().is_none() {
+        return;
+    }
+
+    let mut world = LoadedChunks {
+        loaded_chunks: &chunk_manager.loaded_chunks,
+        chunks: &mut chunks,
+    };
+    for block in &falling_blocks {
+        match resting_cell(&world, block) {
+            Some(cell) => world.set_block(cell, block.block_type, block.state),
+            None => println!(
+                "⚠️  No room to save falling {:?} at {:?}",
+                block.block_type, block.position
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block_update::TestWorld;
+
+    #[test]
+    fn test_unsupported_sand_falls_as_a_column() {
+        let mut world = TestWorld::default();
+        world.set_block(IVec3::new(0, 1, 0), BlockType::Stone, BlockState::default());
+        for y in 2..5 {
+            world.set_block(IVec3::new(0, y, 0), BlockType::Sand, BlockState::default());
+        }
+        world.set_block(IVec3::new(2, 1, 0), BlockType::Sand, BlockState::default());
+
+        // Breaking the stone brings the column down from the bottom up
+        let fell = world.place_and_settle(IVec3::new(0, 1, 0), BlockType::Air);
+        assert_eq!(
+            fell,
+            [
+                IVec3::new(0, 2, 0),
+                IVec3::new(0, 3, 0),
+                IVec3::new(0, 4, 0)
+            ]
+        );
+        assert!((2..5).all(|y| world.block_type(IVec3::new(0, y, 0)) == BlockType::Air));
+        assert_eq!(world.block_type(IVec3::new(2, 1, 0)), BlockType::Sand);
+
+        // Sand placed in the air falls straight away; on the floor it stays put
+        assert_eq!(
+            world.place_and_settle(IVec3::new(4, 3, 4), BlockType::Sand),
+            [IVec3::new(4, 3, 4)]
+        );
+        assert!(world
+            .place_and_settle(IVec3::new(4, 1, 4), BlockType::Sand)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_falling_blocks_sink_through_fluids_and_land_on_solid_ground() {
+        let mut world = TestWorld::default();
+        world.set_block(IVec3::new(0, 1, 0), BlockType::Water, BlockState::default());
+        let mut block = FallingBlock {
+            block_type: BlockType::Sand,
+            state: BlockState::default(),
+            position: Vec3::new(0.0, 6.0, 0.0),
+            velocity: 0.0,
+        };
+
+        let mut landing = None;
+        for _ in 0..600 {
+            landing = fall(&world, &mut block, 1.0 / 60.0);
+            if landing.is_some() {
+                break;
+            }
+        }
+        assert_eq!(landing, Some(IVec3::new(0, 1, 0)));
+        assert!(block.velocity <= MAX_FALL_SPEED);
+        assert!(block.position.y >= 1.0);
+    }
+
+    #[test]
+    fn test_blocks_settle_in_the_nearest_free_cell_above() {
+        let mut world = TestWorld::default();
+        for y in 1..3 {
+            world.set_block(IVec3::new(0, y, 0), BlockType::Dirt, BlockState::default());
+        }
+        world.set_block(IVec3::new(0, 3, 0), BlockType::Water, BlockState::default());
+        assert_eq!(
+            free_cell_above(&world, IVec3::new(0, 1, 0)),
+            Some(IVec3::new(0, 3, 0))
+        );
+        assert_eq!(
+            free_cell_above(&world, IVec3::new(1, 1, 0)),
+            Some(IVec3::new(1, 1, 0))
+        );
+        for y in 1..8 {
+            world.set_block(IVec3::new(2, y, 0), BlockType::Stone, BlockState::default());
+        }
+        assert_eq!(free_cell_above(&world, IVec3::new(2, 1, 0)), None);
+
+        // A block caught mid-fall rests where it would have landed
+        let block = FallingBlock {
+            block_type: BlockType::Sand,
+            state: BlockState::default(),
+            position: Vec3::new(0.0, 6.0, 0.0),
+            velocity: 5.0,
+        };
+        assert_eq!(resting_cell(&world, &block), Some(IVec3::new(0, 3, 0)));
+        let block = FallingBlock {
+            position: Vec3::new(1.0, 4.5, 0.0),
+            ..block
+        };
+        assert_eq!(resting_cell(&world, &block), Some(IVec3::new(1, 1, 0)));
+    }
+}
@@ -25,6 +25,15 @@ impl ItemType {
             ItemType::Food(food_type) => food_type.name(),
         }
     }
+
+    /// Items given to the player for breaking a block, with their counts
+    /// Blocks whose definition lists no drops drop themselves.
+    pub fn dropped_by(block_type: BlockType) -> Vec<(ItemType, u32)> {
+        match &block_type.definition().drops {
+            Some(drops) => drops.iter().map(|drop| (drop.item, drop.count)).collect(),
+            None => vec![(ItemType::Block(block_type), 1)],
+        }
+    }
 }
 
 /// Enum representing different types of tools
@@ -55,6 +64,7 @@ pub enum ResourceType {
     Coal,
     IronIngot,
     GoldIngot,
+    Diamond,
 }
 
 impl ResourceType {
@@ -65,6 +75,7 @@ impl ResourceType {
             ResourceType::Coal => "Coal",
             ResourceType::IronIngot => "Iron Ingot",
             ResourceType::GoldIngot => "Gold Ingot",
+            ResourceType::Diamond => "Diamond",
         }
     }
 }
@@ -0,0 +1,592 @@
+// Lighting for Bevy Craft
+// This module stores per-block light levels and spreads them through the world
+//
+// Every block position holds two light levels from 0 to 15. Skylight pours straight down from
+// the top of the world without fading and spreads sideways one level weaker per block; block
+// light comes from blocks that glow, such as lava, and fades one level per block in every
+// direction. Opaque blocks stop light, while water and leaves let it through one level dimmer.
+//
+// A chunk's light is worked out in full once its blocks are ready and then kept up to date as
+// blocks change, with the usual pair of flood fills: one removes light that lost its source and
+// the other spreads light outwards again. Both run across chunk borders on the chunks around
+// the change, so light is never more than 15 blocks away from where it started.
+
+use bevy::prelude::*;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::block::BlockType;
+use crate::chunk::{Chunk, ChunkPosition, WorldHeight, CHUNK_AREA, CHUNK_SIZE};
+use crate::time::GameTime;
+
+/// Brightest light level
+pub const MAX_LIGHT: u8 = 15;
+
+/// Most chunks whose light is worked out from scratch each frame
+const MAX_CHUNKS_LIT_PER_FRAME: usize = 4;
+
+/// Sky brightness at night, so the surface stays faintly visible under the moon
+const NIGHT_SKY_BRIGHTNESS: f32 = 0.2;
+
+/// The six directions light spreads in
+const LIGHT_DIRECTIONS: [IVec3; 6] = [
+    IVec3::X,
+    IVec3::NEG_X,
+    IVec3::Y,
+    IVec3::NEG_Y,
+    IVec3::Z,
+    IVec3::NEG_Z,
+];
+
+/// The two kinds of light stored for every block
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LightChannel {
+    /// Light from the sky
+    Sky,
+    /// Light given off by blocks
+    Block,
+}
+
+impl LightChannel {
+    const ALL: [LightChannel; 2] = [LightChannel::Sky, LightChannel::Block];
+
+    /// Position of the channel's four bits within a stored byte
+    fn shift(self) -> u32 {
+        match self {
+            LightChannel::Sky => 4,
+            LightChannel::Block => 0,
+        }
+    }
+}
+
+/// Light levels of every block in a chunk, kept next to its `ChunkData`
+/// Both channels share a byte per block; positions outside the chunk read as dark.
+#[derive(Debug, Clone)]
+pub struct ChunkLight {
+    min_y: i32,
+    height: usize,
+    levels: Vec<u8>,
+}
+
+impl Default for ChunkLight {
+    fn default() -> Self {
+        Self::new(WorldHeight::default())
+    }
+}
+
+impl ChunkLight {
+    pub fn new(world_height: WorldHeight) -> Self {
+        Self {
+            min_y: world_height.min_y,
+            height: world_height.height(),
+            levels: vec![0; CHUNK_AREA * world_height.height()],
+        }
+    }
+
+    /// Light level of one channel at local chunk coordinates and world y
+    pub fn get(&self, channel: LightChannel, local_x: usize, y: i32, local_z: usize) -> u8 {
+        self.index(local_x, y, local_z).map_or(0, |index| {
+            (self.levels[index] >> channel.shift()) & MAX_LIGHT
+        })
+    }
+
+    /// Set the light level of one channel at local chunk coordinates and world y
+    pub fn set(
+        &mut self,
+        channel: LightChannel,
+        local_x: usize,
+        y: i32,
+        local_z: usize,
+        level: u8,
+    ) {
+        if let Some(index) = self.index(local_x, y, local_z) {
+            let mask = MAX_LIGHT << channel.shift();
+            self.levels[index] =
+                (self.levels[index] & !mask) | ((level.min(MAX_LIGHT) << channel.shift()) & mask);
+        }
+    }
+
+    fn index(&self, local_x: usize, y: i32, local_z: usize) -> Option<usize> {
+        if local_x >= CHUNK_SIZE || local_z >= CHUNK_SIZE || y < self.min_y {
+            return None;
+        }
+        let offset_y = (y - self.min_y) as usize;
+        (offset_y < self.height).then(|| (offset_y * CHUNK_SIZE + local_z) * CHUNK_SIZE + local_x)
+    }
+}
+
+/// How much dimmer light gets entering a block, beyond the level it loses for every step
+/// None for blocks that stop light altogether.
+fn light_absorption(block: Option<BlockType>) -> Option<u8> {
+    match block {
+        None | Some(BlockType::Air) => Some(0),
+        Some(BlockType::Water) | Some(BlockType::Leaves) => Some(1),
+        Some(block) if block.is_transparent() => Some(0),
+        Some(_) => None,
+    }
+}
+
+/// Chunks around a lighting change, addressed by world block position
+/// Only chunks whose terrain is ready take part; light doesn't spread into the others until
+/// they are lit themselves. Chunks whose light changes are flagged for a new mesh.
+pub struct LightVolume<'a> {
+    chunks: HashMap<ChunkPosition, &'a mut Chunk>,
+}
+
+impl<'a> LightVolume<'a> {
+    pub fn new(chunks: impl IntoIterator<Item = &'a mut Chunk>) -> Self {
+        Self {
+            chunks: chunks
+                .into_iter()
+                .filter(|chunk| chunk.is_generated)
+                .map(|chunk| (chunk.position, chunk))
+                .collect(),
+        }
+    }
+
+    /// Chunk and local coordinates holding a world position
+    fn locate(&self, position: IVec3) -> Option<(&Chunk, usize, usize)> {
+        let chunk = self
+            .chunks
+            .get(&ChunkPosition::from_block_position(position))?;
+        (position.y >= chunk.data.min_y() && position.y < chunk.data.max_y()).then(|| {
+            (
+                &**chunk,
+                position.x.rem_euclid(CHUNK_SIZE as i32) as usize,
+                position.z.rem_euclid(CHUNK_SIZE as i32) as usize,
+            )
+        })
+    }
+
+    /// Block at a world position, or None when the position isn't part of the volume
+    fn block(&self, position: IVec3) -> Option<Option<BlockType>> {
+        self.locate(position)
+            .map(|(chunk, x, z)| chunk.data.get_block(x, position.y, z))
+    }
+
+    /// Light level at a world position; dark outside the volume
+    pub fn light(&self, channel: LightChannel, position: IVec3) -> u8 {
+        self.locate(position).map_or(0, |(chunk, x, z)| {
+            chunk.light.get(channel, x, position.y, z)
+        })
+    }
+
+    fn set_light(&mut self, channel: LightChannel, position: IVec3, level: u8) {
+        let chunk_pos = ChunkPosition::from_block_position(position);
+        if let Some(chunk) = self.chunks.get_mut(&chunk_pos) {
+            let (x, z) = (
+                position.x.rem_euclid(CHUNK_SIZE as i32) as usize,
+                position.z.rem_euclid(CHUNK_SIZE as i32) as usize,
+            );
+            if chunk.light.get(channel, x, position.y, z) != level {
+                chunk.light.set(channel, x, position.y, z, level);
+                chunk.needs_mesh_update = true;
+            }
+        }
+    }
+
+    /// Spread light outwards from the queued positions until it fades out
+    fn spread(&mut self, channel: LightChannel, queue: &mut VecDeque<IVec3>) {
+        while let Some(position) = queue.pop_front() {
+            let level = self.light(channel, position);
+            if level <= 1 {
+                continue;
+            }
+            for direction in LIGHT_DIRECTIONS {
+                let next = position + direction;
+                let Some(absorption) = self.block(next).and_then(light_absorption) else {
+                    continue;
+                };
+                // Full skylight falls straight down without fading
+                let next_level = if channel == LightChannel::Sky
+                    && direction == IVec3::NEG_Y
+                    && level == MAX_LIGHT
+                {
+                    MAX_LIGHT - absorption
+                } else {
+                    level.saturating_sub(1 + absorption)
+                };
+                if next_level > self.light(channel, next) {
+                    self.set_light(channel, next, next_level);
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+
+    /// Take away light that came from the queued positions and their old levels
+    /// Light from other sources that borders the darkened area is queued in `refill` to spread
+    /// back in.
+    fn remove(
+        &mut self,
+        channel: LightChannel,
+        queue: &mut VecDeque<(IVec3, u8)>,
+        refill: &mut VecDeque<IVec3>,
+    ) {
+        while let Some((position, level)) = queue.pop_front() {
+            for direction in LIGHT_DIRECTIONS {
+                let next = position + direction;
+                let Some(block) = self.block(next) else {
+                    continue;
+                };
+                let next_level = self.light(channel, next);
+                if next_level == 0 {
+                    continue;
+                }
+
+                let lit_from_here = next_level < level
+                    || (channel == LightChannel::Sky
+                        && direction == IVec3::NEG_Y
+                        && level == MAX_LIGHT
+                        && next_level == MAX_LIGHT);
+                if lit_from_here {
+                    self.set_light(channel, next, 0);
+                    queue.push_back((next, next_level));
+
+                    // Glowing blocks keep their own light
+                    let emission = block.map_or(0, |block| block.light_emission());
+                    if channel == LightChannel::Block && emission > 0 {
+                        self.set_light(channel, next, emission);
+                        refill.push_back(next);
+                    }
+                } else {
+                    refill.push_back(next);
+                }
+            }
+        }
+    }
+
+    /// Update light around a block that changed
+    pub fn update_block(&mut self, position: IVec3) {
+        let Some(block) = self.block(position) else {
+            return;
+        };
+
+        for channel in LightChannel::ALL {
+            let mut removal = VecDeque::from([(position, self.light(channel, position))]);
+            let mut refill = VecDeque::new();
+            self.set_light(channel, position, 0);
+            self.remove(channel, &mut removal, &mut refill);
+
+            let emission = block.map_or(0, |block| block.light_emission());
+            if channel == LightChannel::Block && emission > 0 {
+                self.set_light(channel, position, emission);
+                refill.push_back(position);
+            }
+            self.spread(channel, &mut refill);
+        }
+    }
+
+    /// Work out a chunk's light from scratch and exchange light with its neighbours
+    pub fn light_chunk(&mut self, chunk_pos: ChunkPosition) {
+        let Some(chunk) = self.chunks.get_mut(&chunk_pos) else {
+            return;
+        };
+        let (min_y, max_y) = (chunk.data.min_y(), chunk.data.max_y());
+        chunk.light = ChunkLight::new(WorldHeight { min_y, max_y });
+        let origin = chunk_pos.min_block_position();
+        let mut sky_queue = VecDeque::new();
+        let mut block_queue = VecDeque::new();
+
+        // Skylight pours straight down each column until something dims it
+        let mut lowest_lit = [[max_y; CHUNK_SIZE]; CHUNK_SIZE];
+        for (x, row) in lowest_lit.iter_mut().enumerate() {
+            for (z, lowest) in row.iter_mut().enumerate() {
+                let mut y = max_y - 1;
+                while y >= min_y && light_absorption(chunk.data.get_block(x, y, z)) == Some(0) {
+                    chunk.light.set(LightChannel::Sky, x, y, z, MAX_LIGHT);
+                    y -= 1;
+                }
+                *lowest = y + 1;
+                if y + 1 < max_y {
+                    sky_queue.push_back(origin + IVec3::new(x as i32, y + 1, z as i32));
+                }
+            }
+        }
+
+        // Blocks that glow light up their surroundings
+        for section_index in 0..chunk.data.section_count() {
+            if chunk.data.is_section_empty(section_index) {
+                continue;
+            }
+            let section_min_y = chunk.data.section_min_y(section_index);
+            for y in section_min_y..section_min_y + crate::chunk_section::SECTION_SIZE as i32 {
+                for x in 0..CHUNK_SIZE {
+                    for z in 0..CHUNK_SIZE {
+                        let emission = chunk
+                            .data
+                            .get_block(x, y, z)
+                            .map_or(0, |block| block.light_emission());
+                        if emission > 0 {
+                            chunk.light.set(LightChannel::Block, x, y, z, emission);
+                            block_queue.push_back(origin + IVec3::new(x as i32, y, z as i32));
+                        }
+                    }
+                }
+            }
+        }
+
+        // Inside the chunk a column only spreads sideways where its neighbour is lit less far down
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                for (dx, dz) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+                    let (nx, nz) = (x as i32 + dx, z as i32 + dz);
+                    if !(0..CHUNK_SIZE as i32).contains(&nx)
+                        || !(0..CHUNK_SIZE as i32).contains(&nz)
+                    {
+                        continue;
+                    }
+                    for y in lowest_lit[x][z]..lowest_lit[nx as usize][nz as usize] {
+                        sky_queue.push_back(origin + IVec3::new(x as i32, y, z as i32));
+                    }
+                }
+            }
+        }
+
+        // Across the borders, whichever side is brighter spreads into the other
+        let size = CHUNK_SIZE as i32;
+        for along in 0..size {
+            for (inside, outside) in [
+                (IVec3::new(0, 0, along), IVec3::new(-1, 0, along)),
+                (IVec3::new(size - 1, 0, along), IVec3::new(size, 0, along)),
+                (IVec3::new(along, 0, 0), IVec3::new(along, 0, -1)),
+                (IVec3::new(along, 0, size - 1), IVec3::new(along, 0, size)),
+            ] {
+                for y in min_y..max_y {
+                    let inside = origin + inside.with_y(y);
+                    let outside = origin + outside.with_y(y);
+                    for (channel, queue) in [
+                        (LightChannel::Sky, &mut sky_queue),
+                        (LightChannel::Block, &mut block_queue),
+                    ] {
+                        let (inside_level, outside_level) =
+                            (self.light(channel, inside), self.light(channel, outside));
+                        if inside_level > outside_level + 1 {
+                            queue.push_back(inside);
+                        } else if outside_level > inside_level + 1 {
+                            queue.push_back(outside);
+                        }
+                    }
+                }
+            }
+        }
+
+        self.spread(LightChannel::Sky, &mut sky_queue);
+        self.spread(LightChannel::Block, &mut block_queue);
+    }
+}
+
+/// System to light chunks whose blocks are ready and keep their light up to date as blocks change
+/// Each change is worked out on the chunk and the eight around it, since light reaches at most
+/// 15 blocks.
+pub fn update_chunk_lighting_system(mut chunks: Query<&mut Chunk>) {
+    let mut to_light: Vec<ChunkPosition> = Vec::new();
+    let mut to_update: Vec<ChunkPosition> = Vec::new();
+    for chunk in &chunks {
+        if !chunk.is_generated {
+            continue;
+        }
+        if chunk.needs_light_update {
+            to_light.push(chunk.position);
+        } else if !chunk.light_updates.is_empty() {
+            to_update.push(chunk.position);
+        }
+    }
+    to_light.truncate(MAX_CHUNKS_LIT_PER_FRAME);
+
+    for (chunk_pos, from_scratch) in to_light
+        .into_iter()
+        .map(|pos| (pos, true))
+        .chain(to_update.into_iter().map(|pos| (pos, false)))
+    {
+        let mut around: HashSet<ChunkPosition> = chunk_pos.all_neighbors().into_iter().collect();
+        around.insert(chunk_pos);
+        let mut volume = LightVolume::new(
+            chunks
+                .iter_mut()
+                .filter(|chunk| around.contains(&chunk.position))
+                .map(Mut::into_inner),
+        );
+
+        let Some(chunk) = volume.chunks.get_mut(&chunk_pos) else {
+            continue;
+        };
+        let changed_blocks = std::mem::take(&mut chunk.light_updates);
+        if from_scratch {
+            chunk.needs_light_update = false;
+            volume.light_chunk(chunk_pos);
+        } else {
+            for position in changed_blocks {
+                volume.update_block(position);
+            }
+        }
+    }
+}
+
+/// Brightness of skylight right now, from night to full day
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct SkyBrightness(pub f32);
+
+impl Default for SkyBrightness {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+impl SkyBrightness {
+    /// Sky brightness for a time of day, following the height of the sun
+    pub fn at(time: &GameTime) -> Self {
+        let sun_height = time.sun_angle_radians().sin();
+        // Fade between night and day while the sun is close to the horizon
+        let daylight = ((sun_height + 0.1) / 0.4).clamp(0.0, 1.0);
+        Self(NIGHT_SKY_BRIGHTNESS + (1.0 - NIGHT_SKY_BRIGHTNESS) * daylight)
+    }
+}
+
+/// System to follow the sun with the brightness of skylight
+pub fn update_sky_brightness(time: Res<GameTime>, mut sky_brightness: ResMut<SkyBrightness>) {
+    sky_brightness.set_if_neq(SkyBrightness::at(&time));
+}
+
+/// How bright a surface looks under skylight and block light, as fractions of the brightest light
+/// level, counting skylight at the current sky brightness
+/// The chunk material's shader works brightness out the same way.
+pub fn light_brightness(sky_light: f32, block_light: f32, sky_brightness: f32) -> f32 {
+    // Caves are never completely black, so they stay navigable
+    const DARKEST: f32 = 0.05;
+    let level = (sky_light * sky_brightness).max(block_light);
+    DARKEST + (1.0 - DARKEST) * level.powf(1.5)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Generated chunk with a stone floor at y = 10
+    fn floored_chunk(position: ChunkPosition) -> Chunk {
+        let mut chunk = Chunk::new(position, WorldHeight::default());
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                chunk.data.set_block(x, 10, z, BlockType::Stone);
+            }
+        }
+        chunk.is_generated = true;
+        chunk
+    }
+
+    #[test]
+    fn test_skylight_falls_into_rooms_and_fades_under_roofs() {
+        let mut chunk = floored_chunk(ChunkPosition::new(0, 0));
+        // A roof over one corner of the chunk, open towards +x and +z
+        for x in 0..6 {
+            for z in 0..6 {
+                chunk.data.set_block(x, 14, z, BlockType::Stone);
+            }
+        }
+        let mut volume = LightVolume::new([&mut chunk]);
+        volume.light_chunk(ChunkPosition::new(0, 0));
+
+        let sky =
+            |volume: &LightVolume, x, y, z| volume.light(LightChannel::Sky, IVec3::new(x, y, z));
+        assert_eq!(sky(&volume, 10, 11, 10), MAX_LIGHT);
+        assert_eq!(sky(&volume, 10, 200, 10), MAX_LIGHT);
+        // Under the roof light comes in from the open side and fades one level per block
+        assert_eq!(sky(&volume, 5, 11, 2), MAX_LIGHT - 1);
+        assert_eq!(sky(&volume, 1, 11, 1), MAX_LIGHT - 5);
+        assert_eq!(sky(&volume, 0, 11, 0), MAX_LIGHT - 6);
+        // Nothing gets through the floor
+        assert_eq!(sky(&volume, 10, 9, 10), 0);
+
+        // Closing the room off darkens it, opening it again brings the light back
+        for z in 0..6 {
+            volume
+                .chunks
+                .get_mut(&ChunkPosition::new(0, 0))
+                .unwrap()
+                .data
+                .set_block(6, 11, z, BlockType::Stone);
+        }
+        for x in 0..7 {
+            for y in 11..14 {
+                volume
+                    .chunks
+                    .get_mut(&ChunkPosition::new(0, 0))
+                    .unwrap()
+                    .data
+                    .set_block(x, y, 6, BlockType::Stone);
+            }
+        }
+        for z in 0..6 {
+            for y in 12..14 {
+                volume
+                    .chunks
+                    .get_mut(&ChunkPosition::new(0, 0))
+                    .unwrap()
+                    .data
+                    .set_block(6, y, z, BlockType::Stone);
+            }
+        }
+        for y in 11..14 {
+            for z in 0..=6 {
+                volume.update_block(IVec3::new(6, y, z));
+            }
+            for x in 0..6 {
+                volume.update_block(IVec3::new(x, y, 6));
+            }
+        }
+        assert_eq!(sky(&volume, 2, 12, 2), 0);
+
+        volume
+            .chunks
+            .get_mut(&ChunkPosition::new(0, 0))
+            .unwrap()
+            .data
+            .set_block(2, 14, 2, BlockType::Air);
+        volume.update_block(IVec3::new(2, 14, 2));
+        assert_eq!(sky(&volume, 2, 11, 2), MAX_LIGHT);
+        assert_eq!(sky(&volume, 4, 11, 2), MAX_LIGHT - 2);
+    }
+
+    #[test]
+    fn test_lava_light_crosses_chunk_borders_and_goes_out_with_it() {
+        let mut west = floored_chunk(ChunkPosition::new(-1, 0));
+        let mut east = floored_chunk(ChunkPosition::new(0, 0));
+        west.data.set_block(CHUNK_SIZE - 1, 11, 4, BlockType::Lava);
+        let mut volume = LightVolume::new([&mut west, &mut east]);
+        volume.light_chunk(ChunkPosition::new(-1, 0));
+        volume.light_chunk(ChunkPosition::new(0, 0));
+
+        let block_light =
+            |volume: &LightVolume, x, z| volume.light(LightChannel::Block, IVec3::new(x, 11, z));
+        assert_eq!(block_light(&volume, -1, 4), MAX_LIGHT);
+        assert_eq!(block_light(&volume, 0, 4), MAX_LIGHT - 1);
+        assert_eq!(block_light(&volume, 3, 6), MAX_LIGHT - 6);
+
+        // Removing the lava takes its light away on both sides of the border
+        volume
+            .chunks
+            .get_mut(&ChunkPosition::new(-1, 0))
+            .unwrap()
+            .data
+            .set_block(CHUNK_SIZE - 1, 11, 4, BlockType::Air);
+        volume.update_block(IVec3::new(-1, 11, 4));
+        assert_eq!(block_light(&volume, -1, 4), 0);
+        assert_eq!(block_light(&volume, 3, 6), 0);
+        assert!(east.needs_mesh_update);
+    }
+
+    #[test]
+    fn test_sky_brightness_follows_the_sun() {
+        let noon = GameTime::default();
+        let midnight = GameTime {
+            current_time: 0.0,
+            ..default()
+        };
+        assert_eq!(SkyBrightness::at(&noon), SkyBrightness(1.0));
+        assert!(SkyBrightness::at(&midnight).0 < 0.3);
+        assert!(light_brightness(1.0, 0.0, 1.0) > light_brightness(1.0, 0.0, 0.2));
+        assert_eq!(
+            light_brightness(1.0, 1.0, 0.2),
+            light_brightness(1.0, 0.0, 1.0)
+        );
+    }
+}
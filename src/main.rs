@@ -3,15 +3,33 @@ use bevy::prelude::*;
 use bevy_compute_noise::prelude::*;
 
 mod block;
-use block::{Block, BlockType};
+mod block_state;
+mod block_update;
+mod fluid;
+mod gravity;
+use block::Block;
 
 mod chunk;
 use chunk::{Chunk, ChunkManager, ChunkPosition, ChunkPriority};
 
+mod chunk_material;
 mod chunk_mesh;
-use chunk_mesh::{ChunkMesh, ChunkMeshMaterials};
+mod chunk_section;
+mod chunk_visibility;
+mod lighting;
+use chunk_material::ChunkMaterial;
+use chunk_mesh::{
+    sort_translucent_chunk_meshes, update_chunk_sky_brightness, ChunkMesh, ChunkMeshMaterials,
+    ChunkMeshPart, ChunkMeshSettings, ChunkMeshSnapshot, ChunkMeshTasks, ChunkSubMesh, MeshLayer,
+    TranslucentChunkMesh,
+};
+use chunk_visibility::{
+    display_chunk_visibility_stats, update_chunk_visibility, ChunkVisibilityStats,
+};
 
 mod texture_atlas;
+use lighting::{update_chunk_lighting_system, update_sky_brightness, SkyBrightness};
+
 use texture_atlas::{initialize_texture_atlas, load_procedural_textures_into_atlas, TextureAtlas};
 
 mod texture_gen;
@@ -20,15 +38,21 @@ use texture_gen::{initialize_block_textures, BlockTextures, TextureGenSettings};
 mod noise;
 mod test_sophisticated_algorithms;
 
+mod biome;
+use biome::BiomeRegistry;
+mod biome_blend;
 mod biome_debug;
 mod biome_material;
 mod biome_texture_cache;
 mod biome_textures;
 use biome_debug::{BiomeDebugSettings, BiomeDebugStats};
-use biome_material::BiomeMaterial;
 
 mod player;
 use player::{FoodConsumedEvent, PlayerDamageEvent, PlayerDeathEvent};
+mod caves;
+mod features;
+mod structures;
+mod water_bodies;
 mod world_gen;
 use crate::noise::NoiseSettings;
 use player::{HealthRegenerationSettings, PlayerMovementSettings};
@@ -58,8 +82,8 @@ mod weather;
 use weather::WeatherSystem;
 use weather::{
     display_weather_info, initialize_weather_system, spawn_cloud_layers, spawn_weather_particles,
-    update_cloud_rendering, update_lightning_effects, update_weather_particles,
-    update_weather_system, WeatherParticleMaterials,
+    update_biome_weather, update_cloud_rendering, update_lightning_effects,
+    update_weather_particles, update_weather_system, WeatherParticleMaterials,
 };
 
 mod time;
@@ -80,7 +104,46 @@ use health_hunger_ui::{spawn_status_ui, update_status_ui};
 mod crafting;
 use crafting::{CraftItemEvent, CraftingFailEvent, CraftingSuccessEvent, RecipeBook};
 
+mod world_save;
+use world_save::{save_world_on_exit_system, WorldSave};
+
+mod cli;
+use cli::LaunchOptions;
+
 fn main() {
+    // Settle the blocks first, since world saves and the biome registry refer to them
+    block::BlockRegistry::load_or_builtin(block::BLOCK_REGISTRY_PATH).install();
+
+    // Open the world selected on the command line and settle its settings before anything generates
+    let launch_options = LaunchOptions::from_env();
+    let world_save = WorldSave::for_world(&launch_options.world_name);
+    let world_metadata =
+        match world_save.resolve_metadata(launch_options.seed, launch_options.world_height()) {
+            Ok(world_metadata) => world_metadata,
+            Err(error) => {
+                // Starting anyway would make a new world.ron and lose the world's seed
+                println!(
+                    "❌ Can't read the metadata of world '{}': {}",
+                    launch_options.world_name, error
+                );
+                std::process::exit(1);
+            }
+        };
+    let world_seed = world_metadata.seed;
+    // Leaves and trunks that spilled into chunks nobody has visited yet
+    let pending_features = world_save.load_pending_features().unwrap_or_else(|error| {
+        println!("⚠️  Can't read the feature blocks waiting for chunks: {}", error);
+        features::PendingFeatureBlocks::default()
+    });
+    println!(
+        "🌍 Loading world '{}' with seed {}, build limits y {}..{}, render distance {} chunks",
+        launch_options.world_name,
+        world_seed,
+        world_metadata.world_height.min_y,
+        world_metadata.world_height.max_y,
+        launch_options.render_distance
+    );
+
     // Create the app first
     let mut app = App::new();
 
@@ -94,18 +157,33 @@ fn main() {
         .add_event::<CraftingFailEvent>() // Register crafting fail event
         .add_plugins(ComputeNoisePlugin) // Add Perlin noise plugin for world generation
         .add_plugins(bevy::pbr::MaterialPlugin::<sky::AtmosphericScatteringMaterial>::default()) // Add atmospheric scattering material plugin
-        .init_resource::<ChunkManager>()
-        .init_resource::<WorldGenSettings>() // Initialize world generation settings
-        .init_resource::<NoiseSettings>() // Initialize noise settings
+        .insert_resource(ChunkManager::new(
+            launch_options.render_distance,
+            world_metadata.world_height,
+        )) // Initialize chunk manager with the render distance from the command line
+        .insert_resource(WorldGenSettings {
+            seed: world_seed,
+            world_height: world_metadata.world_height,
+            ..default()
+        }) // Initialize world generation settings with the world seed and build limits
+        .insert_resource(NoiseSettings {
+            seed: world_seed,
+            ..default()
+        }) // Initialize noise settings with the world seed
+        .insert_resource(BiomeRegistry::load_or_builtin(biome::BIOME_REGISTRY_PATH)) // Load the biome registry shared by world gen and rendering
         .init_resource::<PlayerMovementSettings>() // Initialize player movement settings
         .init_resource::<HealthRegenerationSettings>() // Initialize health regeneration settings
         .init_resource::<ChunkMeshMaterials>() // Initialize chunk mesh materials
+        .init_resource::<ChunkMeshTasks>() // Initialize background chunk meshing tasks
+        .init_resource::<ChunkMeshSettings>() // Initialize chunk meshing settings
+        .init_resource::<ChunkVisibilityStats>() // Initialize drawn chunk counter
         .init_resource::<TextureAtlas>() // Initialize texture atlas
         .init_resource::<TextureGenSettings>() // Initialize texture generation settings
         .init_resource::<BlockTextures>() // Initialize block textures resource
         .init_resource::<crate::biome_texture_cache::SharedBiomeTextureCache>() // Initialize biome texture cache
         .init_resource::<crate::biome_material::SharedBiomeMaterialCache>() // Initialize biome material cache
         .init_resource::<GameTime>() // Initialize game time for day/night cycle
+        .init_resource::<SkyBrightness>() // Initialize sky brightness chunk materials are lit with
         .init_resource::<AtmosphericScatteringParams>() // Initialize atmospheric scattering parameters
         .init_resource::<BiomeDebugSettings>() // Initialize biome debug settings
         .init_resource::<BiomeDebugStats>() // Initialize biome debug statistics
@@ -117,15 +195,23 @@ fn main() {
         .init_resource::<block_interaction::LeftMouseButtonState>() // Initialize left mouse button state
         .init_resource::<block_interaction::RightMouseButtonState>() // Initialize right mouse button state
         .init_resource::<RecipeBook>() // Initialize recipe book with default recipes
+        .insert_resource(world_save) // Initialize on-disk world save
+        .insert_resource(pending_features) // Initialize feature blocks waiting for neighbouring chunks
+        .init_resource::<world_gen::ChunkGenerationTasks>() // Initialize background terrain generation tasks
+        .init_resource::<gravity::FallingBlockAssets>() // Initialize falling block mesh and materials
         .add_plugins(bevy::pbr::MaterialPlugin::<weather::CloudMaterial>::default()) // Add cloud material plugin
         .add_plugins(bevy::pbr::MaterialPlugin::<crate::biome_material::BiomeMaterial>::default()) // Add biome material plugin
+        .add_plugins(bevy::pbr::MaterialPlugin::<ChunkMaterial>::default()) // Add chunk material plugin
         ;
 
     app.add_systems(Startup, setup)
         .add_systems(Startup, spawn_game_camera)
         .add_systems(Startup, initialize_texture_atlas)
         .add_systems(Startup, initialize_block_textures) // Use standard textures
-        .add_systems(Startup, initialize_chunk_mesh_materials) // Initialize chunk mesh materials
+        .add_systems(
+            Startup,
+            initialize_chunk_mesh_materials.after(load_procedural_textures_into_atlas),
+        ) // Initialize chunk mesh materials once the texture array is built
         .add_systems(Startup, initialize_biome_material_cache) // Initialize biome material cache
         .add_systems(
             Startup,
@@ -171,13 +257,39 @@ fn main() {
             collision_detection_system.after(player::player_movement_system),
         ) // Add collision detection system
         .add_systems(Update, generate_chunks_system) // Add chunk terrain generation system
-        .add_systems(Update, generate_chunk_meshes.after(generate_chunks_system)) // Add chunk mesh generation system
-        .add_systems(Update, update_chunk_meshes) // Add chunk mesh update system
+        .add_systems(
+            Update,
+            update_chunk_lighting_system
+                .after(generate_chunks_system)
+                .after(block_breaking_system)
+                .after(block_placement_system)
+                .after(block_update::block_update_system)
+                .after(gravity::falling_block_system),
+        ) // Add chunk lighting system
+        .add_systems(Update, update_sky_brightness) // Add sky brightness update system
+        .add_systems(
+            Update,
+            generate_chunk_meshes
+                .after(update_chunk_lighting_system)
+                .after(update_chunk_lod_system),
+        ) // Add chunk mesh generation system
+        .add_systems(
+            Update,
+            update_chunk_sky_brightness.after(update_sky_brightness),
+        ) // Add chunk material sky brightness system
         .add_systems(Update, render_chunk_meshes) // Add chunk mesh rendering system
+        .add_systems(
+            Update,
+            sort_translucent_chunk_meshes.after(render_chunk_meshes),
+        ) // Add translucent chunk face sorting system
+        .add_systems(Update, update_chunk_visibility.after(render_chunk_meshes)) // Add chunk frustum and occlusion culling system
+        .add_systems(Update, display_chunk_visibility_stats) // Add drawn chunk count display system
         .add_systems(Update, dynamic_chunk_loading_system) // Add dynamic chunk loading system
+        .add_systems(Update, update_chunk_lod_system) // Add chunk level of detail system
         .add_systems(Update, display_biome_material_stats) // Add biome material stats display system
         .add_systems(Update, update_atmospheric_scattering) // Add atmospheric scattering update system
         .add_systems(Update, update_sun_and_moon_positions) // Add sun and moon position update system
+        .add_systems(Update, update_biome_weather) // Add biome weather tracking system
         .add_systems(Update, update_weather_system) // Add weather system update
         .add_systems(Update, update_cloud_rendering) // Add cloud rendering update system
         .add_systems(Update, update_weather_particles) // Add weather particle update system
@@ -190,17 +302,29 @@ fn main() {
         .add_systems(Update, mouse_button_input_system) // Add mouse button input system
         .add_systems(Update, block_breaking_system) // Add block breaking system
         .add_systems(Update, block_placement_system) // Add block placement system
+        .add_systems(
+            Update,
+            block_update::block_update_system
+                .after(block_breaking_system)
+                .after(block_placement_system),
+        ) // Add block update system for flowing fluids and falling blocks
+        .add_systems(
+            Update,
+            gravity::falling_block_system.after(block_update::block_update_system),
+        ) // Add falling block system
         .add_systems(Update, crafting::handle_crafting_requests) // Add crafting request handling system
         .add_systems(Update, crafting::handle_crafting_success_events) // Add crafting success event handling system
         .add_systems(Update, crafting::handle_crafting_fail_events) // Add crafting fail event handling system
         .add_systems(Update, crafting::handle_crafting_keyboard_input) // Add crafting keyboard input system
+        .add_systems(
+            Last,
+            gravity::settle_falling_blocks_on_exit_system.before(save_world_on_exit_system),
+        ) // Put blocks still falling into their chunks before the world is saved
+        .add_systems(Last, save_world_on_exit_system) // Save loaded chunks when the game exits
         .run();
 }
 
 fn setup(mut commands: Commands, mut chunk_manager: ResMut<ChunkManager>) {
-    // Initialize chunk manager
-    *chunk_manager = ChunkManager::new(2); // Render distance of 2 chunks
-
     // Camera is now spawned by the spawn_game_camera system
 
     // Add light
@@ -226,7 +350,9 @@ fn generate_initial_chunks(commands: &mut Commands, chunk_manager: &mut ChunkMan
     for x in -1..=1 {
         for z in -1..=1 {
             let chunk_pos = ChunkPosition::new(x, z);
-            let chunk_entity = commands.spawn(Chunk::new(chunk_pos)).id();
+            let chunk_entity = commands
+                .spawn(Chunk::new(chunk_pos, chunk_manager.world_height))
+                .id();
 
             // Register the chunk in the manager using spatial partitioning (with existence check)
             if let Some(entity_commands) = commands.get_entity(chunk_entity) {
@@ -249,7 +375,7 @@ fn generate_initial_chunks(commands: &mut Commands, chunk_manager: &mut ChunkMan
 /// System to initialize chunk mesh materials
 fn initialize_chunk_mesh_materials(
     mut mesh_materials: ResMut<ChunkMeshMaterials>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut materials: ResMut<Assets<ChunkMaterial>>,
     texture_atlas: Res<TextureAtlas>,
 ) {
     println!("🎨 Initializing chunk mesh materials...");
@@ -311,154 +437,43 @@ fn display_biome_material_stats(
     }
 }
 
+/// Most chunk meshes being built in the background at once
+const MAX_MESH_TASKS: usize = 32;
+
 /// System to generate chunk meshes
+/// Chunks whose blocks changed are snapshotted together with the border slices of their
+/// neighbours and meshed on the async compute task pool. Finished meshes are uploaded with their
+/// materials here; a mesh whose chunk changed again while it was being built is thrown away.
 fn generate_chunk_meshes(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
-    mut biome_materials: ResMut<Assets<BiomeMaterial>>,
-    mut images: ResMut<Assets<Image>>,
-    mesh_materials: Res<ChunkMeshMaterials>,
-    chunks_without_mesh: Query<(Entity, &Chunk), Without<ChunkMesh>>,
-    all_chunks: Query<&Chunk>,
+    mut chunks: Query<(Entity, &mut Chunk)>,
     chunk_manager: Res<ChunkManager>,
     texture_atlas: Res<TextureAtlas>,
-    biome_cache: Res<crate::biome_texture_cache::SharedBiomeTextureCache>,
-    biome_material_cache: Res<crate::biome_material::SharedBiomeMaterialCache>,
+    biomes: Res<BiomeRegistry>,
+    mut mesh_tasks: ResMut<ChunkMeshTasks>,
+    mesh_settings: Res<ChunkMeshSettings>,
 ) {
-    for (chunk_entity, chunk) in chunks_without_mesh.iter().filter(|(_, c)| c.is_generated) {
-        println!(
-            "🏗️  Generating mesh for chunk ({}, {})",
-            chunk.position.x, chunk.position.z
-        );
-
-        // Generate the mesh for this chunk using neighbor-aware algorithm
-        let mesh = chunk_mesh::generate_chunk_mesh(
-            &chunk.data,
-            &chunk.position,
-            &chunk_manager,
-            &all_chunks,
-            &texture_atlas,
-            &chunk,
-            &biome_cache,
-            &mut images,
-        );
-        let mesh_handle = meshes.add(mesh);
-
-        // Create the chunk mesh component
-        let mut chunk_mesh = ChunkMesh::new();
-        chunk_mesh.mesh_handle = mesh_handle;
-
-        // Optimized: Collect unique block types first, then get materials
-        let mut unique_block_types = std::collections::HashSet::new();
-        for local_x in 0..crate::chunk::CHUNK_SIZE {
-            for local_z in 0..crate::chunk::CHUNK_SIZE {
-                for y in 0..crate::chunk::CHUNK_HEIGHT {
-                    if let Some(block_type) = chunk.data.get_block(local_x, y, local_z) {
-                        if block_type != BlockType::Air {
-                            unique_block_types.insert(block_type);
-                        }
-                    }
-                }
-            }
+    // Drop meshes of chunks that were unloaded while they were being built
+    mesh_tasks.retain(|entity| chunks.contains(entity));
+
+    for (chunk_entity, built) in mesh_tasks.take_finished() {
+        let Ok((_, chunk)) = chunks.get(chunk_entity) else {
+            continue;
+        };
+        if chunk.needs_mesh_update {
+            // The chunk changed after its snapshot was taken; a fresh one is meshed below
+            continue;
         }
 
-        // Add materials for unique block types only
-        for block_type in unique_block_types {
-            if let Some(material_handle) = mesh_materials.get_material(block_type) {
-                chunk_mesh
-                    .material_handles
-                    .insert(block_type, material_handle);
-            }
-        }
-
-        // Add biome-specific materials if available
-        if texture_atlas.has_procedural_textures() {
-            use std::collections::HashMap;
-            let mut biome_material_local_cache: HashMap<String, Handle<StandardMaterial>> =
-                HashMap::new();
-
-            // Track which biomes we've already processed to avoid duplicates
-            let mut processed_biomes: HashMap<String, bool> = HashMap::new();
-
-            for local_x in 0..crate::chunk::CHUNK_SIZE {
-                for local_z in 0..crate::chunk::CHUNK_SIZE {
-                    if let Some(biome_data) = chunk.biome_data.get_biome_data(local_x, local_z) {
-                        // Create a unique biome identifier to avoid processing the same biome multiple times
-                        // Use biome type only to drastically reduce unique biome variations
-                        // This ensures we only generate one set of textures per biome type
-                        let biome_identifier = format!("{}", biome_data.biome_type);
-
-                        // Skip if we've already processed this biome in this chunk
-                        if processed_biomes.contains_key(&biome_identifier) {
-                            continue;
-                        }
-
-                        processed_biomes.insert(biome_identifier, true);
-
-                        // Generate biome parameters once per unique biome
-                        let representative_height = match biome_data.biome_type.as_str() {
-                            "desert" => 15,
-                            "forest" => 25,
-                            "mountain" | "snowy_mountain" => 45,
-                            "hills" => 20,
-                            "plains" => 12,
-                            "swamp" => 8,
-                            "tundra" => 18,
-                            "beach" => 5,
-                            _ => 15,
-                        };
-
-                        let biome_params = crate::biome_textures::BiomeTextureParams::new(
-                            biome_data.biome_type.clone(),
-                            biome_data.temperature,
-                            biome_data.moisture,
-                            representative_height as f32,
-                            representative_height as f32 / 100.0, // Use fixed max height for now
-                        );
-
-                        // Check all block types that should have biome textures
-                        let biome_block_types = [
-                            BlockType::Grass,
-                            BlockType::Dirt,
-                            BlockType::Stone,
-                            BlockType::Sand,
-                        ];
-
-                        for block_type in biome_block_types {
-                            let texture_key = crate::biome_textures::generate_texture_cache_key(
-                                &block_type,
-                                &biome_params,
-                            );
-
-                            // Check if we already have this biome material cached for this chunk
-                            if biome_material_local_cache.contains_key(&texture_key) {
-                                continue;
-                            }
-
-                            // Try to get biome-specific material using enhanced biome material system
-                            if let Some(biome_material) = mesh_materials.get_biome_material(
-                                block_type,
-                                &biome_params,
-                                &biome_cache,
-                                &biome_material_cache,
-                                &mut materials,
-                                &mut biome_materials,
-                                &mut images,
-                            ) {
-                                // Store biome-specific material in chunk cache
-                                biome_material_local_cache
-                                    .insert(texture_key, biome_material.clone());
-                                chunk_mesh
-                                    .material_handles
-                                    .insert(block_type, biome_material);
-                                // Reduce logging spam
-                                // println!("🎨 Added biome-specific material for {:?} at biome {}", block_type, biome_params.biome_type);
-                            }
-                        }
-                    }
-                }
-            }
+        // Create the chunk mesh component with a sub-mesh for each render layer
+        let mut chunk_mesh = ChunkMesh::new();
+        chunk_mesh.connectivity = built.connectivity;
+        for (layer, mesh) in built.parts {
+            chunk_mesh.parts.push(ChunkMeshPart {
+                layer,
+                mesh_handle: meshes.add(mesh),
+            });
         }
 
         // Add the chunk mesh component to the chunk entity with existence check
@@ -473,79 +488,101 @@ fn generate_chunk_meshes(
             }
         }
     }
-}
 
-/// System to update existing chunk meshes when blocks change
-fn update_chunk_meshes(
-    mut commands: Commands,
-    mut chunks_with_mesh: Query<(Entity, &mut Chunk), With<ChunkMesh>>,
-) {
-    for (chunk_entity, mut chunk) in &mut chunks_with_mesh {
-        if chunk.is_generated && chunk.needs_mesh_update {
-            println!(
-                "🔄 Regenerating mesh for chunk ({}, {})",
-                chunk.position.x, chunk.position.z
-            );
+    // Switching mesher rebuilds every chunk
+    if mesh_settings.is_changed() && !mesh_settings.is_added() {
+        for (_, mut chunk) in chunks.iter_mut() {
+            chunk.needs_mesh_update = true;
+        }
+    }
 
-            // Remove ChunkMesh and Mesh3d components so render_chunk_meshes will recreate them
-            commands
-                .entity(chunk_entity)
-                .remove::<ChunkMesh>()
-                .remove::<Mesh3d>()
-                .remove::<MeshMaterial3d<StandardMaterial>>();
+    // Snapshot chunks whose blocks changed and start building their meshes
+    let changed: Vec<Entity> = chunks
+        .iter()
+        .filter(|(_, chunk)| {
+            chunk.is_generated && chunk.needs_mesh_update && !chunk.needs_light_update
+        })
+        .map(|(entity, _)| entity)
+        .collect();
+    for chunk_entity in changed {
+        if mesh_tasks.len() >= MAX_MESH_TASKS {
+            break;
+        }
+        let Ok((_, chunk)) = chunks.get(chunk_entity) else {
+            continue;
+        };
+        println!(
+            "🏗️  Generating mesh for chunk ({}, {})",
+            chunk.position.x, chunk.position.z
+        );
+        let neighbours = ChunkMeshSnapshot::neighbour_positions(chunk.position).map(|position| {
+            chunk_manager
+                .loaded_chunks
+                .get(&position)
+                .and_then(|&entity| chunks.get(entity).ok())
+                .map(|(_, neighbour)| neighbour)
+        });
+        let snapshot = ChunkMeshSnapshot::capture(chunk, neighbours).with_biome_tints(&biomes);
+        mesh_tasks.start(
+            chunk_entity,
+            snapshot,
+            texture_atlas.clone(),
+            *mesh_settings,
+        );
 
-            // Reset the flag to prevent infinite regeneration
+        if let Ok((_, mut chunk)) = chunks.get_mut(chunk_entity) {
             chunk.needs_mesh_update = false;
         }
     }
 }
 
 /// System to render chunk meshes
+/// Each part of a chunk's mesh is drawn by a child entity with the material of its render layer.
 fn render_chunk_meshes(
     mut commands: Commands,
-    chunk_meshes: Query<(Entity, &ChunkMesh, &Chunk)>,
-    existing_meshes: Query<Entity, With<Mesh3d>>,
+    mesh_materials: Res<ChunkMeshMaterials>,
+    chunk_meshes: Query<(Entity, Ref<ChunkMesh>, &Chunk, Option<&Children>)>,
+    sub_meshes: Query<(), With<ChunkSubMesh>>,
 ) {
-    for (entity, chunk_mesh, chunk) in &chunk_meshes {
-        // Check if this entity already has a mesh rendered to avoid duplicate insertion
-        if existing_meshes.get(entity).is_ok() {
-            continue; // Skip if already rendered
+    for (entity, chunk_mesh, chunk, children) in &chunk_meshes {
+        // Skip chunks whose current mesh is already rendered; a rebuilt mesh replaces the old one
+        if !chunk_mesh.is_changed() {
+            continue;
         }
 
         // Multiple existence checks to prevent race conditions
-        // First check: verify the entity exists
-        if commands.get_entity(entity).is_some() {
-            // Second check: can we get entity commands for it?
-            if let Some(mut entity_commands) = commands.get_entity(entity) {
-                // Third check: verify the entity hasn't been despawned
-                if entity_commands.id() == entity {
-                    // Use a deterministic material selection to prevent random material changes
-                    // Prefer Grass material, then Dirt, then first available
-                    let material_handle = chunk_mesh
-                        .material_handles
-                        .get(&BlockType::Grass)
-                        .or_else(|| chunk_mesh.material_handles.get(&BlockType::Dirt))
-                        .or_else(|| chunk_mesh.material_handles.get(&BlockType::Stone))
-                        .or_else(|| {
-                            // Sort keys deterministically and pick first
-                            let mut sorted_keys: Vec<_> =
-                                chunk_mesh.material_handles.keys().cloned().collect();
-                            sorted_keys.sort_by_key(|bt| format!("{:?}", bt));
-                            sorted_keys
-                                .first()
-                                .and_then(|bt| chunk_mesh.material_handles.get(bt))
-                        })
-                        .cloned()
-                        .unwrap_or_else(|| Handle::default());
-
-                    // Only insert components if all checks pass
-                    entity_commands.insert((
-                        Mesh3d(chunk_mesh.mesh_handle.clone()),
-                        MeshMaterial3d(material_handle),
-                        Transform::from_translation(chunk.position.min_block_position().as_vec3()),
-                    ));
-                }
+        let Some(mut entity_commands) = commands.get_entity(entity) else {
+            continue;
+        };
+        if entity_commands.id() != entity {
+            continue;
+        }
+
+        entity_commands.insert((
+            Transform::from_translation(chunk.position.min_block_position().as_vec3()),
+            Visibility::default(),
+        ));
+
+        // Drop the sub-meshes of the previous mesh
+        for &child in children.into_iter().flatten() {
+            if sub_meshes.contains(child) {
+                commands.entity(child).despawn();
+            }
+        }
+
+        for part in &chunk_mesh.parts {
+            let material_handle = mesh_materials.get_material(part.layer).unwrap_or_default();
+            let mut sub_mesh = commands.spawn((
+                ChunkSubMesh,
+                Mesh3d(part.mesh_handle.clone()),
+                MeshMaterial3d(material_handle),
+                Transform::default(),
+            ));
+            if part.layer == MeshLayer::Translucent {
+                sub_mesh.insert(TranslucentChunkMesh::default());
             }
+            let sub_mesh = sub_mesh.id();
+            commands.entity(entity).add_child(sub_mesh);
         }
     }
 }
@@ -587,11 +624,54 @@ fn spawn_player_safe(
         .insert(Collider::player());
 }
 
+/// System to pick each chunk's priority and level of detail from its distance to the player
+/// A chunk whose level of detail changes is remeshed together with its neighbours, whose border
+/// faces depend on it.
+fn update_chunk_lod_system(
+    player_query: Query<&Transform, With<player::Player>>,
+    chunk_manager: Res<ChunkManager>,
+    mut chunks: Query<&mut Chunk>,
+) {
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+    let player_chunk_pos =
+        ChunkPosition::from_block_position(player_transform.translation.floor().as_ivec3());
+
+    let mut changed = Vec::new();
+    for mut chunk in &mut chunks {
+        let position = chunk.position;
+        let is_visible = chunk.is_visible;
+        let priority =
+            chunk_manager.calculate_chunk_priority(&position, &player_chunk_pos, is_visible);
+        if chunk.priority != priority {
+            chunk.priority = priority;
+        }
+        let lod = chunk_manager.calculate_chunk_lod(&position, &player_chunk_pos);
+        if chunk.lod != lod {
+            chunk.lod = lod;
+            chunk.needs_mesh_update = true;
+            changed.push(position);
+        }
+    }
+
+    for position in changed {
+        for neighbour in position.neighbors() {
+            if let Some(&entity) = chunk_manager.loaded_chunks.get(&neighbour)
+                && let Ok(mut chunk) = chunks.get_mut(entity)
+            {
+                chunk.needs_mesh_update = true;
+            }
+        }
+    }
+}
+
 /// System for dynamic chunk loading and unloading based on player position
 fn dynamic_chunk_loading_system(
     mut commands: Commands,
     player_query: Query<&Transform, With<player::Player>>,
     mut chunk_manager: ResMut<ChunkManager>,
+    mut world_save: ResMut<WorldSave>,
     chunks: Query<&Chunk>,
     time: Res<Time>,
 ) {
@@ -621,7 +701,7 @@ fn dynamic_chunk_loading_system(
         // Log performance statistics occasionally
         if time.elapsed_secs_f64() % 10.0 < 0.1 {
             let loaded_chunks = chunk_manager.get_loaded_chunk_count();
-            let memory_usage_kb = chunk_manager.estimate_memory_usage() / 1024;
+            let memory_usage_kb = chunk_manager.estimate_memory_usage(&chunks) / 1024;
             let cache_stats = chunk_manager.get_cache_stats();
 
             println!("📊 Chunk System Stats:");
@@ -648,7 +728,7 @@ fn dynamic_chunk_loading_system(
                     println!("🗑️  Unloading chunk at ({}, {})", chunk_pos.x, chunk_pos.z);
 
                     // Before despawning, cache the chunk data for potential reuse
-                    if let Some(mut entity_commands) = commands.get_entity(chunk_entity) {
+                    if let Some(entity_commands) = commands.get_entity(chunk_entity) {
                         // Double-check entity exists before caching and despawning
                         if entity_commands.id() == chunk_entity {
                             if let Ok(chunk_component) = chunks.get(chunk_entity) {
@@ -663,10 +743,24 @@ fn dynamic_chunk_loading_system(
                                     "💾 Cached chunk data for ({}, {})",
                                     chunk_pos.x, chunk_pos.z
                                 );
+
+                                // Persist generated chunks so edits survive the cache eviction
+                                if chunk_component.is_generated
+                                    && let Err(error) = world_save.save_chunk(
+                                        chunk_pos,
+                                        &chunk_component.data,
+                                        &chunk_component.biome_data,
+                                    )
+                                {
+                                    println!(
+                                        "⚠️  Failed to save chunk ({}, {}): {}",
+                                        chunk_pos.x, chunk_pos.z, error
+                                    );
+                                }
                             }
 
-                            // Despawn the chunk entity
-                            entity_commands.despawn();
+                            // Despawn the chunk entity along with its sub-meshes
+                            entity_commands.despawn_recursive();
                         }
                     }
 
@@ -683,6 +777,11 @@ fn dynamic_chunk_loading_system(
 
         if chunks_unloaded > 0 {
             println!("🔄 Unloaded {} chunks", chunks_unloaded);
+
+            // Write the regions touched by this frame's unloads
+            if let Err(error) = world_save.flush() {
+                println!("⚠️  Failed to write region files: {}", error);
+            }
         }
 
         // Then, load new chunks that are within render distance using priority-based spatial partitioning
@@ -708,7 +807,7 @@ fn dynamic_chunk_loading_system(
                     );
 
                     // Spawn the new chunk with cached data
-                    let mut chunk = Chunk::new(chunk_pos);
+                    let mut chunk = Chunk::new(chunk_pos, chunk_manager.world_height);
                     chunk.data = cached_data.data;
                     chunk.biome_data = cached_data.biome_data;
                     chunk.is_generated = cached_data.is_generated;
@@ -723,7 +822,9 @@ fn dynamic_chunk_loading_system(
                     );
 
                     // Spawn the new chunk
-                    let chunk_entity = commands.spawn(Chunk::new(chunk_pos)).id();
+                    let chunk_entity = commands
+                        .spawn(Chunk::new(chunk_pos, chunk_manager.world_height))
+                        .id();
                     // Register the chunk in the manager with existence check
                     if let Some(entity_commands) = commands.get_entity(chunk_entity) {
                         if entity_commands.id() == chunk_entity {
@@ -795,8 +896,12 @@ fn dynamic_chunk_loading_system(
                                         );
 
                                         // Spawn the new chunk
-                                        let chunk_entity =
-                                            commands.spawn(Chunk::new(chunk_pos)).id();
+                                        let chunk_entity = commands
+                                            .spawn(Chunk::new(
+                                                chunk_pos,
+                                                chunk_manager.world_height,
+                                            ))
+                                            .id();
                                         // Register the chunk in the manager with existence check
                                         if let Some(entity_commands) =
                                             commands.get_entity(chunk_entity)
@@ -847,7 +952,9 @@ fn dynamic_chunk_loading_system(
                                     );
 
                                     // Spawn the new chunk
-                                    let chunk_entity = commands.spawn(Chunk::new(chunk_pos)).id();
+                                    let chunk_entity = commands
+                                        .spawn(Chunk::new(chunk_pos, chunk_manager.world_height))
+                                        .id();
                                     // Register the chunk in the manager with existence check
                                     if let Some(entity_commands) = commands.get_entity(chunk_entity)
                                     {
@@ -25,11 +25,11 @@ pub fn generate_simple_noise(x: f32, z: f32, settings: &NoiseSettings) -> f32 {
         let u = fade(fx);
         let v = fade(fz);
 
-        // Hash-based gradient values
-        let grad00 = hash_noise(i, j, 0);
-        let grad10 = hash_noise(i + 1, j, 1);
-        let grad01 = hash_noise(i, j + 1, 2);
-        let grad11 = hash_noise(i + 1, j + 1, 3);
+        // Hash-based gradient values, offset by the world seed
+        let grad00 = hash_noise(i, j, 0, settings.seed);
+        let grad10 = hash_noise(i + 1, j, 1, settings.seed);
+        let grad01 = hash_noise(i, j + 1, 2, settings.seed);
+        let grad11 = hash_noise(i + 1, j + 1, 3, settings.seed);
 
         // Simple interpolation
         let lerp1 = lerp(grad00, grad10, u);
@@ -63,12 +63,60 @@ pub fn generate_biome_info(x: f32, z: f32, settings: &NoiseSettings) -> (f32, f3
     };
     let temperature = generate_simple_noise(x, z, &temp_settings);
 
-    // Moisture noise
-    let moisture = generate_simple_noise(x + 1000.0, z + 1000.0, &temp_settings);
+    // Moisture noise uses its own seed so it is not correlated with temperature
+    let moisture_settings = NoiseSettings {
+        seed: derive_seed(settings.seed, MOISTURE_SEED_CHANNEL),
+        ..temp_settings
+    };
+    let moisture = generate_simple_noise(x + 1000.0, z + 1000.0, &moisture_settings);
 
     (temperature, moisture)
 }
 
+/// Generate 3D density noise in roughly [-1, 1] for volumetric terrain
+/// Uses its own seed channel so it does not repeat the heightmap pattern.
+pub fn generate_density_noise(x: f32, y: f32, z: f32, settings: &NoiseSettings) -> f32 {
+    let seed = derive_seed(settings.seed, DENSITY_SEED_CHANNEL);
+    let mut value = 0.0;
+    let mut amplitude = 1.0;
+    let mut frequency = 1.0;
+    let mut max_value = 0.0;
+
+    for _ in 0..settings.octaves {
+        let nx = x * frequency * settings.scale;
+        let ny = y * frequency * settings.scale;
+        let nz = z * frequency * settings.scale;
+
+        let i = nx.floor() as i32;
+        let j = ny.floor() as i32;
+        let k = nz.floor() as i32;
+        let u = fade(nx - i as f32);
+        let v = fade(ny - j as f32);
+        let w = fade(nz - k as f32);
+
+        // Interpolate the hashed values at the eight corners of the lattice cell
+        let corner = |di: i32, dj: i32, dk: i32| hash_noise_3d(i + di, j + dj, k + dk, seed);
+        let bottom = lerp(
+            lerp(corner(0, 0, 0), corner(1, 0, 0), u),
+            lerp(corner(0, 0, 1), corner(1, 0, 1), u),
+            w,
+        );
+        let top = lerp(
+            lerp(corner(0, 1, 0), corner(1, 1, 0), u),
+            lerp(corner(0, 1, 1), corner(1, 1, 1), u),
+            w,
+        );
+        let noise_value = lerp(bottom, top, v);
+
+        value += noise_value * amplitude;
+        max_value += amplitude;
+        amplitude *= settings.persistence;
+        frequency *= settings.lacunarity;
+    }
+
+    value / max_value
+}
+
 /// Simple fade function for smooth interpolation
 fn fade(t: f32) -> f32 {
     t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
@@ -79,9 +127,22 @@ fn lerp(a: f32, b: f32, t: f32) -> f32 {
     a + t * (b - a)
 }
 
+/// Seed channel used to decorrelate moisture from temperature
+const MOISTURE_SEED_CHANNEL: u32 = 1;
+
+/// Seed channel used for 3D terrain density
+const DENSITY_SEED_CHANNEL: u32 = 2;
+
+/// Derive an independent seed from the world seed for a separate noise channel
+pub fn derive_seed(seed: u32, channel: u32) -> u32 {
+    seed.wrapping_add(channel.wrapping_mul(0x9E3779B9))
+        .rotate_left(7)
+        ^ 0x5BD1E995
+}
+
 /// Simple hash function for noise generation
-fn hash_noise(x: i32, y: i32, seed: i32) -> f32 {
-    let mut hash = x ^ y ^ seed;
+fn hash_noise(x: i32, y: i32, corner: i32, seed: u32) -> f32 {
+    let mut hash = x ^ y ^ corner ^ (seed.wrapping_mul(0x9E3779B9) as i32);
     hash = hash.wrapping_mul(0x34567891);
     hash = hash.wrapping_add(hash >> 16);
     hash = hash.wrapping_mul(0x01234567);
@@ -90,6 +151,22 @@ fn hash_noise(x: i32, y: i32, seed: i32) -> f32 {
     (hash as f32) / (std::i32::MAX as f32)
 }
 
+/// Hash a 3D lattice point to a value in [-1, 1]
+fn hash_noise_3d(x: i32, y: i32, z: i32, seed: u32) -> f32 {
+    let mut hash = (x as u32)
+        .wrapping_mul(0x8DA6B343)
+        .wrapping_add((y as u32).wrapping_mul(0xD8163841))
+        .wrapping_add((z as u32).wrapping_mul(0xCB1AB31F))
+        ^ seed;
+    hash ^= hash >> 15;
+    hash = hash.wrapping_mul(0x2C1B3C6D);
+    hash ^= hash >> 12;
+    hash = hash.wrapping_mul(0x297A2D39);
+    hash ^= hash >> 15;
+
+    (hash as f32) / (u32::MAX as f32) * 2.0 - 1.0
+}
+
 /// Noise generation settings
 #[derive(Resource, Debug, Clone)]
 pub struct NoiseSettings {
@@ -100,6 +177,7 @@ pub struct NoiseSettings {
     pub persistence: f32,
     pub lacunarity: f32,
     pub biome_scale: f32,
+    pub seed: u32,
 }
 
 impl Default for NoiseSettings {
@@ -112,6 +190,7 @@ impl Default for NoiseSettings {
             persistence: 0.7,
             lacunarity: 1.6,
             biome_scale: 0.005,
+            seed: 0,
         }
     }
 }
@@ -0,0 +1,733 @@
+// Structure generation for Bevy Craft
+// This module builds ruins, dungeons and other multi-piece structures into generated terrain
+//
+// The world is split into a grid of structure regions, and each region may hold one start of
+// every structure kind. Starts are rolled from the world seed and the region, then laid out as
+// pieces cut from block templates. A structure can span several chunks, so every chunk replays
+// the starts of all regions close enough to reach it and writes the blocks that land inside it,
+// the same way caves are carved. Chests store nothing: their loot is rolled again from the seed
+// and the chest's position when one is opened.
+
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::ops::RangeInclusive;
+
+use crate::biome::BiomeRegistry;
+use crate::block::BlockType;
+use crate::caves::chunk_rng;
+use crate::chunk::{Chunk, ChunkPosition, CHUNK_SIZE};
+use crate::inventory::{FoodType, ItemType, ResourceType, ToolType};
+use crate::noise::{derive_seed, generate_biome_info, generate_heightmap, NoiseSettings};
+use crate::water_bodies::WaterBodies;
+use crate::world_gen::{create_noise_settings, WorldGenSettings};
+
+/// Seed channels for structure starts and the blocks and loot inside them
+const RUIN_SEED_CHANNEL: u32 = 50;
+const DUNGEON_SEED_CHANNEL: u32 = 51;
+const WEATHERING_SEED_CHANNEL: u32 = 52;
+const LOOT_SEED_CHANNEL: u32 = 53;
+
+/// Edge length, in blocks, of the regions that can each hold one start of every structure kind
+const REGION_SIZE: i32 = 96;
+
+/// Structures reach at most this many blocks from their start along either horizontal axis
+const MAX_STRUCTURE_REACH: i32 = 32;
+
+/// Chance of a dungeon under each region
+const DUNGEON_CHANCE: f32 = 0.6;
+
+/// Dungeon rooms stay at least this many blocks below the surface
+const DUNGEON_COVER: i32 = 12;
+
+/// Foundations fill gaps under a structure's floor at most this far down
+const FOUNDATION_DEPTH: i32 = 8;
+
+/// One cell of a template
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cell {
+    /// Cobblestone, sometimes mossy or crumbled away
+    Wall,
+    /// Cleared to air
+    Air,
+    /// A loot chest
+    Chest,
+}
+
+/// The blocks of one structure piece
+/// Layers run from the bottom up; each layer lists rows along z, and each row is a string of
+/// cells along x: `#` wall, `.` air, `C` chest. A space leaves the terrain untouched.
+struct Template {
+    layers: &'static [&'static [&'static str]],
+}
+
+impl Template {
+    /// Size of the template before rotation
+    fn size(&self) -> IVec3 {
+        IVec3::new(
+            self.layers[0][0].len() as i32,
+            self.layers.len() as i32,
+            self.layers[0].len() as i32,
+        )
+    }
+}
+
+/// A small house with crumbling walls, left on the surface
+const RUIN: Template = Template {
+    layers: &[
+        &[
+            "#######", "#######", "#######", "#######", "#######", "#######", "#######",
+        ],
+        &[
+            "###.###", "#.....#", "#.....#", "#.....#", "#....C#", "#.....#", "#######",
+        ],
+        &[
+            "###.###", "#.....#", ".......", "#.....#", "#.....#", "#.....#", "##...##",
+        ],
+        &[
+            "##...##", "#.....#", ".......", "#.....#", ".......", "#.....#", "#.....#",
+        ],
+        &[
+            "#.....#", ".......", ".......", ".......", ".......", ".......", "#.....#",
+        ],
+    ],
+};
+
+/// The central room of a dungeon, with a doorway in the middle of each wall
+const DUNGEON_ROOM: Template = Template {
+    layers: &[
+        &[
+            "#########",
+            "#########",
+            "#########",
+            "#########",
+            "#########",
+            "#########",
+            "#########",
+            "#########",
+            "#########",
+        ],
+        &[
+            "####.####",
+            "#C......#",
+            "#.......#",
+            "#.......#",
+            ".........",
+            "#.......#",
+            "#.......#",
+            "#......C#",
+            "####.####",
+        ],
+        &[
+            "####.####",
+            "#.......#",
+            "#.......#",
+            "#.......#",
+            ".........",
+            "#.......#",
+            "#.......#",
+            "#.......#",
+            "####.####",
+        ],
+        &[
+            "#########",
+            "#.......#",
+            "#.......#",
+            "#.......#",
+            "#.......#",
+            "#.......#",
+            "#.......#",
+            "#.......#",
+            "#########",
+        ],
+        &[
+            "#########",
+            "#.......#",
+            "#.......#",
+            "#.......#",
+            "#.......#",
+            "#.......#",
+            "#.......#",
+            "#.......#",
+            "#########",
+        ],
+        &[
+            "#########",
+            "#########",
+            "#########",
+            "#########",
+            "#########",
+            "#########",
+            "#########",
+            "#########",
+            "#########",
+        ],
+    ],
+};
+
+/// One block of dungeon corridor running along x
+const DUNGEON_CORRIDOR: Template = Template {
+    layers: &[
+        &["#", "#", "#"],
+        &["#", ".", "#"],
+        &["#", ".", "#"],
+        &["#", "#", "#"],
+    ],
+};
+
+/// A small store room at the end of a dungeon corridor
+const DUNGEON_CHAMBER: Template = Template {
+    layers: &[
+        &["#####", "#####", "#####", "#####", "#####"],
+        &["##.##", "#C..#", ".....", "#...#", "##.##"],
+        &["##.##", "#...#", ".....", "#...#", "##.##"],
+        &["#####", "#...#", "#...#", "#...#", "#####"],
+        &["#####", "#####", "#####", "#####", "#####"],
+    ],
+};
+
+/// Kinds of structure the world generates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StructureKind {
+    /// A crumbling house on the surface
+    Ruin,
+    /// Rooms and corridors deep underground
+    Dungeon,
+}
+
+/// A template placed in the world
+struct Piece {
+    template: &'static Template,
+    /// Lowest corner of the piece after rotation
+    origin: IVec3,
+    /// Quarter turns about the vertical axis
+    rotation: u8,
+    /// Chance that a wall block at the top of the piece has crumbled away, fading to none at the floor
+    decay: f32,
+    /// Chance that a wall block is mossy
+    moss: f32,
+    /// Fill gaps under the floor down to the ground
+    foundation: bool,
+    loot: LootTable,
+}
+
+impl Piece {
+    /// Size of the piece after rotation
+    fn size(&self) -> IVec3 {
+        let size = self.template.size();
+        if self.rotation % 2 == 1 {
+            IVec3::new(size.z, size.y, size.x)
+        } else {
+            size
+        }
+    }
+
+    /// Check whether any part of the piece falls in a horizontal block range, inclusive
+    fn overlaps(&self, min: IVec2, max: IVec2) -> bool {
+        let end = self.origin.xz() + self.size().xz() - IVec2::ONE;
+        self.origin.x <= max.x && end.x >= min.x && self.origin.z <= max.y && end.y >= min.y
+    }
+
+    /// Every cell of the piece with its world position and the layer it sits in
+    fn cells(&self) -> impl Iterator<Item = (IVec3, Cell, i32)> + '_ {
+        let size = self.template.size();
+        self.template
+            .layers
+            .iter()
+            .enumerate()
+            .flat_map(move |(y, rows)| {
+                rows.iter().enumerate().flat_map(move |(z, row)| {
+                    row.bytes().enumerate().filter_map(move |(x, cell)| {
+                        let cell = match cell {
+                            b'#' => Cell::Wall,
+                            b'.' => Cell::Air,
+                            b'C' => Cell::Chest,
+                            _ => return None,
+                        };
+                        let (x, z) = (x as i32, z as i32);
+                        let (x, z) = match self.rotation % 4 {
+                            0 => (x, z),
+                            1 => (size.z - 1 - z, x),
+                            2 => (size.x - 1 - x, size.z - 1 - z),
+                            _ => (z, size.x - 1 - x),
+                        };
+                        Some((self.origin + IVec3::new(x, y as i32, z), cell, y as i32))
+                    })
+                })
+            })
+    }
+
+    /// Block a cell turns into, with walls weathered by a roll unique to their position
+    fn block_at(&self, seed: u32, position: IVec3, cell: Cell, layer: i32) -> BlockType {
+        match cell {
+            Cell::Air => BlockType::Air,
+            Cell::Chest => BlockType::Chest,
+            Cell::Wall => {
+                let top = (self.template.size().y - 1).max(1);
+                let crumble = self.decay * layer as f32 / top as f32;
+                let roll = block_roll(seed, WEATHERING_SEED_CHANNEL, position);
+                if roll < crumble {
+                    BlockType::Air
+                } else if roll < crumble + self.moss {
+                    BlockType::MossyCobblestone
+                } else {
+                    BlockType::Cobblestone
+                }
+            }
+        }
+    }
+}
+
+/// A laid out structure
+struct Structure {
+    #[allow(dead_code)]
+    kind: StructureKind,
+    pieces: Vec<Piece>,
+}
+
+/// Build the structures reaching a freshly generated chunk into it
+/// Runs after caves are carved, so dungeon walls close off any tunnels crossing them.
+pub fn place_structures(chunk: &mut Chunk, settings: &WorldGenSettings, biomes: &BiomeRegistry) {
+    let min = chunk.position.min_block_position().xz();
+    let max = min + IVec2::splat(CHUNK_SIZE as i32 - 1);
+
+    for structure in structures_in(settings, biomes, min, max) {
+        for piece in structure
+            .pieces
+            .iter()
+            .filter(|piece| piece.overlaps(min, max))
+        {
+            place_piece(chunk, settings.seed, piece);
+        }
+    }
+}
+
+/// Loot in a generated chest, rolled again from the seed
+/// Returns None if no structure put a chest at the position.
+pub fn chest_loot(
+    settings: &WorldGenSettings,
+    biomes: &BiomeRegistry,
+    position: IVec3,
+) -> Option<Vec<(ItemType, u32)>> {
+    let column = position.xz();
+    structures_in(settings, biomes, column, column)
+        .iter()
+        .flat_map(|structure| &structure.pieces)
+        .find(|piece| {
+            piece
+                .cells()
+                .any(|(cell_position, cell, _)| cell == Cell::Chest && cell_position == position)
+        })
+        .map(|piece| piece.loot.roll(settings.seed, position))
+}
+
+/// Every structure starting close enough to reach a horizontal block range, inclusive
+fn structures_in(
+    settings: &WorldGenSettings,
+    biomes: &BiomeRegistry,
+    min: IVec2,
+    max: IVec2,
+) -> Vec<Structure> {
+    let mut structures = Vec::new();
+    if settings.structure_density <= 0.0 {
+        return structures;
+    }
+
+    let noise_settings = create_noise_settings(settings);
+    let mut water_bodies = WaterBodies::new(settings, &noise_settings);
+    let first = (min - IVec2::splat(MAX_STRUCTURE_REACH)).div_euclid(IVec2::splat(REGION_SIZE));
+    let last = (max + IVec2::splat(MAX_STRUCTURE_REACH)).div_euclid(IVec2::splat(REGION_SIZE));
+    for region_x in first.x..=last.x {
+        for region_z in first.y..=last.y {
+            let region = ChunkPosition::new(region_x, region_z);
+            structures.extend(ruin_start(
+                settings,
+                biomes,
+                &noise_settings,
+                &mut water_bodies,
+                region,
+            ));
+            structures.extend(dungeon_start(settings, &noise_settings, region));
+        }
+    }
+    structures
+}
+
+/// Pick a random column inside a structure region
+fn random_column(rng: &mut StdRng, region: ChunkPosition) -> IVec2 {
+    IVec2::new(
+        region.x * REGION_SIZE + rng.gen_range(0..REGION_SIZE),
+        region.z * REGION_SIZE + rng.gen_range(0..REGION_SIZE),
+    )
+}
+
+/// Roll the ruin of a region, if it has one
+/// Ruins stand on dry land in biomes that list them, with their floor on the heightmap surface.
+fn ruin_start(
+    settings: &WorldGenSettings,
+    biomes: &BiomeRegistry,
+    noise_settings: &NoiseSettings,
+    water_bodies: &mut WaterBodies,
+    region: ChunkPosition,
+) -> Option<Structure> {
+    let mut rng = chunk_rng(settings.seed, RUIN_SEED_CHANNEL, region);
+    let column = random_column(&mut rng, region);
+    let roll = rng.gen_range(0.0..1.0);
+    let rotation = rng.gen_range(0..4);
+
+    let (x, z) = (column.x as f32, column.y as f32);
+    let height = generate_heightmap(x, z, noise_settings);
+    let water = water_bodies.shape_column(column.x, column.y, height);
+    let floor = water.height as i32;
+    if floor <= water.water_level {
+        return None;
+    }
+
+    let (temperature, moisture) = generate_biome_info(x, z, noise_settings);
+    let biome = biomes.classify(temperature, moisture, floor - settings.sea_level);
+    if roll >= biome.ruin_chance() * settings.structure_density {
+        return None;
+    }
+
+    let half = RUIN.size() / 2;
+    Some(Structure {
+        kind: StructureKind::Ruin,
+        pieces: vec![Piece {
+            template: &RUIN,
+            origin: IVec3::new(column.x - half.x, floor, column.y - half.z),
+            rotation,
+            decay: 0.6,
+            moss: 0.3,
+            foundation: true,
+            loot: LootTable::Ruin,
+        }],
+    })
+}
+
+/// Roll the dungeon of a region, if it has one
+/// A dungeon is a room with corridors leading out of some of its doorways, each of which may end
+/// in a small chamber.
+fn dungeon_start(
+    settings: &WorldGenSettings,
+    noise_settings: &NoiseSettings,
+    region: ChunkPosition,
+) -> Option<Structure> {
+    let mut rng = chunk_rng(settings.seed, DUNGEON_SEED_CHANNEL, region);
+    if rng.gen_range(0.0..1.0) >= DUNGEON_CHANCE * settings.structure_density {
+        return None;
+    }
+
+    let column = random_column(&mut rng, region);
+    let surface = generate_heightmap(column.x as f32, column.y as f32, noise_settings) as i32;
+    let lowest = settings.world_height.min_y + 2;
+    let floor = rng
+        .gen_range(lowest..=lowest.max(-DUNGEON_COVER))
+        .min(surface - DUNGEON_COVER - DUNGEON_ROOM.size().y)
+        .max(lowest);
+
+    let room = DUNGEON_ROOM.size();
+    let origin = IVec3::new(column.x - room.x / 2, floor, column.y - room.z / 2);
+    let dungeon_piece = |template, origin, rotation| Piece {
+        template,
+        origin,
+        rotation,
+        decay: 0.0,
+        moss: 0.4,
+        foundation: false,
+        loot: LootTable::Dungeon,
+    };
+    let mut pieces = vec![dungeon_piece(&DUNGEON_ROOM, origin, 0)];
+
+    let center = origin + IVec3::new(room.x / 2, 0, room.z / 2);
+    for direction in [IVec3::X, IVec3::NEG_X, IVec3::Z, IVec3::NEG_Z] {
+        if rng.gen_range(0.0..1.0) > 0.6 {
+            continue;
+        }
+        let length = rng.gen_range(4..=12);
+        let has_chamber = rng.gen_range(0.0..1.0) < 0.5;
+
+        // Corridors leave through the doorway in the middle of the wall
+        let doorway = center + direction * (room.x / 2);
+        let across = IVec3::new(direction.z.abs(), 0, direction.x.abs());
+        let rotation = if direction.x != 0 { 0 } else { 1 };
+        for step in 1..=length {
+            let corridor_origin = doorway + direction * step - across;
+            pieces.push(dungeon_piece(&DUNGEON_CORRIDOR, corridor_origin, rotation));
+        }
+
+        if has_chamber {
+            let chamber = DUNGEON_CHAMBER.size();
+            let chamber_center = doorway + direction * (length + 1 + chamber.x / 2);
+            let chamber_origin = chamber_center - IVec3::new(chamber.x / 2, 0, chamber.z / 2);
+            pieces.push(dungeon_piece(&DUNGEON_CHAMBER, chamber_origin, 0));
+        }
+    }
+
+    Some(Structure {
+        kind: StructureKind::Dungeon,
+        pieces,
+    })
+}
+
+/// Write the blocks of a piece that fall inside a chunk
+fn place_piece(chunk: &mut Chunk, seed: u32, piece: &Piece) {
+    let chunk_min = chunk.position.min_block_position();
+    for (position, cell, layer) in piece.cells() {
+        let local = position - chunk_min;
+        if local.x < 0
+            || local.z < 0
+            || local.x >= CHUNK_SIZE as i32
+            || local.z >= CHUNK_SIZE as i32
+        {
+            continue;
+        }
+        let (local_x, local_z) = (local.x as usize, local.z as usize);
+        let block = piece.block_at(seed, position, cell, layer);
+        if block == BlockType::Air {
+            chunk.data.clear_block(local_x, position.y, local_z);
+        } else {
+            chunk.data.set_block(local_x, position.y, local_z, block);
+        }
+
+        // Prop the floor up on cobblestone wherever the ground falls away below it
+        if piece.foundation && layer == 0 && block != BlockType::Air {
+            for y in (position.y - FOUNDATION_DEPTH..position.y).rev() {
+                match chunk.data.get_block(local_x, y, local_z) {
+                    None | Some(BlockType::Air) | Some(BlockType::Water) => {
+                        chunk
+                            .data
+                            .set_block(local_x, y, local_z, BlockType::Cobblestone)
+                    }
+                    Some(_) => break,
+                }
+            }
+        }
+    }
+}
+
+/// Hash of a block position on a seed channel
+fn block_hash(seed: u32, channel: u32, position: IVec3) -> u64 {
+    let mut hash = derive_seed(seed, channel) as u64;
+    for value in [position.x, position.y, position.z] {
+        hash = (hash ^ value as u32 as u64).wrapping_mul(0x9E3779B97F4A7C15);
+        hash ^= hash >> 29;
+    }
+    hash
+}
+
+/// Roll in [0, 1) that is always the same for a block position
+fn block_roll(seed: u32, channel: u32, position: IVec3) -> f32 {
+    (block_hash(seed, channel, position) >> 40) as f32 / (1u64 << 24) as f32
+}
+
+/// Which loot a chest is filled with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LootTable {
+    Ruin,
+    Dungeon,
+}
+
+/// One kind of item a loot table can give
+struct LootEntry {
+    item: ItemType,
+    count: RangeInclusive<u32>,
+    weight: u32,
+}
+
+const RUIN_LOOT: [LootEntry; 6] = [
+    LootEntry {
+        item: ItemType::Food(FoodType::Bread),
+        count: 1..=3,
+        weight: 4,
+    },
+    LootEntry {
+        item: ItemType::Food(FoodType::Apple),
+        count: 1..=4,
+        weight: 4,
+    },
+    LootEntry {
+        item: ItemType::Resource(ResourceType::Stick),
+        count: 2..=6,
+        weight: 3,
+    },
+    LootEntry {
+        item: ItemType::Resource(ResourceType::Coal),
+        count: 2..=8,
+        weight: 4,
+    },
+    LootEntry {
+        item: ItemType::Resource(ResourceType::IronIngot),
+        count: 1..=3,
+        weight: 2,
+    },
+    LootEntry {
+        item: ItemType::Tool(ToolType::Pickaxe),
+        count: 1..=1,
+        weight: 1,
+    },
+];
+
+const DUNGEON_LOOT: [LootEntry; 6] = [
+    LootEntry {
+        item: ItemType::Resource(ResourceType::Coal),
+        count: 4..=12,
+        weight: 4,
+    },
+    LootEntry {
+        item: ItemType::Resource(ResourceType::IronIngot),
+        count: 2..=6,
+        weight: 4,
+    },
+    LootEntry {
+        item: ItemType::Resource(ResourceType::GoldIngot),
+        count: 1..=4,
+        weight: 3,
+    },
+    LootEntry {
+        item: ItemType::Resource(ResourceType::Diamond),
+        count: 1..=2,
+        weight: 1,
+    },
+    LootEntry {
+        item: ItemType::Resource(ResourceType::String),
+        count: 2..=6,
+        weight: 3,
+    },
+    LootEntry {
+        item: ItemType::Food(FoodType::Bread),
+        count: 1..=3,
+        weight: 2,
+    },
+];
+
+impl LootTable {
+    fn entries(self) -> &'static [LootEntry] {
+        match self {
+            LootTable::Ruin => &RUIN_LOOT,
+            LootTable::Dungeon => &DUNGEON_LOOT,
+        }
+    }
+
+    /// How many times the table is drawn from for one chest
+    fn draws(self) -> RangeInclusive<u32> {
+        match self {
+            LootTable::Ruin => 2..=4,
+            LootTable::Dungeon => 4..=7,
+        }
+    }
+
+    /// Roll the contents of a chest of this table at a position
+    /// Items drawn more than once are merged into a single stack.
+    pub fn roll(self, seed: u32, position: IVec3) -> Vec<(ItemType, u32)> {
+        let mut rng = StdRng::seed_from_u64(block_hash(seed, LOOT_SEED_CHANNEL, position));
+        let entries = self.entries();
+        let total_weight: u32 = entries.iter().map(|entry| entry.weight).sum();
+
+        let mut loot: Vec<(ItemType, u32)> = Vec::new();
+        for _ in 0..rng.gen_range(self.draws()) {
+            let mut pick = rng.gen_range(0..total_weight);
+            let entry = entries
+                .iter()
+                .find(|entry| {
+                    if pick < entry.weight {
+                        true
+                    } else {
+                        pick -= entry.weight;
+                        false
+                    }
+                })
+                .unwrap_or(&entries[0]);
+            let count = rng.gen_range(entry.count.clone());
+            match loot.iter_mut().find(|(item, _)| *item == entry.item) {
+                Some((_, total)) => *total += count,
+                None => loot.push((entry.item, count)),
+            }
+        }
+        loot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::WorldHeight;
+    use std::collections::HashMap;
+
+    /// First dungeon found in the regions around the origin
+    fn find_dungeon(settings: &WorldGenSettings) -> Structure {
+        let noise_settings = create_noise_settings(settings);
+        (0..16)
+            .find_map(|index| {
+                dungeon_start(
+                    settings,
+                    &noise_settings,
+                    ChunkPosition::new(index % 4, index / 4),
+                )
+            })
+            .expect("expected a dungeon near the origin")
+    }
+
+    #[test]
+    fn test_dungeon_is_built_the_same_across_chunk_borders() {
+        let settings = WorldGenSettings {
+            seed: 11,
+            ..default()
+        };
+        let biomes = BiomeRegistry::builtin();
+        let dungeon = find_dungeon(&settings);
+
+        // Generate every chunk the dungeon touches, each one on its own
+        let mut chunks: HashMap<ChunkPosition, Chunk> = HashMap::new();
+        for piece in &dungeon.pieces {
+            for (position, _, _) in piece.cells() {
+                let chunk_pos = ChunkPosition::from_block_position(position);
+                chunks.entry(chunk_pos).or_insert_with(|| {
+                    let mut chunk = Chunk::new(chunk_pos, WorldHeight::default());
+                    place_structures(&mut chunk, &settings, &biomes);
+                    chunk
+                });
+            }
+        }
+        assert!(chunks.len() > 1, "expected the dungeon to span chunks");
+
+        for piece in &dungeon.pieces {
+            for (position, cell, layer) in piece.cells() {
+                let chunk = &chunks[&ChunkPosition::from_block_position(position)];
+                let local = position - chunk.position.min_block_position();
+                let expected = match piece.block_at(settings.seed, position, cell, layer) {
+                    BlockType::Air => None,
+                    block => Some(block),
+                };
+                assert_eq!(
+                    chunk
+                        .data
+                        .get_block(local.x as usize, position.y, local.z as usize),
+                    expected,
+                    "mismatch at {position}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_chests_hold_the_same_loot_every_time() {
+        let settings = WorldGenSettings {
+            seed: 11,
+            ..default()
+        };
+        let biomes = BiomeRegistry::builtin();
+        let dungeon = find_dungeon(&settings);
+        let (chest, _, _) = dungeon.pieces[0]
+            .cells()
+            .find(|(_, cell, _)| *cell == Cell::Chest)
+            .unwrap();
+
+        let loot = chest_loot(&settings, &biomes, chest).expect("expected loot in the chest");
+        assert!(!loot.is_empty());
+        assert!(loot.iter().all(|(_, count)| *count > 0));
+        assert_eq!(chest_loot(&settings, &biomes, chest), Some(loot));
+
+        // Only chest positions hold loot
+        assert_eq!(chest_loot(&settings, &biomes, chest + IVec3::Y), None);
+    }
+}
@@ -1,11 +1,22 @@
 // Texture atlas system for Bevy Craft
 // This module handles loading and managing the texture atlas for block textures
+//
+// Chunks are drawn from a texture array holding one layer per block texture named in the block
+// registry, built from the procedural block textures once they're generated. Faces without a
+// texture of their own get a layer filled with their block's plain colour.
 
 use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{
+    Extent3d, TextureDimension, TextureFormat, TextureViewDescriptor, TextureViewDimension,
+};
 use std::collections::HashMap;
 
-use crate::block::BlockType;
-use crate::texture_gen::BlockTextures;
+use crate::block::{BlockDefinition, BlockRegistry, BlockType};
+use crate::texture_gen::{repeating_sampler, BlockTextures};
+
+/// Width and height of each layer of the block texture array
+pub const TEXTURE_ARRAY_SIZE: u32 = 128;
 
 /// Enum representing different faces of a block
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -16,18 +27,22 @@ pub enum BlockFace {
 }
 
 /// Resource that stores the loaded texture atlas
-#[derive(Resource, Debug)]
+#[derive(Resource, Debug, Clone)]
 pub struct TextureAtlas {
     /// Handle to the texture atlas image
     pub texture_handle: Handle<Image>,
     /// Map of block types to their UV coordinates in the atlas for each face
     pub block_face_uvs: HashMap<BlockType, HashMap<BlockFace, (f32, f32, f32, f32)>>, // (u_min, v_min, u_max, v_max)
-    /// Map of block types to their procedural texture handles
-    pub procedural_textures: HashMap<BlockType, Handle<Image>>,
+    /// Map of texture names to their procedural texture handles
+    pub procedural_textures: HashMap<String, Handle<Image>>,
     /// Flag indicating if the atlas is loaded
     pub is_loaded: bool,
     /// Flag indicating if procedural textures are available
     pub has_procedural_textures: bool,
+    /// Handle to the texture array chunks are drawn from
+    pub texture_array: Handle<Image>,
+    /// Layer of each block face in the texture array
+    pub texture_layers: HashMap<(BlockType, BlockFace), u32>,
 }
 
 impl Default for TextureAtlas {
@@ -38,6 +53,8 @@ impl Default for TextureAtlas {
             procedural_textures: HashMap::new(),
             is_loaded: false,
             has_procedural_textures: false,
+            texture_array: Handle::default(),
+            texture_layers: HashMap::new(),
         }
     }
 }
@@ -164,6 +181,36 @@ impl TextureAtlas {
         bedrock_uvs.insert(BlockFace::Bottom, bedrock_uv);
         self.block_face_uvs.insert(BlockType::Bedrock, bedrock_uvs);
 
+        // Ore blocks: the atlas has no ore cells, so they share the stone cell.
+        // Their specks come from the procedural ore textures instead.
+        for ore in [
+            BlockType::CoalOre,
+            BlockType::IronOre,
+            BlockType::GoldOre,
+            BlockType::DiamondOre,
+        ] {
+            let mut ore_uvs = HashMap::new();
+            ore_uvs.insert(BlockFace::Top, stone_uv);
+            ore_uvs.insert(BlockFace::Side, stone_uv);
+            ore_uvs.insert(BlockFace::Bottom, stone_uv);
+            self.block_face_uvs.insert(ore, ore_uvs);
+        }
+
+        // Structure blocks are procedural too: cobblestone shares the stone cell and chests
+        // share the wood cell
+        let wood_top_uv = (CELL_WIDTH * 3.0, 0.0, CELL_WIDTH * 4.0, CELL_HEIGHT);
+        for (block_type, uv) in [
+            (BlockType::Cobblestone, stone_uv),
+            (BlockType::MossyCobblestone, stone_uv),
+            (BlockType::Chest, wood_top_uv),
+        ] {
+            let mut structure_uvs = HashMap::new();
+            structure_uvs.insert(BlockFace::Top, uv);
+            structure_uvs.insert(BlockFace::Side, uv);
+            structure_uvs.insert(BlockFace::Bottom, uv);
+            self.block_face_uvs.insert(block_type, structure_uvs);
+        }
+
         self.texture_handle = texture_handle;
         self.is_loaded = true;
 
@@ -175,6 +222,7 @@ impl TextureAtlas {
     }
 
     /// Get UV coordinates for a specific block type and face
+    #[allow(dead_code)]
     pub fn get_uv(&self, block_type: BlockType, face: BlockFace) -> (f32, f32, f32, f32) {
         self.block_face_uvs
             .get(&block_type)
@@ -184,11 +232,13 @@ impl TextureAtlas {
     }
 
     /// Check if the texture atlas is loaded
+    #[allow(dead_code)]
     pub fn is_loaded(&self) -> bool {
         self.is_loaded
     }
 
     /// Get the texture handle
+    #[allow(dead_code)]
     pub fn texture_handle(&self) -> &Handle<Image> {
         &self.texture_handle
     }
@@ -217,23 +267,12 @@ impl TextureAtlas {
             println!("  Debug: Available texture: {} - {:?}", name, handle);
         }
 
-        // Map block type names to BlockType enum
-        let block_type_mapping = [
-            ("stone", BlockType::Stone),
-            ("dirt", BlockType::Dirt),
-            ("grass", BlockType::Grass),
-            ("wood", BlockType::Wood),
-            ("sand", BlockType::Sand),
-            ("water", BlockType::Water),
-            ("bedrock", BlockType::Bedrock),
-            ("leaves", BlockType::Leaves),
-        ];
-
-        for (name, block_type) in block_type_mapping {
+        // Pick out the textures the registry's blocks are drawn with
+        for name in BlockRegistry::global().texture_names() {
             if let Some(texture_handle) = block_textures.textures.get(name) {
                 self.procedural_textures
-                    .insert(block_type, texture_handle.clone());
-                println!("  ✓ Loaded procedural texture for {:?}", block_type);
+                    .insert(name.to_string(), texture_handle.clone());
+                println!("  ✓ Loaded procedural texture {}", name);
             } else {
                 println!("  ⚠️  No procedural texture found for: {}", name);
             }
@@ -248,12 +287,18 @@ impl TextureAtlas {
         }
     }
 
-    /// Get procedural texture handle for a block type, if available
-    pub fn get_procedural_texture(&self, block_type: BlockType) -> Option<&Handle<Image>> {
-        self.procedural_textures.get(&block_type)
+    /// Get procedural texture handle for a face of a block type, if available
+    #[allow(dead_code)]
+    pub fn get_procedural_texture(
+        &self,
+        block_type: BlockType,
+        face: BlockFace,
+    ) -> Option<&Handle<Image>> {
+        self.procedural_textures.get(block_type.texture_name(face)?)
     }
 
     /// Get biome-specific texture handle for a block type, if available
+    #[allow(dead_code)]
     pub fn get_biome_texture(
         &self,
         block_type: BlockType,
@@ -281,9 +326,98 @@ impl TextureAtlas {
     }
 
     /// Check if procedural textures are available
+    #[allow(dead_code)]
     pub fn has_procedural_textures(&self) -> bool {
         self.has_procedural_textures
     }
+
+    /// Layer of the texture on a face of a block type in the texture array
+    pub fn texture_layer(&self, block_type: BlockType, face: BlockFace) -> u32 {
+        self.texture_layers
+            .get(&(block_type, face))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Build the texture array chunks are drawn from, one layer per block texture
+    /// Procedural textures are resampled to the size of the array; faces without a texture, or
+    /// whose texture wasn't generated, get a layer of their block's plain colour.
+    pub fn build_texture_array(&mut self, images: &mut Assets<Image>) {
+        let mut data = Vec::new();
+        let mut layer_count = 0;
+        let mut push_layer = |layer: Vec<u8>| {
+            data.extend(layer);
+            layer_count += 1;
+            layer_count - 1
+        };
+
+        let mut named_layers = HashMap::new();
+        for block in BlockRegistry::global().iter() {
+            // Blocks with neither a texture nor a colour, like air, are never drawn
+            if block.color.is_none() && block.textures.is_empty() {
+                continue;
+            }
+            let mut color_layer = None;
+            for face in [BlockFace::Top, BlockFace::Side, BlockFace::Bottom] {
+                let layer = match block.textures.get(face) {
+                    Some(name) => *named_layers.entry(name).or_insert_with(|| {
+                        let texture = self
+                            .procedural_textures
+                            .get(name)
+                            .and_then(|handle| images.get(handle))
+                            .filter(|image| {
+                                image.texture_descriptor.format == TextureFormat::Rgba8UnormSrgb
+                            });
+                        push_layer(texture.map_or_else(|| color_layer_data(block), resample_layer))
+                    }),
+                    None => *color_layer.get_or_insert_with(|| push_layer(color_layer_data(block))),
+                };
+                self.texture_layers
+                    .insert((block.block_type(), face), layer);
+            }
+        }
+
+        let mut image = Image::new(
+            Extent3d {
+                width: TEXTURE_ARRAY_SIZE,
+                height: TEXTURE_ARRAY_SIZE,
+                depth_or_array_layers: layer_count,
+            },
+            TextureDimension::D2,
+            data,
+            TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::default(),
+        );
+        image.texture_view_descriptor = Some(TextureViewDescriptor {
+            dimension: Some(TextureViewDimension::D2Array),
+            ..default()
+        });
+        image.sampler = repeating_sampler();
+        self.texture_array = images.add(image);
+
+        println!("✓ Built block texture array with {} layers", layer_count);
+    }
+}
+
+/// One layer of the texture array filled with a block's plain colour
+fn color_layer_data(block: &BlockDefinition) -> Vec<u8> {
+    let color = block.block_type().color().to_srgba().to_u8_array();
+    color.repeat((TEXTURE_ARRAY_SIZE * TEXTURE_ARRAY_SIZE) as usize)
+}
+
+/// Resample an RGBA image to one layer of the texture array, picking the nearest pixel
+fn resample_layer(image: &Image) -> Vec<u8> {
+    let (width, height) = (image.width().max(1), image.height().max(1));
+    let mut layer = Vec::with_capacity((TEXTURE_ARRAY_SIZE * TEXTURE_ARRAY_SIZE * 4) as usize);
+    for y in 0..TEXTURE_ARRAY_SIZE {
+        for x in 0..TEXTURE_ARRAY_SIZE {
+            let source_x = x * width / TEXTURE_ARRAY_SIZE;
+            let source_y = y * height / TEXTURE_ARRAY_SIZE;
+            let index = ((source_y * width + source_x) * 4) as usize;
+            layer.extend_from_slice(image.data.get(index..index + 4).unwrap_or(&[255; 4]));
+        }
+    }
+    layer
 }
 
 /// System to initialize the texture atlas
@@ -299,6 +433,8 @@ pub fn initialize_texture_atlas(
 pub fn load_procedural_textures_into_atlas(
     mut texture_atlas: ResMut<TextureAtlas>,
     block_textures: Res<BlockTextures>,
+    mut images: ResMut<Assets<Image>>,
 ) {
     texture_atlas.load_procedural_textures(&block_textures);
+    texture_atlas.build_texture_array(&mut images);
 }
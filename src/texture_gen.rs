@@ -1,6 +1,7 @@
 // Simplified texture generation module
 // This module provides a minimal interface for texture generation
 
+use bevy::image::{ImageAddressMode, ImageSampler, ImageSamplerDescriptor};
 use bevy::prelude::*;
 use bevy::render::render_asset::RenderAssetUsages;
 use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
@@ -63,6 +64,15 @@ impl TextureGenSettings {
 #[derive(Component)]
 pub struct ProceduralTexture;
 
+/// Sampler that repeats the texture, so one merged quad can tile it across several blocks
+pub fn repeating_sampler() -> ImageSampler {
+    ImageSampler::Descriptor(ImageSamplerDescriptor {
+        address_mode_u: ImageAddressMode::Repeat,
+        address_mode_v: ImageAddressMode::Repeat,
+        ..ImageSamplerDescriptor::linear()
+    })
+}
+
 /// Component to store an image handle on an entity
 #[derive(Component)]
 pub struct EntityImageHandle {
@@ -87,7 +97,7 @@ pub fn generate_procedural_textures(
         );
 
         // Create a new image for the procedural texture
-        let image = Image::new(
+        let mut image = Image::new(
             Extent3d {
                 width: settings.texture_size.x,
                 height: settings.texture_size.y,
@@ -98,6 +108,7 @@ pub fn generate_procedural_textures(
             TextureFormat::Rgba8UnormSrgb,
             RenderAssetUsages::default(),
         );
+        image.sampler = repeating_sampler();
 
         // Add the image to assets
         let image_handle = images.add(image);
@@ -175,10 +186,8 @@ pub fn initialize_block_textures(
     if block_textures.textures.is_empty() {
         println!("ℹ Generating basic textures");
 
-        // Generate textures for different block types
-        let block_types = [
-            "stone", "dirt", "grass", "wood", "sand", "water", "bedrock", "leaves",
-        ];
+        // Generate every texture the registry's blocks are drawn with
+        let block_types = crate::block::BlockRegistry::global().texture_names();
 
         for block_type in block_types {
             // Create settings for this block type
@@ -193,7 +202,7 @@ pub fn initialize_block_textures(
             );
 
             // Create image
-            let image = Image::new(
+            let mut image = Image::new(
                 Extent3d {
                     width: block_settings.texture_size.x,
                     height: block_settings.texture_size.y,
@@ -204,6 +213,7 @@ pub fn initialize_block_textures(
                 TextureFormat::Rgba8UnormSrgb,
                 RenderAssetUsages::default(),
             );
+            image.sampler = repeating_sampler();
 
             // Add to assets and store handle
             let image_handle = images.add(image);
@@ -250,7 +260,7 @@ pub fn regenerate_dynamic_textures(
             );
 
             // Create new image
-            let image = Image::new(
+            let mut image = Image::new(
                 Extent3d {
                     width: dynamic_texture.settings.texture_size.x,
                     height: dynamic_texture.settings.texture_size.y,
@@ -261,6 +271,7 @@ pub fn regenerate_dynamic_textures(
                 TextureFormat::Rgba8UnormSrgb,
                 RenderAssetUsages::default(),
             );
+            image.sampler = repeating_sampler();
 
             // Replace the texture in assets
             let new_image_handle = images.add(image);
@@ -393,19 +404,21 @@ pub fn generate_biome_texture_data(
 
     // Adjust noise parameters based on biome
     let base_scale = match block_type {
-        "stone" => 0.1,
+        "stone" | "coal_ore" | "iron_ore" | "gold_ore" | "diamond_ore" => 0.1,
+        "cobblestone" | "mossy_cobblestone" => 0.15,
         "dirt" => 0.08,
         "grass" => 0.07,
-        "wood" => 0.06,
+        "wood" | "chest" => 0.06,
         "sand" => 0.09,
         _ => 0.05,
     };
 
     let base_octaves = match block_type {
-        "stone" => 6,
+        "stone" | "coal_ore" | "iron_ore" | "gold_ore" | "diamond_ore" => 6,
+        "cobblestone" | "mossy_cobblestone" => 5,
         "dirt" => 5,
         "grass" => 4,
-        "wood" => 4,
+        "wood" | "chest" => 4,
         "sand" => 3,
         _ => 4,
     };
@@ -491,53 +504,73 @@ fn apply_biome_color_modifications(
         "water" => water_color(noise_value),
         "bedrock" => bedrock_color(noise_value),
         "leaves" => leaves_color(noise_value),
+        "coal_ore" => coal_ore_color(noise_value),
+        "iron_ore" => iron_ore_color(noise_value),
+        "gold_ore" => gold_ore_color(noise_value),
+        "diamond_ore" => diamond_ore_color(noise_value),
+        "cobblestone" => cobblestone_color(noise_value),
+        "mossy_cobblestone" => mossy_cobblestone_color(noise_value),
+        "chest" => chest_color(noise_value),
         _ => natural_color(noise_value),
     };
 
     // Apply biome-specific color modifications
+    // Each effect fades in over a band around its threshold instead of switching on at it, so
+    // blended climate values along biome borders give blended colours.
     let mut r = base_color[0] as f32;
     let mut g = base_color[1] as f32;
     let mut b = base_color[2] as f32;
 
     // Temperature effects
-    if biome_params.temperature > 0.7 {
-        // Hot biomes - more red/yellow tones
-        r = (r as f32 * 1.2).min(255.0);
-        g = (g as f32 * 0.9).min(255.0);
-    } else if biome_params.temperature < 0.3 {
-        // Cold biomes - more blue tones
-        b = (b as f32 * 1.3).min(255.0);
-        r = (r as f32 * 0.8).min(255.0);
-    }
+    let hot = smoothstep(0.6, 0.8, biome_params.temperature);
+    let cold = 1.0 - smoothstep(0.2, 0.4, biome_params.temperature);
+    // Hot biomes - more red/yellow tones
+    r = (r * lerp(1.0, 1.2, hot)).min(255.0);
+    g = (g * lerp(1.0, 0.9, hot)).min(255.0);
+    // Cold biomes - more blue tones
+    b = (b * lerp(1.0, 1.3, cold)).min(255.0);
+    r = (r * lerp(1.0, 0.8, cold)).min(255.0);
 
     // Moisture effects
-    if biome_params.moisture > 0.7 {
-        // Wet biomes - more green/blue tones
-        g = (g as f32 * 1.1).min(255.0);
-        b = (b as f32 * 1.1).min(255.0);
-    } else if biome_params.moisture < 0.3 {
-        // Dry biomes - more brown/red tones
-        r = (r as f32 * 1.1).min(255.0);
-        g = (g as f32 * 0.9).min(255.0);
-        b = (b as f32 * 0.8).min(255.0);
-    }
+    let wet = smoothstep(0.6, 0.8, biome_params.moisture);
+    let dry = 1.0 - smoothstep(0.2, 0.4, biome_params.moisture);
+    // Wet biomes - more green/blue tones
+    g = (g * lerp(1.0, 1.1, wet)).min(255.0);
+    b = (b * lerp(1.0, 1.1, wet)).min(255.0);
+    // Dry biomes - more brown/red tones
+    r = (r * lerp(1.0, 1.1, dry)).min(255.0);
+    g = (g * lerp(1.0, 0.9, dry)).min(255.0);
+    b = (b * lerp(1.0, 0.8, dry)).min(255.0);
 
     // Height effects
-    if biome_params.relative_height > 0.8 {
-        // High altitude - lighter colors
-        r = (r as f32 * 1.1).min(255.0);
-        g = (g as f32 * 1.1).min(255.0);
-        b = (b as f32 * 1.1).min(255.0);
-    } else if biome_params.relative_height < 0.2 {
-        // Low altitude - darker colors
-        r = (r as f32 * 0.9).min(255.0);
-        g = (g as f32 * 0.9).min(255.0);
-        b = (b as f32 * 0.9).min(255.0);
-    }
+    let high = smoothstep(0.7, 0.9, biome_params.relative_height);
+    let low = 1.0 - smoothstep(0.1, 0.3, biome_params.relative_height);
+    // High altitude - lighter colors, low altitude - darker colors
+    let brightness = lerp(1.0, 1.1, high) * lerp(1.0, 0.9, low);
+    r = (r * brightness).min(255.0);
+    g = (g * brightness).min(255.0);
+    b = (b * brightness).min(255.0);
+
+    // Biome tint from the registry
+    let (tint_r, tint_g, tint_b) = biome_params.tint;
+    r = (r * tint_r).min(255.0);
+    g = (g * tint_g).min(255.0);
+    b = (b * tint_b).min(255.0);
 
     [r as u8, g as u8, b as u8, 255]
 }
 
+/// Smooth 0..1 ramp of `value` between `edge0` and `edge1`
+fn smoothstep(edge0: f32, edge1: f32, value: f32) -> f32 {
+    let t = ((value - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// Linear interpolation between `a` and `b`
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
 /// Simple hash function for noise generation
 fn hash_noise(x: i32, y: i32, seed: i32) -> f32 {
     let mut hash = x ^ y ^ seed;
@@ -565,6 +598,13 @@ fn noise_to_color_with_scheme(noise_value: f32, scheme: &str) -> [u8; 4] {
         "water" => water_color(noise_value),
         "bedrock" => bedrock_color(noise_value),
         "leaves" => leaves_color(noise_value),
+        "coal_ore" => coal_ore_color(noise_value),
+        "iron_ore" => iron_ore_color(noise_value),
+        "gold_ore" => gold_ore_color(noise_value),
+        "diamond_ore" => diamond_ore_color(noise_value),
+        "cobblestone" => cobblestone_color(noise_value),
+        "mossy_cobblestone" => mossy_cobblestone_color(noise_value),
+        "chest" => chest_color(noise_value),
         _ => natural_color(noise_value), // Default natural scheme
     }
 }
@@ -643,5 +683,73 @@ fn leaves_color(noise_value: f32) -> [u8; 4] {
     let r = 32 + (noise_value * 32.0) as u8;
     let g = 128 + (noise_value * 64.0) as u8;
     let b = 32 + (noise_value * 16.0) as u8;
-    [r, g, b, 255]
+    // Gaps between the leaves where the noise dips, cut out by the leaves material
+    let a = if noise_value < 0.2 { 0 } else { 255 };
+    [r, g, b, a]
+}
+
+/// Ore color scheme: stone with specks of the ore color where the noise peaks
+fn ore_color(noise_value: f32, speck: [u8; 3]) -> [u8; 4] {
+    if noise_value > 0.55 {
+        let shade = 0.8 + (noise_value - 0.55) * 0.4;
+        let r = (speck[0] as f32 * shade).min(255.0) as u8;
+        let g = (speck[1] as f32 * shade).min(255.0) as u8;
+        let b = (speck[2] as f32 * shade).min(255.0) as u8;
+        [r, g, b, 255]
+    } else {
+        stone_color(noise_value)
+    }
+}
+
+/// Coal ore color scheme
+fn coal_ore_color(noise_value: f32) -> [u8; 4] {
+    ore_color(noise_value, [40, 40, 40])
+}
+
+/// Iron ore color scheme
+fn iron_ore_color(noise_value: f32) -> [u8; 4] {
+    ore_color(noise_value, [216, 175, 147])
+}
+
+/// Gold ore color scheme
+fn gold_ore_color(noise_value: f32) -> [u8; 4] {
+    ore_color(noise_value, [252, 238, 75])
+}
+
+/// Diamond ore color scheme
+fn diamond_ore_color(noise_value: f32) -> [u8; 4] {
+    ore_color(noise_value, [93, 236, 245])
+}
+
+/// Cobblestone color scheme: stone broken up by dark mortar lines
+fn cobblestone_color(noise_value: f32) -> [u8; 4] {
+    if (noise_value * 6.0).fract() < 0.12 {
+        [70, 70, 70, 255]
+    } else {
+        stone_color(noise_value)
+    }
+}
+
+/// Mossy cobblestone color scheme: cobblestone with patches of moss
+fn mossy_cobblestone_color(noise_value: f32) -> [u8; 4] {
+    if noise_value > 0.6 {
+        let shade = 0.8 + (noise_value - 0.6) * 0.5;
+        [
+            (70.0 * shade) as u8,
+            (120.0 * shade) as u8,
+            (50.0 * shade) as u8,
+            255,
+        ]
+    } else {
+        cobblestone_color(noise_value)
+    }
+}
+
+/// Chest color scheme: wood with darker bands for the iron fittings
+fn chest_color(noise_value: f32) -> [u8; 4] {
+    if noise_value > 0.45 && noise_value < 0.5 {
+        [60, 60, 60, 255]
+    } else {
+        wood_color(noise_value)
+    }
 }
@@ -0,0 +1,270 @@
+// Water bodies for Bevy Craft
+// This module shapes oceans, rivers and lakes into the terrain during world generation
+//
+// Everything below the sea level is flooded, which turns low terrain into oceans. Rivers follow
+// the zero line of their own noise and cut channels down to the sea. Lakes sit in a coarse grid
+// of cells: a separate noise decides which cells hold one, and each lake gets a flat surface
+// just below the lowest point of its rim so the water never hangs over lower ground.
+
+use bevy::prelude::*;
+use rand::Rng;
+use std::collections::HashMap;
+
+use crate::block::BlockType;
+use crate::caves::chunk_rng;
+use crate::chunk::{ChunkData, ChunkPosition};
+use crate::noise::{derive_seed, generate_heightmap, generate_simple_noise, NoiseSettings};
+use crate::world_gen::WorldGenSettings;
+
+/// Seed channels for river and lake noise
+const RIVER_SEED_CHANNEL: u32 = 40;
+const LAKE_SEED_CHANNEL: u32 = 41;
+
+/// Rivers stop cutting into terrain this far above the sea
+const RIVER_MAX_BANK_HEIGHT: f32 = 36.0;
+
+/// Edge length of the grid cells that can each hold one lake
+const LAKE_CELL_SIZE: i32 = 128;
+
+/// Lakes only form in lowlands, at most this far above the sea
+const LAKE_MAX_ALTITUDE: i32 = 30;
+
+/// A single lake with a flat surface
+#[derive(Debug, Clone, Copy)]
+struct Lake {
+    center: Vec2,
+    radius: f32,
+    /// Height of the water surface
+    level: i32,
+}
+
+/// Terrain height and water of one column after shaping water bodies
+#[derive(Debug, Clone, Copy)]
+pub struct ColumnWater {
+    pub height: f32,
+    /// Water fills every empty block from the terrain up to this height
+    pub water_level: i32,
+    pub is_river: bool,
+}
+
+/// Shapes terrain columns around oceans, rivers and lakes
+pub struct WaterBodies {
+    seed: u32,
+    sea_level: i32,
+    river_width: f32,
+    lake_chance: f32,
+    terrain_settings: NoiseSettings,
+    river_settings: NoiseSettings,
+    lake_settings: NoiseSettings,
+    /// Lakes already worked out for each lake cell
+    lakes: HashMap<IVec2, Option<Lake>>,
+}
+
+impl WaterBodies {
+    pub fn new(settings: &WorldGenSettings, terrain_settings: &NoiseSettings) -> Self {
+        Self {
+            seed: settings.seed,
+            sea_level: settings.sea_level,
+            river_width: settings.river_width,
+            lake_chance: settings.lake_chance,
+            terrain_settings: terrain_settings.clone(),
+            river_settings: NoiseSettings {
+                scale: 0.003,
+                octaves: 4,
+                persistence: 0.5,
+                lacunarity: 2.0,
+                seed: derive_seed(settings.seed, RIVER_SEED_CHANNEL),
+                ..terrain_settings.clone()
+            },
+            lake_settings: NoiseSettings {
+                scale: 0.05,
+                octaves: 2,
+                persistence: 0.5,
+                lacunarity: 2.0,
+                seed: derive_seed(settings.seed, LAKE_SEED_CHANNEL),
+                ..terrain_settings.clone()
+            },
+            lakes: HashMap::new(),
+        }
+    }
+
+    /// Lower a column's terrain for any river or lake crossing it and work out its water level
+    pub fn shape_column(&mut self, world_x: i32, world_z: i32, height: f32) -> ColumnWater {
+        let mut column = ColumnWater {
+            height,
+            water_level: self.sea_level,
+            is_river: false,
+        };
+        let sea_level = self.sea_level as f32;
+
+        // Rivers: a channel along the zero line of the river noise, fading out on high ground
+        if self.river_width > 0.0 {
+            let river_noise =
+                generate_simple_noise(world_x as f32, world_z as f32, &self.river_settings);
+            let closeness = 1.0 - river_noise.abs() / self.river_width;
+            let fade = ((sea_level + RIVER_MAX_BANK_HEIGHT - height) / 16.0).clamp(0.0, 1.0);
+            let strength = closeness * fade;
+            if strength > 0.0 {
+                let river_bed = sea_level - 1.0 - strength * 3.0;
+                let blend = (strength * 1.5).min(1.0);
+                let blend = blend * blend * (3.0 - 2.0 * blend);
+                column.height = column.height.min(height + (river_bed - height) * blend);
+                column.is_river = column.height < sea_level && height >= sea_level;
+            }
+        }
+
+        // Lakes: a bowl dug into the terrain below a flat surface
+        if let Some(lake) = self.lake_near(world_x, world_z) {
+            let position = Vec2::new(world_x as f32, world_z as f32);
+            let shore_noise =
+                generate_simple_noise(world_x as f32, world_z as f32, &self.lake_settings);
+            let distance = position.distance(lake.center) / lake.radius + shore_noise * 0.25;
+            if distance < 1.0 {
+                let depth = 1.0 + (1.0 - distance) * 6.0;
+                column.height = column.height.min(lake.level as f32 - depth);
+                column.water_level = lake.level;
+            }
+        }
+
+        column
+    }
+
+    /// The lake of the cell containing a position, if that cell has one
+    fn lake_near(&mut self, world_x: i32, world_z: i32) -> Option<Lake> {
+        let cell = IVec2::new(
+            world_x.div_euclid(LAKE_CELL_SIZE),
+            world_z.div_euclid(LAKE_CELL_SIZE),
+        );
+        if let Some(lake) = self.lakes.get(&cell) {
+            return *lake;
+        }
+        let lake = self.generate_lake(cell);
+        self.lakes.insert(cell, lake);
+        lake
+    }
+
+    /// Decide whether a lake cell holds a lake and where
+    fn generate_lake(&self, cell: IVec2) -> Option<Lake> {
+        if self.lake_chance <= 0.0 {
+            return None;
+        }
+
+        // The RNG is keyed by the lake cell rather than a chunk
+        let mut rng = chunk_rng(
+            self.seed,
+            LAKE_SEED_CHANNEL,
+            ChunkPosition::new(cell.x, cell.y),
+        );
+        let half_cell = LAKE_CELL_SIZE as f32 * 0.5;
+        let center = (cell * LAKE_CELL_SIZE).as_vec2()
+            + Vec2::splat(half_cell)
+            + Vec2::new(rng.gen_range(-24.0..24.0), rng.gen_range(-24.0..24.0));
+        let radius: f32 = rng.gen_range(10.0..26.0);
+
+        // Lake noise sampled at the cell center decides which cells get a lake
+        let presence = generate_simple_noise(center.x, center.y, &self.lake_settings) * 0.5 + 0.5;
+        if presence >= self.lake_chance {
+            return None;
+        }
+
+        // Sit the surface just below the lowest point around the lake so the rim holds it
+        let rim_radius = radius * 1.3;
+        let mut level = generate_heightmap(center.x, center.y, &self.terrain_settings) as i32;
+        for step in 0..8 {
+            let angle = step as f32 * std::f32::consts::FRAC_PI_4;
+            let rim = center + Vec2::new(angle.cos(), angle.sin()) * rim_radius;
+            level = level.min(generate_heightmap(rim.x, rim.y, &self.terrain_settings) as i32);
+        }
+        level -= 1;
+
+        // Oceans already cover the lowlands and lakes don't belong on mountain tops
+        if level <= self.sea_level || level > self.sea_level + LAKE_MAX_ALTITUDE {
+            return None;
+        }
+
+        Some(Lake {
+            center,
+            radius,
+            level,
+        })
+    }
+}
+
+/// Flood the empty blocks of a column from its terrain up to the water level
+/// Grass doesn't grow underwater, so a flooded grass surface turns to dirt.
+pub fn fill_water(data: &mut ChunkData, local_x: usize, local_z: usize, water_level: i32) {
+    let top = water_level.min(data.max_y() - 1);
+    for y in ((data.min_y() + 1)..=top).rev() {
+        match data.get_block(local_x, y, local_z) {
+            None | Some(BlockType::Air) => data.set_block(local_x, y, local_z, BlockType::Water),
+            Some(BlockType::Grass) => {
+                if y < top {
+                    data.set_block(local_x, y, local_z, BlockType::Dirt);
+                }
+                break;
+            }
+            Some(_) => break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::WorldHeight;
+
+    #[test]
+    fn test_fill_water_floods_down_to_the_terrain() {
+        let mut data = ChunkData::new(WorldHeight::default());
+        for y in data.min_y()..=-3 {
+            data.set_block(2, y, 5, BlockType::Stone);
+        }
+        data.set_block(2, -2, 5, BlockType::Grass);
+
+        fill_water(&mut data, 2, 5, 4);
+
+        for y in -1..=4 {
+            assert_eq!(data.get_block(2, y, 5), Some(BlockType::Water));
+        }
+        assert_eq!(data.get_block(2, 5, 5), None);
+        // Flooded grass turns to dirt and nothing below it changes
+        assert_eq!(data.get_block(2, -2, 5), Some(BlockType::Dirt));
+        assert_eq!(data.get_block(2, -3, 5), Some(BlockType::Stone));
+    }
+
+    #[test]
+    fn test_lakes_sit_above_the_sea() {
+        let settings = WorldGenSettings {
+            seed: 5,
+            lake_chance: 1.0,
+            ..default()
+        };
+        let terrain_settings = NoiseSettings {
+            seed: settings.seed,
+            ..NoiseSettings::default()
+        };
+        let mut water_bodies = WaterBodies::new(&settings, &terrain_settings);
+        let mut lakes = 0;
+        for cell_x in -8..8 {
+            for cell_z in -8..8 {
+                if let Some(lake) =
+                    water_bodies.lake_near(cell_x * LAKE_CELL_SIZE, cell_z * LAKE_CELL_SIZE)
+                {
+                    assert!(lake.level > settings.sea_level);
+                    assert!(lake.level <= settings.sea_level + LAKE_MAX_ALTITUDE);
+
+                    // The lake center is always underwater
+                    let column = water_bodies.shape_column(
+                        lake.center.x as i32,
+                        lake.center.y as i32,
+                        lake.level as f32 + 10.0,
+                    );
+                    assert_eq!(column.water_level, lake.level);
+                    assert!(column.height < lake.level as f32);
+                    lakes += 1;
+                }
+            }
+        }
+        assert!(lakes > 0, "expected lakes in the lowlands");
+    }
+}
@@ -8,6 +8,9 @@ use bevy::render::render_resource::{AsBindGroup, ShaderRef};
 use encase::ShaderType;
 use std::f32::consts::PI;
 
+use crate::biome::{BiomeRegistry, Precipitation, WeatherProfile};
+use crate::chunk::{Chunk, ChunkManager, ChunkPosition};
+
 /// Main weather system resource containing all weather-related state
 #[derive(Resource, Debug, Clone)]
 pub struct WeatherSystem {
@@ -40,6 +43,8 @@ pub struct WeatherSystem {
     /// Maximum time between weather changes (seconds)
     #[allow(dead_code)]
     pub max_weather_duration: f32,
+    /// Weather profile of the biome the player is in
+    pub biome_weather: WeatherProfile,
 }
 
 impl Default for WeatherSystem {
@@ -59,6 +64,7 @@ impl Default for WeatherSystem {
             time_since_weather_change: 0.0,
             min_weather_duration: 300.0,  // 5 minutes
             max_weather_duration: 1800.0, // 30 minutes
+            biome_weather: WeatherProfile::default(),
         }
     }
 }
@@ -132,6 +138,31 @@ impl WeatherType {
         )
     }
 
+    /// The form this weather takes in a biome with the given precipitation
+    /// Rain turns to snow in cold biomes and to sandstorms in dry ones.
+    pub fn for_precipitation(self, precipitation: Precipitation) -> WeatherType {
+        match (precipitation, self) {
+            (Precipitation::Rain, WeatherType::Snow) => WeatherType::Rain,
+            (Precipitation::Rain, WeatherType::HeavySnow) => WeatherType::HeavyRain,
+            (Precipitation::Rain, WeatherType::Sandstorm) => WeatherType::Clear,
+            (Precipitation::Snow, WeatherType::Rain) => WeatherType::Snow,
+            (
+                Precipitation::Snow,
+                WeatherType::HeavyRain | WeatherType::Thunderstorm | WeatherType::Sandstorm,
+            ) => WeatherType::HeavySnow,
+            (
+                Precipitation::Sand,
+                WeatherType::Rain
+                | WeatherType::HeavyRain
+                | WeatherType::Thunderstorm
+                | WeatherType::Snow
+                | WeatherType::HeavySnow,
+            ) => WeatherType::Sandstorm,
+            (Precipitation::Sand, WeatherType::Fog) => WeatherType::Clear,
+            _ => self,
+        }
+    }
+
     /// Get typical cloud coverage for this weather type
     pub fn typical_cloud_coverage(&self) -> f32 {
         match self {
@@ -501,6 +532,40 @@ pub fn update_weather_system(
     update_environmental_conditions(&game_time, &mut weather_system);
 }
 
+/// System to pick up the weather profile of the biome the player is standing in
+pub fn update_biome_weather(
+    player_query: Query<&Transform, With<crate::player::Player>>,
+    chunk_manager: Res<ChunkManager>,
+    chunks: Query<&Chunk>,
+    biomes: Res<BiomeRegistry>,
+    mut weather_system: ResMut<WeatherSystem>,
+) {
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+    let block_position = player_transform.translation.floor().as_ivec3();
+    let chunk_pos = ChunkPosition::from_block_position(block_position);
+    let Some(chunk) = chunk_manager
+        .loaded_chunks
+        .get(&chunk_pos)
+        .and_then(|&entity| chunks.get(entity).ok())
+    else {
+        return;
+    };
+
+    let local = block_position - chunk_pos.min_block_position();
+    if let Some(biome_data) = chunk
+        .biome_data
+        .get_biome_data(local.x as usize, local.z as usize)
+    {
+        let profile = biomes.get(biome_data.biome).weather;
+        // Only write on change so other systems don't see the resource change every frame
+        if weather_system.biome_weather != profile {
+            weather_system.biome_weather = profile;
+        }
+    }
+}
+
 /// Change weather to a random appropriate type
 fn change_weather_randomly(weather_system: &mut WeatherSystem) {
     let current_weather = weather_system.current_weather;
@@ -573,9 +638,10 @@ fn change_weather_randomly(weather_system: &mut WeatherSystem) {
         ],
     };
 
-    // Choose a random weather type from possible transitions
-    let new_weather =
-        possible_weather_types[rand::random::<usize>() % possible_weather_types.len()];
+    // Choose a random weather type from possible transitions, in the form the biome allows
+    let new_weather = possible_weather_types
+        [rand::random::<usize>() % possible_weather_types.len()]
+    .for_precipitation(weather_system.biome_weather.precipitation);
 
     // Start transition to new weather
     weather_system.current_weather = current_weather;
@@ -626,13 +692,13 @@ fn update_environmental_conditions(
     let time_of_day = game_time.time_of_day_normalized();
 
     // Temperature varies with time of day (colder at night, warmer during day)
-    let base_temperature = 20.0; // Base temperature in Celsius
+    let base_temperature = weather_system.biome_weather.temperature; // Base temperature in Celsius
     let temperature_variation = 10.0 * (time_of_day * 2.0 * PI).sin(); // Sinusoidal variation
 
     weather_system.temperature = base_temperature + temperature_variation;
 
     // Humidity varies with time of day and weather
-    let base_humidity = 0.5;
+    let base_humidity = weather_system.biome_weather.humidity;
     let humidity_variation = 0.2 * (1.0 - (time_of_day - 0.5).abs() * 2.0); // Higher humidity around dawn/dusk
 
     weather_system.humidity = (base_humidity + humidity_variation).clamp(0.2, 0.9);
@@ -2,13 +2,36 @@
 // This module handles procedural world generation using noise algorithms
 
 use bevy::prelude::*;
+use bevy::tasks::{block_on, futures_lite::future, AsyncComputeTaskPool, Task};
+use std::collections::HashMap;
 
+use crate::biome::{Biome, BiomeFeature, BiomeRegistry};
+use crate::biome_blend::BiomeBlender;
 use crate::block::BlockType;
-use crate::chunk::{Chunk, CHUNK_HEIGHT, CHUNK_SIZE};
-use crate::noise::{generate_biome_info, generate_heightmap, NoiseSettings};
+use crate::block_update::schedule_flowing_fluids;
+use crate::caves::{carve_caves, chunk_rng};
+use crate::chunk::{Chunk, ChunkManager, ChunkPosition, ChunkPriority, WorldHeight, CHUNK_SIZE};
+use crate::features::{place_trees, PendingFeatureBlocks};
+use crate::noise::{
+    generate_biome_info, generate_density_noise, generate_heightmap, NoiseSettings,
+};
+use crate::structures::place_structures;
+use crate::water_bodies::{fill_water, WaterBodies};
+use crate::world_save::WorldSave;
+use rand::Rng;
+
+/// How the terrain shape is generated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TerrainMode {
+    /// Every column is solid up to a single 2D heightmap value
+    #[allow(dead_code)]
+    Heightmap,
+    /// 3D density noise around the heightmap surface carves cliffs, overhangs and arches
+    Density,
+}
 
 /// World generation settings
-#[derive(Resource, Debug)]
+#[derive(Resource, Debug, Clone)]
 pub struct WorldGenSettings {
     pub base_height: f32,
     pub height_scale: f32,
@@ -17,6 +40,33 @@ pub struct WorldGenSettings {
     pub persistence: f32,
     pub lacunarity: f32,
     pub biome_scale: f32,
+    /// World seed; every noise channel used for generation is derived from it
+    pub seed: u32,
+    /// Build limits of the world, replaced by the limits stored with the world at startup
+    pub world_height: WorldHeight,
+    pub terrain_mode: TerrainMode,
+    /// Frequency of the 3D density noise used in density mode
+    pub density_frequency: f32,
+    /// How far, in blocks, density noise can move the surface away from the heightmap
+    pub density_strength: f32,
+    /// Average number of worm caves starting in each chunk; 0 disables them
+    pub cave_frequency: f32,
+    /// Chance that a chunk starts a ravine
+    pub ravine_chance: f32,
+    /// How much rock cavern chambers hollow out, from 0 (none) to about 0.3 (swiss cheese)
+    pub cavern_density: f32,
+    /// Multiplier on how often trees grow in each biome; 0 disables them
+    pub tree_density: f32,
+    /// Height of the sea surface; terrain below it is flooded
+    pub sea_level: i32,
+    /// Width of river channels in river-noise units; 0 disables rivers
+    pub river_width: f32,
+    /// Roughly the share of lake cells that hold a lake; 0 disables lakes
+    pub lake_chance: f32,
+    /// Radius in blocks over which neighbouring biomes blend; 0 gives hard borders
+    pub biome_blend_radius: i32,
+    /// Multiplier on how often ruins, dungeons and other structures generate; 0 disables them
+    pub structure_density: f32,
 }
 
 impl Default for WorldGenSettings {
@@ -29,12 +79,26 @@ impl Default for WorldGenSettings {
             persistence: 0.3,   // Low persistence for extreme variation
             lacunarity: 2.3,    // Higher lacunarity for more detail
             biome_scale: 0.005, // Scale for biome generation
+            seed: 0,            // Replaced by the seed stored with the world at startup
+            world_height: WorldHeight::default(),
+            terrain_mode: TerrainMode::Density,
+            density_frequency: 0.08, // Cliffs and arches roughly a dozen blocks across
+            density_strength: 32.0,  // Scaled per biome, full strength in mountains
+            cave_frequency: 0.8,     // Most chunks have a tunnel passing through
+            ravine_chance: 0.02,     // Roughly one ravine per 50 chunks
+            cavern_density: 0.08,    // Occasional large chambers deep underground
+            tree_density: 1.0,       // Biome defaults: dense forests, sparse hills, bare deserts
+            sea_level: 4,            // Floods the lowest terrain, about a third of the world
+            river_width: 0.035,      // Rivers a few blocks wide winding across the lowlands
+            lake_chance: 0.35,       // A lake in a few of the lowland lake cells
+            biome_blend_radius: 8,   // Borders fade over about half a chunk
+            structure_density: 1.0,  // A dungeon under most regions, ruins where biomes allow
         }
     }
 }
 
 /// Convert WorldGenSettings to NoiseSettings for noise generation
-fn create_noise_settings(settings: &WorldGenSettings) -> NoiseSettings {
+pub fn create_noise_settings(settings: &WorldGenSettings) -> NoiseSettings {
     NoiseSettings {
         base_height: settings.base_height,
         height_scale: settings.height_scale,
@@ -43,17 +107,23 @@ fn create_noise_settings(settings: &WorldGenSettings) -> NoiseSettings {
         persistence: settings.persistence,
         lacunarity: settings.lacunarity,
         biome_scale: settings.biome_scale,
+        seed: settings.seed,
     }
 }
 
 /// Generate heightmap for a chunk using noise algorithms
-pub fn generate_chunk_heightmap(chunk: &mut Chunk, settings: &WorldGenSettings) {
+pub fn generate_chunk_heightmap(
+    chunk: &mut Chunk,
+    settings: &WorldGenSettings,
+    biomes: &BiomeRegistry,
+    pending_features: &mut PendingFeatureBlocks,
+) {
     let chunk_x = chunk.position.x;
     let chunk_z = chunk.position.z;
 
     println!(
-        "🌱 Generating terrain for chunk ({}, {}) with noise algorithms",
-        chunk_x, chunk_z
+        "🌱 Generating terrain for chunk ({}, {}) with noise algorithms (seed {})",
+        chunk_x, chunk_z, settings.seed
     );
 
     let mut min_height = i32::MAX;
@@ -63,9 +133,21 @@ pub fn generate_chunk_heightmap(chunk: &mut Chunk, settings: &WorldGenSettings)
 
     // Create noise settings from world generation settings
     let noise_settings = create_noise_settings(settings);
+    let density_settings = NoiseSettings {
+        scale: settings.density_frequency,
+        octaves: 3,
+        persistence: 0.5,
+        lacunarity: 2.0,
+        ..noise_settings.clone()
+    };
 
     // Generate heights using simple, deterministic approach with world coordinates
     let mut raw_heights = [[0.0; CHUNK_SIZE as usize]; CHUNK_SIZE as usize];
+    let mut water_levels = [[settings.sea_level; CHUNK_SIZE]; CHUNK_SIZE];
+    let mut river_columns = [[false; CHUNK_SIZE]; CHUNK_SIZE];
+    let mut water_bodies = WaterBodies::new(settings, &noise_settings);
+    let biome_blender = BiomeBlender::new(settings, biomes, &noise_settings, chunk.position);
+    let river = biomes.by_name("river");
 
     for local_x in 0..CHUNK_SIZE {
         for local_z in 0..CHUNK_SIZE {
@@ -74,8 +156,13 @@ pub fn generate_chunk_heightmap(chunk: &mut Chunk, settings: &WorldGenSettings)
             let world_z = chunk_z * CHUNK_SIZE as i32 + local_z as i32;
 
             // Generate noise using world coordinates - should be deterministic
-            raw_heights[local_x as usize][local_z as usize] =
-                generate_heightmap(world_x as f32, world_z as f32, &noise_settings);
+            let height = generate_heightmap(world_x as f32, world_z as f32, &noise_settings);
+
+            // Cut rivers and lakes into the terrain
+            let column = water_bodies.shape_column(world_x, world_z, height);
+            raw_heights[local_x][local_z] = column.height;
+            water_levels[local_x][local_z] = column.water_level;
+            river_columns[local_x][local_z] = column.is_river;
         }
     }
 
@@ -129,22 +216,51 @@ pub fn generate_chunk_heightmap(chunk: &mut Chunk, settings: &WorldGenSettings)
             let world_z = chunk_z * CHUNK_SIZE as i32 + local_z as i32;
             let (temperature, moisture) =
                 generate_biome_info(world_x as f32, world_z as f32, &noise_settings);
+            let blend = biome_blender.blend(world_x, world_z);
 
-            // Determine biome type for this position
-            let biome_type = determine_biome_type(temperature, moisture, height);
+            // Determine biome for this position
+            let biome = match river {
+                Some(river) if river_columns[local_x][local_z] => river,
+                _ => biomes.classify(temperature, moisture, height - settings.sea_level),
+            };
 
-            // Store biome data in chunk
-            chunk
-                .biome_data
-                .set_biome_data(local_x, local_z, temperature, moisture, biome_type);
+            // Store biome data in chunk, with the blended climate so colours fade across borders
+            chunk.biome_data.set_biome_data(
+                local_x,
+                local_z,
+                blend.temperature,
+                blend.moisture,
+                biome.id,
+            );
 
-            generate_terrain_column_with_biome(
-                chunk,
+            // Surface materials come from a dithered pick of the nearby biomes
+            let climate = ColumnClimate {
+                biomes,
+                temperature: blend.surface_temperature,
+                moisture: blend.surface_moisture,
+                sea_level: settings.sea_level,
+            };
+
+            match settings.terrain_mode {
+                TerrainMode::Heightmap => {
+                    generate_terrain_column_with_biome(chunk, local_x, local_z, height, &climate)
+                }
+                TerrainMode::Density => generate_density_terrain_column(
+                    chunk,
+                    IVec3::new(world_x, height, world_z),
+                    &climate,
+                    settings.density_strength
+                        * blend.blend_value(|biome| biomes.get(biome).ruggedness),
+                    &density_settings,
+                ),
+            }
+
+            // Flood everything left empty below the sea, river or lake surface
+            fill_water(
+                &mut chunk.data,
                 local_x,
                 local_z,
-                height,
-                temperature,
-                moisture,
+                water_levels[local_x][local_z],
             );
         }
     }
@@ -161,6 +277,25 @@ pub fn generate_chunk_heightmap(chunk: &mut Chunk, settings: &WorldGenSettings)
         noise_settings.octaves
     );
 
+    // Ores go in before caves so tunnel walls expose them
+    generate_ore_veins(chunk, settings, biomes);
+
+    // Carve caves after every column is filled so tunnels cut through all of them
+    let mut surface_heights = [[0; CHUNK_SIZE]; CHUNK_SIZE];
+    for (surface_row, raw_row) in surface_heights.iter_mut().zip(&raw_heights) {
+        for (surface, raw) in surface_row.iter_mut().zip(raw_row) {
+            *surface = *raw as i32;
+        }
+    }
+    carve_caves(&mut chunk.data, chunk.position, settings, &surface_heights);
+
+    // Structures go in after caves so their walls stay whole, and before trees so none grow inside
+    place_structures(chunk, settings, biomes);
+
+    // Grow trees on the finished surface; leaves reaching into neighbours wait for them
+    place_trees(chunk, settings, biomes, pending_features);
+
+    chunk.data.compact();
     chunk.is_generated = true;
     chunk.needs_mesh_update = true;
     println!(
@@ -169,6 +304,157 @@ pub fn generate_chunk_heightmap(chunk: &mut Chunk, settings: &WorldGenSettings)
     );
 }
 
+/// Seed channel for ore vein placement
+const ORE_SEED_CHANNEL: u32 = 20;
+
+/// Depth range and frequency of one kind of ore vein
+struct OreVein {
+    block_type: BlockType,
+    /// Lowest and highest (exclusive) world y a vein can start at, cut off at the build limits
+    min_y: i32,
+    max_y: i32,
+    /// Height where veins are most common; frequency falls off linearly towards the range ends
+    peak_y: i32,
+    /// Average number of veins starting in each chunk
+    veins_per_chunk: f32,
+    /// Number of blocks each vein tries to place
+    vein_size: usize,
+}
+
+impl OreVein {
+    /// Lowest, peak and highest (exclusive) start heights of the vein within a chunk's build
+    /// limits, above its bedrock floor
+    /// Ranges reaching past the limits are cut off at them, so in a shallow world the deepest ores
+    /// crowd into its lowest layers instead of disappearing.
+    fn start_range(&self, min_y: i32, max_y: i32) -> (i32, i32, i32) {
+        let clamp = |y: i32| y.clamp(min_y + 1, max_y - 1);
+        let min = clamp(self.min_y);
+        (min, clamp(self.peak_y), clamp(self.max_y).max(min + 1))
+    }
+}
+
+/// Ore veins from most to least common; rarer ores sit deeper underground
+const ORE_VEINS: [OreVein; 4] = [
+    OreVein {
+        block_type: BlockType::CoalOre,
+        min_y: 0,
+        max_y: 128,
+        peak_y: 48,
+        veins_per_chunk: 14.0,
+        vein_size: 12,
+    },
+    OreVein {
+        block_type: BlockType::IronOre,
+        min_y: -48,
+        max_y: 64,
+        peak_y: 16,
+        veins_per_chunk: 9.0,
+        vein_size: 8,
+    },
+    OreVein {
+        block_type: BlockType::GoldOre,
+        min_y: -64,
+        max_y: 32,
+        peak_y: -16,
+        veins_per_chunk: 3.0,
+        vein_size: 7,
+    },
+    OreVein {
+        block_type: BlockType::DiamondOre,
+        min_y: -64,
+        max_y: -40,
+        peak_y: -58,
+        veins_per_chunk: 1.0,
+        vein_size: 5,
+    },
+];
+
+/// Biome at the centre of a chunk, used to scale the ore veins starting there.
+/// Computed from noise alone so neighbouring chunks agree on it without being generated.
+fn chunk_center_biome<'a>(
+    chunk_pos: ChunkPosition,
+    noise_settings: &NoiseSettings,
+    biomes: &'a BiomeRegistry,
+    sea_level: i32,
+) -> &'a Biome {
+    let center = chunk_pos.min_block_position().as_vec3() + Vec3::splat(CHUNK_SIZE as f32 * 0.5);
+    let height = generate_heightmap(center.x, center.z, noise_settings) as i32;
+    let (temperature, moisture) = generate_biome_info(center.x, center.z, noise_settings);
+    biomes.classify(temperature, moisture, height - sea_level)
+}
+
+/// Pick a vein starting height from a triangular distribution over a vein's start range
+fn triangular_height(rng: &mut impl Rng, (min, peak, max): (i32, i32, i32)) -> f32 {
+    let (min, peak, max) = (min as f32, peak as f32, max as f32);
+    let sample: f32 = rng.gen_range(0.0..1.0);
+    if sample < (peak - min) / (max - min) {
+        min + (sample * (max - min) * (peak - min)).sqrt()
+    } else {
+        max - ((1.0 - sample) * (max - min) * (max - peak)).sqrt()
+    }
+}
+
+/// Place ore veins into the stone of a chunk
+/// Veins start in this chunk or a direct neighbour; each source chunk's veins are replayed from
+/// its own RNG so veins crossing a chunk border line up on both sides.
+fn generate_ore_veins(chunk: &mut Chunk, settings: &WorldGenSettings, biomes: &BiomeRegistry) {
+    let noise_settings = create_noise_settings(settings);
+    let chunk_min = chunk.position.min_block_position();
+
+    for source_x in (chunk.position.x - 1)..=(chunk.position.x + 1) {
+        for source_z in (chunk.position.z - 1)..=(chunk.position.z + 1) {
+            let source = ChunkPosition::new(source_x, source_z);
+            let biome = chunk_center_biome(source, &noise_settings, biomes, settings.sea_level);
+            let source_min = source.min_block_position();
+            let mut rng = chunk_rng(settings.seed, ORE_SEED_CHANNEL, source);
+
+            for ore in &ORE_VEINS {
+                let frequency = ore.veins_per_chunk * biome.ore_factor(ore.block_type);
+                let vein_count = frequency.floor() as usize
+                    + (rng.gen_range(0.0..1.0) < frequency.fract()) as usize;
+
+                for _ in 0..vein_count {
+                    let start_range = ore.start_range(chunk.data.min_y(), chunk.data.max_y());
+                    let y = triangular_height(&mut rng, start_range);
+                    let mut position = IVec3::new(
+                        source_min.x + rng.gen_range(0..CHUNK_SIZE as i32),
+                        y as i32,
+                        source_min.z + rng.gen_range(0..CHUNK_SIZE as i32),
+                    );
+
+                    // Wander one block at a time, replacing only stone
+                    for _ in 0..ore.vein_size {
+                        let local = position - chunk_min;
+                        if (0..CHUNK_SIZE as i32).contains(&local.x)
+                            && (0..CHUNK_SIZE as i32).contains(&local.z)
+                            && chunk
+                                .data
+                                .get_block(local.x as usize, local.y, local.z as usize)
+                                == Some(BlockType::Stone)
+                        {
+                            chunk.data.set_block(
+                                local.x as usize,
+                                local.y,
+                                local.z as usize,
+                                ore.block_type,
+                            );
+                        }
+
+                        match rng.gen_range(0..6) {
+                            0 => position.x += 1,
+                            1 => position.x -= 1,
+                            2 => position.y += 1,
+                            3 => position.y -= 1,
+                            4 => position.z += 1,
+                            _ => position.z -= 1,
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
 /// Generate fractal noise (multiple octaves) for more natural terrain
 #[allow(dead_code)]
 fn generate_fractal_noise(x: f32, z: f32, settings: &WorldGenSettings) -> f32 {
@@ -179,7 +465,7 @@ fn generate_fractal_noise(x: f32, z: f32, settings: &WorldGenSettings) -> f32 {
 
     for _ in 0..settings.octaves {
         // Generate noise for this octave using simple CPU Perlin noise
-        let noise_value = cpu_perlin_noise(x * frequency, z * frequency, 42); // Use fixed seed for now
+        let noise_value = cpu_perlin_noise(x * frequency, z * frequency, settings.seed);
 
         // Apply amplitude and add to total
         total += noise_value * amplitude;
@@ -263,7 +549,24 @@ fn calculate_height(noise_value: f32, settings: &WorldGenSettings) -> i32 {
     let final_height = height * variation_factor;
 
     // Ensure height is within valid range, but allow very low terrain
-    final_height.clamp(2.0, (CHUNK_HEIGHT - 1) as f32) as i32
+    final_height.clamp(2.0, (settings.world_height.max_y - 1) as f32) as i32
+}
+
+/// Climate of a terrain column, used to pick its biome once the surface height is known
+#[derive(Clone, Copy)]
+struct ColumnClimate<'a> {
+    biomes: &'a BiomeRegistry,
+    temperature: f32,
+    moisture: f32,
+    sea_level: i32,
+}
+
+impl<'a> ColumnClimate<'a> {
+    /// Biome of the column if its surface is at `height`
+    fn biome_at(&self, height: i32) -> &'a Biome {
+        self.biomes
+            .classify(self.temperature, self.moisture, height - self.sea_level)
+    }
 }
 
 /// Generate a terrain column (vertical stack of blocks) with biome information
@@ -272,62 +575,104 @@ fn generate_terrain_column_with_biome(
     local_x: usize,
     local_z: usize,
     height: i32,
-    temperature: f32,
-    moisture: f32,
+    climate: &ColumnClimate,
 ) {
-    // Define minimum terrain height to prevent voids
-    const MIN_TERRAIN_HEIGHT: i32 = 3;
-
-    // Use the maximum of calculated height and minimum height to ensure solid foundation
-    let effective_height = height.max(MIN_TERRAIN_HEIGHT);
+    // Keep a few blocks above the bedrock so the deepest ocean floors stay solid
+    let effective_height = height.max(chunk.data.min_y() + 2);
 
-    // Generate bedrock layer at the bottom
+    // Generate bedrock layer at the bottom of the world
+    let min_y = chunk.data.min_y();
     chunk
         .data
-        .set_block(local_x, 0, local_z, BlockType::Bedrock);
+        .set_block(local_x, min_y, local_z, BlockType::Bedrock);
+
+    // Fill the deep underground below y = 1 with stone, leaving room for soil under sea floors
+    let deep_stone_top = 1.min(effective_height - 2);
+    for y in (min_y + 1)..deep_stone_top {
+        chunk.data.set_block(local_x, y, local_z, BlockType::Stone);
+    }
 
     // Determine biome-based terrain composition
     let (stone_height, surface_block, sub_surface_block) =
-        determine_biome_terrain(effective_height, temperature, moisture);
+        determine_biome_terrain(effective_height, climate.biome_at(effective_height));
 
     // Fill with stone or biome-specific sub-surface material
-    for y in 1..stone_height.min(effective_height) {
-        chunk
-            .data
-            .set_block(local_x, y as usize, local_z, sub_surface_block);
+    for y in deep_stone_top..stone_height.min(effective_height) {
+        chunk.data.set_block(local_x, y, local_z, sub_surface_block);
     }
 
     // Fill with dirt or biome-specific material for the remaining part up to the surface
     for y in stone_height.min(effective_height)..effective_height {
-        chunk
-            .data
-            .set_block(local_x, y as usize, local_z, sub_surface_block);
+        chunk.data.set_block(local_x, y, local_z, sub_surface_block);
     }
 
     // Add biome-specific surface block
-    if effective_height > 0 {
+    if effective_height > min_y {
         chunk
             .data
-            .set_block(local_x, effective_height as usize, local_z, surface_block);
+            .set_block(local_x, effective_height, local_z, surface_block);
     }
 
     // Add environmental features based on height, position, and biome
-    add_environmental_features_with_biome(
-        chunk,
-        local_x,
-        local_z,
-        effective_height,
-        temperature,
-        moisture,
-    );
+    add_environmental_features_with_biome(chunk, local_x, local_z, effective_height, climate);
+}
+
+/// Generate a terrain column shaped by 3D density noise around the heightmap surface
+/// `surface` holds the world x and z of the column and its heightmap height in y.
+fn generate_density_terrain_column(
+    chunk: &mut Chunk,
+    surface: IVec3,
+    climate: &ColumnClimate,
+    strength: f32,
+    density_settings: &NoiseSettings,
+) {
+    let local_x = surface.x.rem_euclid(CHUNK_SIZE as i32) as usize;
+    let local_z = surface.z.rem_euclid(CHUNK_SIZE as i32) as usize;
+    let height = surface.y;
+
+    // Density only needs sampling close to the heightmap surface: further down the terrain is
+    // always solid and further up it is always air
+    let band = strength.ceil() as i32;
+    let band_bottom = (height - band).max(chunk.data.min_y() + 1);
+    let band_top = (height + band).min(chunk.data.max_y() - 1);
+
+    let mut solid = Vec::with_capacity((band_top - band_bottom + 1).max(0) as usize);
+    let mut top_solid = band_bottom - 1;
+    for y in band_bottom..=band_top {
+        // Sampled in world coordinates so neighbouring chunks line up exactly
+        let noise = generate_density_noise(
+            surface.x as f32,
+            y as f32,
+            surface.z as f32,
+            density_settings,
+        );
+        let is_solid = (height - y) as f32 + noise * strength > 0.0;
+        if is_solid {
+            top_solid = y;
+        }
+        solid.push(is_solid);
+    }
+
+    // Lay out the column as a heightmap column reaching the highest solid block
+    generate_terrain_column_with_biome(chunk, local_x, local_z, top_solid, climate);
+
+    // Carve the gaps that form overhangs and arches, and cover newly exposed soil
+    let (_, surface_block, sub_surface_block) =
+        determine_biome_terrain(top_solid, climate.biome_at(top_solid));
+    for y in (band_bottom..top_solid).rev() {
+        let index = (y - band_bottom) as usize;
+        if !solid[index] {
+            chunk.data.clear_block(local_x, y, local_z);
+        } else if !solid[index + 1]
+            && chunk.data.get_block(local_x, y, local_z) == Some(sub_surface_block)
+        {
+            chunk.data.set_block(local_x, y, local_z, surface_block);
+        }
+    }
 }
 
 /// Determine terrain composition based on biome information and height
-fn determine_biome_terrain(
-    effective_height: i32,
-    temperature: f32,
-    moisture: f32,
-) -> (i32, BlockType, BlockType) {
+fn determine_biome_terrain(effective_height: i32, biome: &Biome) -> (i32, BlockType, BlockType) {
     // Determine stone height based on terrain height (same logic as before)
     let stone_height = if effective_height < 10 {
         (effective_height as f32 * 0.9) as i32
@@ -337,220 +682,9 @@ fn determine_biome_terrain(
         (effective_height as f32 * 0.6) as i32
     };
 
-    // Determine biome based on temperature, moisture, and height
-    let biome_type = determine_biome_type(temperature, moisture, effective_height);
-
-    // Return appropriate surface and sub-surface blocks based on biome and height
-    match biome_type {
-        "desert" => determine_desert_terrain(effective_height, stone_height),
-        "forest" => determine_forest_terrain(effective_height, stone_height),
-        "mountain" => determine_mountain_terrain(effective_height, stone_height),
-        "snowy_mountain" => determine_snowy_mountain_terrain(effective_height, stone_height),
-        "hills" => determine_hills_terrain(effective_height, stone_height),
-        "plains" => determine_plains_terrain(effective_height, stone_height),
-        "swamp" => determine_swamp_terrain(effective_height, stone_height),
-        "tundra" => determine_tundra_terrain(effective_height, stone_height),
-        "beach" => determine_beach_terrain(effective_height, stone_height),
-        _ => (stone_height, BlockType::Grass, BlockType::Dirt), // Default
-    }
-}
-
-/// Determine desert terrain with height variation
-fn determine_desert_terrain(
-    effective_height: i32,
-    stone_height: i32,
-) -> (i32, BlockType, BlockType) {
-    if effective_height < 5 {
-        // Low desert areas might have some grass near water sources
-        (stone_height, BlockType::Grass, BlockType::Dirt)
-    } else if effective_height < 15 {
-        // Typical desert - all sand
-        (stone_height, BlockType::Sand, BlockType::Sand)
-    } else {
-        // High desert areas might have more stone exposure
-        (stone_height, BlockType::Sand, BlockType::Stone)
-    }
-}
-
-/// Determine forest terrain with height variation
-fn determine_forest_terrain(
-    effective_height: i32,
-    stone_height: i32,
-) -> (i32, BlockType, BlockType) {
-    if effective_height < 10 {
-        // Low forest areas - more dirt exposed
-        (stone_height, BlockType::Grass, BlockType::Dirt)
-    } else if effective_height < 25 {
-        // Typical forest - grass with dirt
-        (stone_height, BlockType::Grass, BlockType::Dirt)
-    } else {
-        // High forest/mountain areas - more stone exposure
-        (stone_height, BlockType::Grass, BlockType::Stone)
-    }
-}
-
-/// Determine mountain terrain with height variation
-fn determine_mountain_terrain(
-    effective_height: i32,
-    stone_height: i32,
-) -> (i32, BlockType, BlockType) {
-    if effective_height < 20 {
-        // Lower mountain areas - some grass
-        (stone_height, BlockType::Grass, BlockType::Stone)
-    } else if effective_height < 40 {
-        // Mid mountain areas - mostly stone
-        (stone_height, BlockType::Stone, BlockType::Stone)
-    } else {
-        // High mountain areas - all stone
-        (stone_height, BlockType::Stone, BlockType::Stone)
-    }
-}
-
-/// Determine snowy mountain terrain with height variation
-fn determine_snowy_mountain_terrain(
-    effective_height: i32,
-    stone_height: i32,
-) -> (i32, BlockType, BlockType) {
-    if effective_height < 25 {
-        // Lower snowy areas - some grass
-        (stone_height, BlockType::Grass, BlockType::Stone)
-    } else if effective_height < 45 {
-        // Mid snowy areas - stone with snow
-        (stone_height, BlockType::Stone, BlockType::Stone)
-    } else {
-        // High snowy areas - all stone (snow would be a separate layer)
-        (stone_height, BlockType::Stone, BlockType::Stone)
-    }
-}
-
-/// Determine plains terrain with height variation
-fn determine_plains_terrain(
-    effective_height: i32,
-    stone_height: i32,
-) -> (i32, BlockType, BlockType) {
-    if effective_height < 8 {
-        // Low plains - might have some sand near water
-        (stone_height, BlockType::Grass, BlockType::Dirt)
-    } else if effective_height < 18 {
-        // Typical plains - grass with dirt
-        (stone_height, BlockType::Grass, BlockType::Dirt)
-    } else {
-        // High plains - more stone exposure
-        (stone_height, BlockType::Grass, BlockType::Stone)
-    }
-}
-
-/// Determine swamp terrain with height variation
-fn determine_swamp_terrain(
-    effective_height: i32,
-    stone_height: i32,
-) -> (i32, BlockType, BlockType) {
-    if effective_height < 6 {
-        // Low swamp areas - might be waterlogged
-        (stone_height, BlockType::Grass, BlockType::Dirt)
-    } else if effective_height < 15 {
-        // Typical swamp - grass with more moisture
-        (stone_height, BlockType::Grass, BlockType::Dirt)
-    } else {
-        // High swamp areas - transition to forest
-        (stone_height, BlockType::Grass, BlockType::Dirt)
-    }
-}
-
-/// Determine tundra terrain with height variation
-fn determine_tundra_terrain(
-    effective_height: i32,
-    stone_height: i32,
-) -> (i32, BlockType, BlockType) {
-    if effective_height < 10 {
-        // Low tundra - some grass
-        (stone_height, BlockType::Grass, BlockType::Dirt)
-    } else if effective_height < 25 {
-        // Typical tundra - grass with stone
-        (stone_height, BlockType::Grass, BlockType::Stone)
-    } else {
-        // High tundra - mostly stone
-        (stone_height, BlockType::Stone, BlockType::Stone)
-    }
-}
-
-/// Determine beach terrain with height variation
-fn determine_beach_terrain(
-    effective_height: i32,
-    stone_height: i32,
-) -> (i32, BlockType, BlockType) {
-    if effective_height < 4 {
-        // Very low beach - might be underwater
-        (stone_height, BlockType::Sand, BlockType::Sand)
-    } else if effective_height < 10 {
-        // Typical beach - all sand
-        (stone_height, BlockType::Sand, BlockType::Sand)
-    } else {
-        // High beach - transition to grass
-        (stone_height, BlockType::Grass, BlockType::Dirt)
-    }
-}
-
-/// Determine hills terrain with height variation
-fn determine_hills_terrain(
-    effective_height: i32,
-    stone_height: i32,
-) -> (i32, BlockType, BlockType) {
-    if effective_height < 15 {
-        // Low hills - mostly grass with some stone
-        (stone_height, BlockType::Grass, BlockType::Dirt)
-    } else if effective_height < 30 {
-        // Typical hills - grass with more stone exposure
-        (stone_height, BlockType::Grass, BlockType::Stone)
-    } else {
-        // High hills - transition to mountains
-        (stone_height, BlockType::Stone, BlockType::Stone)
-    }
-}
-
-/// Determine biome type based on temperature, moisture, and height
-fn determine_biome_type(temperature: f32, moisture: f32, height: i32) -> &'static str {
-    // Enhanced biome classification based on temperature, moisture, and height
-    if height < 5 {
-        // Low areas near water level - beaches or swamps
-        if moisture > 0.5 {
-            "swamp"
-        } else {
-            "beach"
-        }
-    } else if height > 50 {
-        // Very high areas - snowy mountains (increased threshold)
-        "snowy_mountain"
-    } else if height > 40 {
-        // High areas - mountains (increased threshold)
-        "mountain"
-    } else if height > 25 {
-        // Medium-high areas - hills (new biome type)
-        "hills"
-    } else {
-        // Normal terrain classification based on temperature and moisture
-        if temperature > 0.7 {
-            if moisture < 0.3 {
-                "desert"
-            } else {
-                "plains"
-            }
-        } else if temperature > 0.5 {
-            if moisture > 0.6 {
-                "forest"
-            } else {
-                "plains"
-            }
-        } else if temperature > 0.3 {
-            if moisture > 0.5 {
-                "swamp"
-            } else {
-                "mountain"
-            }
-        } else {
-            "tundra"
-        }
-    }
+    // Surface and sub-surface blocks come from the biome's layers for this height
+    let (surface_block, sub_surface_block) = biome.surface_blocks(effective_height);
+    (stone_height, surface_block, sub_surface_block)
 }
 
 /// Add environmental features with biome information
@@ -559,19 +693,15 @@ fn add_environmental_features_with_biome(
     local_x: usize,
     local_z: usize,
     height: i32,
-    temperature: f32,
-    moisture: f32,
+    climate: &ColumnClimate,
 ) {
-    let biome_type = determine_biome_type(temperature, moisture, height);
+    let biome = climate.biome_at(height);
+    let sea_level = climate.sea_level;
 
-    // Add sand for beach-like areas (low terrain near "water level")
-    if height > 5 && height < 12 {
-        for y in 3..=6 {
-            if y < height {
-                chunk
-                    .data
-                    .set_block(local_x, y as usize, local_z, BlockType::Sand);
-            }
+    // Add sand for beaches along the shore, just above the sea surface
+    if height >= sea_level && height <= sea_level + 3 {
+        for y in (height - 3)..=height {
+            chunk.data.set_block(local_x, y, local_z, BlockType::Sand);
         }
     }
 
@@ -581,60 +711,62 @@ fn add_environmental_features_with_biome(
         if height > 30 {
             for y in (height - 3)..height {
                 if y > 0 && y < height {
-                    chunk
-                        .data
-                        .set_block(local_x, y as usize, local_z, BlockType::Stone);
+                    chunk.data.set_block(local_x, y, local_z, BlockType::Stone);
                 }
             }
         }
     }
 
     // Add biome-specific features
-    match biome_type {
-        "desert" => {
-            // Deserts have more sand at higher elevations
-            if height > 8 && height < 15 {
-                for y in 5..=8 {
-                    if y < height {
-                        chunk
-                            .data
-                            .set_block(local_x, y as usize, local_z, BlockType::Sand);
+    for feature in &biome.features {
+        match feature {
+            BiomeFeature::SandDrifts => {
+                // Sand reaches further down under low dunes
+                if height > 8 && height < 15 {
+                    for y in 5..=8 {
+                        if y < height {
+                            chunk.data.set_block(local_x, y, local_z, BlockType::Sand);
+                        }
                     }
                 }
             }
-        }
-        "hills" => {
-            // Hills have some stone outcrops
-            if height > 20 && height < 28 {
-                for y in (height - 3)..height {
-                    if y > 0 && y < height && y % 3 == 0 {
-                        chunk
-                            .data
-                            .set_block(local_x, y as usize, local_z, BlockType::Stone);
+            BiomeFeature::StoneOutcrops => {
+                // Bands of stone on mid-height slopes
+                if height > 20 && height < 28 {
+                    for y in (height - 3)..height {
+                        if y > 0 && y < height && y % 3 == 0 {
+                            chunk.data.set_block(local_x, y, local_z, BlockType::Stone);
+                        }
                     }
                 }
             }
-        }
-        "forest" => {
-            // Forests might have more dirt variation
-            if height > 10 {
-                for y in (height - 2)..height {
-                    if y > 0 && y < height {
-                        chunk
-                            .data
-                            .set_block(local_x, y as usize, local_z, BlockType::Dirt);
+            BiomeFeature::DeepSoil => {
+                // More dirt under the surface
+                if height > 10 {
+                    for y in (height - 2)..height {
+                        if y > 0 && y < height {
+                            chunk.data.set_block(local_x, y, local_z, BlockType::Dirt);
+                        }
                     }
                 }
             }
+            // Trees, ores and ruins are placed by their own passes
+            BiomeFeature::Trees { .. } | BiomeFeature::Ore { .. } | BiomeFeature::Ruins { .. } => {}
         }
-        _ => {}
     }
 }
 
 /// Original terrain column function (kept for compatibility)
 #[allow(dead_code)]
 fn generate_terrain_column(chunk: &mut Chunk, local_x: usize, local_z: usize, height: i32) {
-    generate_terrain_column_with_biome(chunk, local_x, local_z, height, 0.5, 0.5);
+    let biomes = BiomeRegistry::builtin();
+    let climate = ColumnClimate {
+        biomes: &biomes,
+        temperature: 0.5,
+        moisture: 0.5,
+        sea_level: WorldGenSettings::default().sea_level,
+    };
+    generate_terrain_column_with_biome(chunk, local_x, local_z, height, &climate);
 }
 
 /// Add environmental features like sand, water, etc. based on terrain characteristics
@@ -644,9 +776,7 @@ fn add_environmental_features(chunk: &mut Chunk, local_x: usize, local_z: usize,
     if height > 5 && height < 12 {
         for y in 3..=6 {
             if y < height {
-                chunk
-                    .data
-                    .set_block(local_x, y as usize, local_z, BlockType::Sand);
+                chunk.data.set_block(local_x, y, local_z, BlockType::Sand);
             }
         }
     }
@@ -657,30 +787,534 @@ fn add_environmental_features(chunk: &mut Chunk, local_x: usize, local_z: usize,
         if height > 30 {
             for y in (height - 3)..height {
                 if y > 0 && y < height {
-                    chunk
-                        .data
-                        .set_block(local_x, y as usize, local_z, BlockType::Stone);
+                    chunk.data.set_block(local_x, y, local_z, BlockType::Stone);
                 }
             }
         }
     }
 }
 
+/// Load a chunk from the world save, returning false if it has never been saved
+fn load_chunk_from_save(
+    chunk: &mut Chunk,
+    world_save: &mut WorldSave,
+    world_height: WorldHeight,
+) -> bool {
+    match world_save.load_chunk(chunk.position, world_height) {
+        Ok(Some((data, biome_data))) => {
+            chunk.data = data;
+            chunk.biome_data = biome_data;
+            chunk.is_generated = true;
+            chunk.needs_mesh_update = true;
+            println!(
+                "📂 Loaded chunk ({}, {}) from world save",
+                chunk.position.x, chunk.position.z
+            );
+            true
+        }
+        Ok(None) => false,
+        Err(error) => {
+            println!(
+                "⚠️  Failed to load chunk ({}, {}) from world save, regenerating: {}",
+                chunk.position.x, chunk.position.z, error
+            );
+            false
+        }
+    }
+}
+
+/// Most chunks generating in the background at once
+/// New work is only started as old work finishes, so chunks that become important later still
+/// get ahead of a long queue of far ones.
+const MAX_GENERATION_TASKS: usize = 16;
+
+/// Most chunks loaded from the world save per frame; loading happens on the main thread
+const MAX_SAVED_CHUNKS_PER_FRAME: usize = 4;
+
+/// Terrain generated off the main thread, waiting to be copied into its chunk
+struct GeneratedChunk {
+    chunk: Chunk,
+    /// Feature blocks the chunk spilled into its neighbours
+    pending_features: PendingFeatureBlocks,
+}
+
+/// Terrain generation in progress for one chunk
+struct GenerationTask {
+    /// Chunk entity the terrain is for
+    entity: Entity,
+    task: Task<GeneratedChunk>,
+}
+
+/// Chunks whose terrain is being generated on the async compute task pool
+#[derive(Resource, Default)]
+pub struct ChunkGenerationTasks {
+    tasks: HashMap<ChunkPosition, GenerationTask>,
+}
+
+impl ChunkGenerationTasks {
+    /// Number of chunks still generating
+    #[allow(dead_code)]
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+}
+
 /// System to generate chunks that need generation using noise algorithms
-pub fn generate_chunks_system(mut chunks: Query<&mut Chunk>, settings: Res<WorldGenSettings>) {
-    // Limit the number of chunks generated per frame to prevent performance issues
-    let mut chunks_generated = 0;
-    const MAX_CHUNKS_PER_FRAME: usize = 2;
-
-    for mut chunk in &mut chunks {
-        if !chunk.is_generated {
-            generate_chunk_heightmap(&mut chunk, &settings);
-            chunks_generated += 1;
-
-            // Stop if we've generated enough chunks for this frame
-            if chunks_generated >= MAX_CHUNKS_PER_FRAME {
-                break;
+/// Saved chunks are read back on the main thread; everything else is generated on the async
+/// compute task pool, highest priority first, and copied into its chunk once finished. Work for
+/// chunks that unload before it finishes is dropped, which cancels the task.
+#[allow(clippy::too_many_arguments)]
+pub fn generate_chunks_system(
+    mut chunks: Query<&mut Chunk>,
+    settings: Res<WorldGenSettings>,
+    mut world_save: ResMut<WorldSave>,
+    biomes: Res<BiomeRegistry>,
+    mut pending_features: ResMut<PendingFeatureBlocks>,
+    mut generation_tasks: ResMut<ChunkGenerationTasks>,
+    mut chunk_manager: ResMut<ChunkManager>,
+    player_query: Query<&Transform, With<crate::player::Player>>,
+) {
+    // Cancel work for chunks that were unloaded, or unloaded and loaded again as a new entity
+    generation_tasks.tasks.retain(|chunk_pos, generation| {
+        let still_loaded = chunk_manager.loaded_chunks.get(chunk_pos) == Some(&generation.entity);
+        if !still_loaded {
+            println!(
+                "🚫 Cancelled generation of unloaded chunk ({}, {})",
+                chunk_pos.x, chunk_pos.z
+            );
+        }
+        still_loaded
+    });
+
+    // Copy finished terrain into its chunk
+    let mut finished = Vec::new();
+    for (&chunk_pos, generation) in generation_tasks.tasks.iter_mut() {
+        if let Some(generated) = block_on(future::poll_once(&mut generation.task)) {
+            finished.push((chunk_pos, generation.entity, generated));
+        }
+    }
+    for (chunk_pos, entity, generated) in finished {
+        generation_tasks.tasks.remove(&chunk_pos);
+        if let Ok(mut chunk) = chunks.get_mut(entity) {
+            chunk.data = generated.chunk.data;
+            chunk.biome_data = generated.chunk.biome_data;
+            chunk.is_generated = true;
+            chunk.needs_mesh_update = true;
+        }
+        for (_, blocks) in generated.pending_features.blocks {
+            for (position, block_type) in blocks {
+                pending_features.push(position, block_type);
+            }
+        }
+    }
+
+    // Start on the most important chunks still waiting for terrain
+    let player_chunk_pos = player_query
+        .get_single()
+        .map(|transform| ChunkPosition::from_block_position(transform.translation.as_ivec3()))
+        .unwrap_or(ChunkPosition::new(0, 0));
+    let mut waiting: Vec<(ChunkPriority, i32, Entity)> = Vec::new();
+    for (entity, chunk_pos) in chunk_manager
+        .loaded_chunks
+        .iter()
+        .map(|(pos, e)| (*e, *pos))
+    {
+        if generation_tasks.tasks.contains_key(&chunk_pos) {
+            continue;
+        }
+        if let Ok(mut chunk) = chunks.get_mut(entity)
+            && !chunk.is_generated
+        {
+            let priority = chunk_manager.calculate_chunk_priority(
+                &chunk_pos,
+                &player_chunk_pos,
+                chunk.is_visible,
+            );
+            chunk.priority = priority;
+            let distance = (chunk_pos.x - player_chunk_pos.x)
+                .abs()
+                .max((chunk_pos.z - player_chunk_pos.z).abs());
+            waiting.push((priority, distance, entity));
+        }
+    }
+    waiting.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+
+    let task_pool = AsyncComputeTaskPool::get();
+    let mut saved_chunks_loaded = 0;
+    for (_, _, entity) in waiting {
+        if generation_tasks.tasks.len() >= MAX_GENERATION_TASKS
+            && saved_chunks_loaded >= MAX_SAVED_CHUNKS_PER_FRAME
+        {
+            break;
+        }
+        let Ok(mut chunk) = chunks.get_mut(entity) else {
+            continue;
+        };
+
+        // Saved chunks take precedence over freshly generated terrain. One past this frame's
+        // limit waits for a later frame rather than being generated over the player's changes;
+        // a save that can't be read is generated afresh.
+        if world_save.has_chunk(chunk.position).unwrap_or(true) {
+            if saved_chunks_loaded >= MAX_SAVED_CHUNKS_PER_FRAME {
+                continue;
+            }
+            saved_chunks_loaded += 1;
+            if load_chunk_from_save(&mut chunk, &mut world_save, settings.world_height) {
+                schedule_flowing_fluids(&chunk, &mut chunk_manager.block_updates);
+                continue;
+            }
+        }
+        if generation_tasks.tasks.len() >= MAX_GENERATION_TASKS {
+            continue;
+        }
+
+        let chunk_pos = chunk.position;
+        let settings = settings.clone();
+        let biomes = biomes.clone();
+        let task = task_pool.spawn(async move {
+            let mut chunk = Chunk::new(chunk_pos, settings.world_height);
+            let mut pending_features = PendingFeatureBlocks::default();
+            generate_chunk_heightmap(&mut chunk, &settings, &biomes, &mut pending_features);
+            GeneratedChunk {
+                chunk,
+                pending_features,
+            }
+        });
+        generation_tasks
+            .tasks
+            .insert(chunk_pos, GenerationTask { entity, task });
+    }
+
+    // Hand over feature blocks that were waiting for chunks which are now ready
+    if !pending_features.blocks.is_empty() {
+        for mut chunk in &mut chunks {
+            if chunk.is_generated
+                && pending_features.blocks.contains_key(&chunk.position)
+                && pending_features.apply_to_chunk(&mut chunk)
+            {
+                chunk.data.compact();
+                chunk.needs_mesh_update = true;
+                chunk.needs_light_update = true;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ore_veins_replace_only_stone_within_depth_range() {
+        let settings = WorldGenSettings {
+            seed: 99,
+            ..default()
+        };
+        let mut chunk = Chunk::new(ChunkPosition::new(2, -5), WorldHeight::default());
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                for y in chunk.data.min_y()..64 {
+                    chunk.data.set_block(x, y, z, BlockType::Stone);
+                }
+                chunk.data.set_block(x, 20, z, BlockType::Dirt);
             }
         }
+
+        generate_ore_veins(&mut chunk, &settings, &BiomeRegistry::builtin());
+
+        let mut ore_counts = std::collections::HashMap::new();
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                // Non-stone blocks are never replaced
+                assert_eq!(chunk.data.get_block(x, 20, z), Some(BlockType::Dirt));
+
+                for y in chunk.data.min_y()..64 {
+                    if let Some(block_type) = chunk.data.get_block(x, y, z)
+                        && let Some(vein) =
+                            ORE_VEINS.iter().find(|vein| vein.block_type == block_type)
+                    {
+                        // A vein can wander at most its own size away from where it started
+                        let (min_y, _, max_y) =
+                            vein.start_range(chunk.data.min_y(), chunk.data.max_y());
+                        assert!(y >= min_y - vein.vein_size as i32);
+                        assert!(y < max_y + vein.vein_size as i32);
+                        *ore_counts.entry(block_type).or_insert(0) += 1;
+                    }
+                }
+            }
+        }
+
+        assert!(ore_counts.get(&BlockType::CoalOre).copied().unwrap_or(0) > 0);
+        assert!(ore_counts.get(&BlockType::IronOre).copied().unwrap_or(0) > 0);
+    }
+
+    #[test]
+    fn test_ore_depth_ranges_fit_the_build_limits() {
+        // Diamonds start below y = -40 in the default world; a world starting at y = 0 gets them
+        // just above its bedrock
+        let diamonds = &ORE_VEINS[3];
+        assert_eq!(diamonds.start_range(-64, 256), (-63, -58, -40));
+        assert_eq!(diamonds.start_range(0, 128), (1, 1, 2));
+        assert_eq!(ORE_VEINS[0].start_range(0, 32), (1, 31, 31));
+
+        let settings = WorldGenSettings {
+            seed: 99,
+            ..default()
+        };
+        let mut chunk = Chunk::new(ChunkPosition::new(2, -5), WorldHeight::new(0, 32));
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                for y in 0..32 {
+                    chunk.data.set_block(x, y, z, BlockType::Stone);
+                }
+            }
+        }
+
+        generate_ore_veins(&mut chunk, &settings, &BiomeRegistry::builtin());
+
+        let mut deepest_ores = std::collections::HashMap::new();
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                for y in 0..32 {
+                    if let Some(block_type) = chunk.data.get_block(x, y, z)
+                        && block_type != BlockType::Stone
+                    {
+                        let deepest = deepest_ores.entry(block_type).or_insert(y);
+                        *deepest = y.min(*deepest);
+                    }
+                }
+            }
+        }
+        // Every ore still generates, the rarest ones near the floor of the world
+        for vein in &ORE_VEINS {
+            assert!(deepest_ores.contains_key(&vein.block_type));
+        }
+        assert!(deepest_ores[&BlockType::DiamondOre] <= 1 + ORE_VEINS[3].vein_size as i32);
+    }
+
+    /// Generate a chunk's terrain alone, without caves, water bodies, trees or structures
+    fn generate_density_terrain(seed: u32, position: ChunkPosition) -> Chunk {
+        let settings = WorldGenSettings {
+            seed,
+            terrain_mode: TerrainMode::Density,
+            cave_frequency: 0.0,
+            ravine_chance: 0.0,
+            cavern_density: 0.0,
+            tree_density: 0.0,
+            river_width: 0.0,
+            lake_chance: 0.0,
+            structure_density: 0.0,
+            ..default()
+        };
+        let mut chunk = Chunk::new(position, WorldHeight::default());
+        generate_chunk_heightmap(
+            &mut chunk,
+            &settings,
+            &BiomeRegistry::builtin(),
+            &mut PendingFeatureBlocks::default(),
+        );
+        chunk
+    }
+
+    /// Height of the highest block of each column that isn't air or fluid
+    fn surface_heights(chunk: &Chunk) -> [[i32; CHUNK_SIZE]; CHUNK_SIZE] {
+        let mut heights = [[0; CHUNK_SIZE]; CHUNK_SIZE];
+        for (x, row) in heights.iter_mut().enumerate() {
+            for (z, height) in row.iter_mut().enumerate() {
+                *height = (chunk.data.min_y()..chunk.data.max_y())
+                    .rev()
+                    .find(|&y| {
+                        chunk
+                            .data
+                            .get_block(x, y, z)
+                            .is_some_and(|block_type| block_type.fluid().is_none())
+                    })
+                    .unwrap();
+            }
+        }
+        heights
+    }
+
+    #[test]
+    fn test_density_terrain_is_deterministic_for_a_seed() {
+        let position = ChunkPosition::new(-3, 7);
+        let first = generate_density_terrain(1234, position);
+        let second = generate_density_terrain(1234, position);
+        let other_seed = generate_density_terrain(4321, position);
+
+        let mut differs_from_other_seed = false;
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                for y in first.data.min_y()..first.data.max_y() {
+                    let block = first.data.get_block(x, y, z);
+                    assert_eq!(block, second.data.get_block(x, y, z));
+                    differs_from_other_seed |= block != other_seed.data.get_block(x, y, z);
+                }
+            }
+        }
+        assert!(differs_from_other_seed);
+    }
+
+    #[test]
+    fn test_density_terrain_is_continuous_across_chunk_seams() {
+        // Hills where density noise leaves overhangs and uneven steps
+        let seed = 77;
+        let heights =
+            |x, z| surface_heights(&generate_density_terrain(seed, ChunkPosition::new(x, z)));
+        let (center, east, south) = (heights(4, -5), heights(5, -5), heights(4, -4));
+
+        // Steps between neighbouring columns inside the chunk
+        let mut inner_steps = Vec::new();
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                if x > 0 {
+                    inner_steps.push((center[x][z] - center[x - 1][z]).abs());
+                }
+                if z > 0 {
+                    inner_steps.push((center[x][z] - center[x][z - 1]).abs());
+                }
+            }
+        }
+        // Steps between columns facing each other across the east and south borders
+        let last = CHUNK_SIZE - 1;
+        let seam_steps: Vec<i32> = (0..CHUNK_SIZE)
+            .flat_map(|i| {
+                [
+                    (center[last][i] - east[0][i]).abs(),
+                    (center[i][last] - south[i][0]).abs(),
+                ]
+            })
+            .collect();
+
+        let mean = |steps: &[i32]| steps.iter().sum::<i32>() as f32 / steps.len() as f32;
+        let inner_max = *inner_steps.iter().max().unwrap();
+        assert!(inner_max > 1, "terrain too flat to show a seam");
+        assert!(seam_steps.iter().all(|&step| step <= inner_max));
+        assert!(mean(&seam_steps) <= mean(&inner_steps) * 2.0);
+    }
+
+    #[test]
+    fn test_chunks_generate_in_the_background_until_unloaded() {
+        use bevy::ecs::system::RunSystemOnce;
+        use bevy::tasks::TaskPool;
+
+        AsyncComputeTaskPool::get_or_init(TaskPool::new);
+        let directory = std::env::temp_dir().join(format!(
+            "bevy_craft_async_generation_{}",
+            std::process::id()
+        ));
+        let mut world = World::new();
+        world.insert_resource(WorldGenSettings {
+            seed: 5,
+            ..default()
+        });
+        world.insert_resource(BiomeRegistry::builtin());
+        world.insert_resource(WorldSave::new(&directory));
+        world.insert_resource(PendingFeatureBlocks::default());
+        world.insert_resource(ChunkGenerationTasks::default());
+        let mut chunk_manager = ChunkManager::new(4, WorldHeight::default());
+        let kept_pos = ChunkPosition::new(0, 0);
+        let unloaded_pos = ChunkPosition::new(3, 0);
+        let kept = world
+            .spawn(Chunk::new(kept_pos, WorldHeight::default()))
+            .id();
+        let unloaded = world
+            .spawn(Chunk::new(unloaded_pos, WorldHeight::default()))
+            .id();
+        chunk_manager.insert_chunk(kept_pos, kept);
+        chunk_manager.insert_chunk(unloaded_pos, unloaded);
+        world.insert_resource(chunk_manager);
+
+        // Both chunks start generating without blocking the system
+        world.run_system_once(generate_chunks_system).unwrap();
+        assert_eq!(world.resource::<ChunkGenerationTasks>().len(), 2);
+
+        // Unloading a chunk drops its task
+        world
+            .resource_mut::<ChunkManager>()
+            .remove_chunk(&unloaded_pos);
+        world.despawn(unloaded);
+        world.run_system_once(generate_chunks_system).unwrap();
+        assert!(world.resource::<ChunkGenerationTasks>().len() <= 1);
+
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(60);
+        while !world.get::<Chunk>(kept).unwrap().is_generated {
+            assert!(std::time::Instant::now() < deadline, "generation timed out");
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            world.run_system_once(generate_chunks_system).unwrap();
+        }
+        assert_eq!(world.resource::<ChunkGenerationTasks>().len(), 0);
+        let chunk = world.get::<Chunk>(kept).unwrap();
+        assert!(chunk.needs_mesh_update);
+        assert_eq!(
+            chunk.data.get_block(0, chunk.data.min_y(), 0),
+            Some(BlockType::Bedrock)
+        );
+    }
+
+    #[test]
+    fn test_saved_chunks_over_the_frame_limit_wait_instead_of_generating() {
+        use crate::chunk::{ChunkBiomeData, ChunkData};
+        use bevy::ecs::system::RunSystemOnce;
+        use bevy::tasks::TaskPool;
+
+        AsyncComputeTaskPool::get_or_init(TaskPool::new);
+        let directory = std::env::temp_dir().join(format!(
+            "bevy_craft_saved_chunk_limit_{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&directory);
+        let mut world_save = WorldSave::new(&directory);
+        let saved_positions: Vec<ChunkPosition> = (0..MAX_SAVED_CHUNKS_PER_FRAME as i32 * 2 + 1)
+            .map(|x| ChunkPosition::new(x, 0))
+            .collect();
+        for chunk_pos in &saved_positions {
+            let mut data = ChunkData::new(WorldHeight::default());
+            data.set_block(1, 200, 1, BlockType::Cobblestone);
+            world_save
+                .save_chunk(*chunk_pos, &data, &ChunkBiomeData::new())
+                .unwrap();
+        }
+
+        let mut world = World::new();
+        world.insert_resource(WorldGenSettings {
+            seed: 5,
+            ..default()
+        });
+        world.insert_resource(BiomeRegistry::builtin());
+        world.insert_resource(world_save);
+        world.insert_resource(PendingFeatureBlocks::default());
+        world.insert_resource(ChunkGenerationTasks::default());
+        let mut chunk_manager = ChunkManager::new(16, WorldHeight::default());
+        let unsaved_pos = ChunkPosition::new(0, 1);
+        let mut entities = Vec::new();
+        for &chunk_pos in saved_positions.iter().chain([&unsaved_pos]) {
+            let entity = world
+                .spawn(Chunk::new(chunk_pos, WorldHeight::default()))
+                .id();
+            chunk_manager.insert_chunk(chunk_pos, entity);
+            entities.push(entity);
+        }
+        world.insert_resource(chunk_manager);
+
+        // Only the chunk without a save is generated; the saved ones load over a few frames
+        for _ in 0..3 {
+            world.run_system_once(generate_chunks_system).unwrap();
+            assert!(world
+                .resource::<ChunkGenerationTasks>()
+                .tasks
+                .keys()
+                .all(|&chunk_pos| chunk_pos == unsaved_pos));
+        }
+        for &entity in &entities[..saved_positions.len()] {
+            let chunk = world.get::<Chunk>(entity).unwrap();
+            assert!(chunk.is_generated);
+            assert_eq!(
+                chunk.data.get_block(1, 200, 1),
+                Some(BlockType::Cobblestone)
+            );
+        }
+
+        let _ = std::fs::remove_dir_all(&directory);
     }
 }
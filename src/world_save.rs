@@ -0,0 +1,757 @@
+// World save system for Bevy Craft
+// This module persists chunk data on disk using compressed region files
+//
+// Region File Layout:
+// Chunks are grouped into regions of REGION_SIZE x REGION_SIZE chunks, one file per region
+// named `r.<x>.<z>.bcr`. Each file starts with a small header followed by the chunk entries:
+//
+//   magic "BCRG" | format version (u32) | chunk count (u32)
+//   per chunk:   local x (u8) | local z (u8) | payload length (u32) | payload
+//
+// Every payload is a zlib-compressed bincode encoding of the chunk's block and biome data,
+// so a single chunk can be decoded without touching the rest of the region.
+//
+// World-wide settings such as the seed live next to the regions in `world.ron`, and feature blocks
+// waiting for chunks that haven't been generated yet in `pending_features.ron`.
+
+use bevy::prelude::*;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::chunk::{
+    Chunk, ChunkBiomeData, ChunkData, ChunkManager, ChunkPosition, WorldHeight, CHUNK_AREA,
+};
+use crate::features::PendingFeatureBlocks;
+
+/// Number of chunks along each horizontal axis of a region file
+pub const REGION_SIZE: i32 = 32;
+
+/// Magic bytes identifying a region file
+const REGION_MAGIC: [u8; 4] = *b"BCRG";
+
+/// Current version of the region file format
+pub const REGION_FORMAT_VERSION: u32 = 6;
+
+/// Directory containing all saved worlds
+pub const SAVES_DIRECTORY: &str = "saves";
+
+/// Name of the world used when none is given on the command line
+pub const DEFAULT_WORLD_NAME: &str = "world";
+
+/// File holding the world metadata inside the world directory
+const WORLD_METADATA_FILE: &str = "world.ron";
+
+/// File holding feature blocks waiting for chunks that haven't been generated yet
+const PENDING_FEATURES_FILE: &str = "pending_features.ron";
+
+/// Current version of the world metadata format
+pub const WORLD_METADATA_VERSION: u32 = 2;
+
+/// Oldest world metadata version that can still be read, upgraded as it's loaded
+const OLDEST_WORLD_METADATA_VERSION: u32 = 1;
+
+/// World-wide settings stored alongside the region files
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct WorldMetadata {
+    pub version: u32,
+    pub seed: u32,
+    /// Build limits; version 1 didn't store them, as every world then was built the same
+    #[serde(default = "WorldMetadata::version_1_height")]
+    pub world_height: WorldHeight,
+}
+
+impl WorldMetadata {
+    pub fn new(seed: u32, world_height: WorldHeight) -> Self {
+        Self {
+            version: WORLD_METADATA_VERSION,
+            seed,
+            world_height,
+        }
+    }
+
+    /// Build limits of every world created before they became configurable
+    fn version_1_height() -> WorldHeight {
+        WorldHeight::new(0, 128)
+    }
+}
+
+/// Chunk contents as they are stored on disk
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct SavedChunk {
+    data: ChunkData,
+    biome_data: ChunkBiomeData,
+}
+
+/// Position of a region in region coordinates
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RegionPosition {
+    pub x: i32,
+    pub z: i32,
+}
+
+impl RegionPosition {
+    /// Get the region that contains a chunk
+    pub fn from_chunk_position(chunk_pos: &ChunkPosition) -> Self {
+        Self {
+            x: chunk_pos.x.div_euclid(REGION_SIZE),
+            z: chunk_pos.z.div_euclid(REGION_SIZE),
+        }
+    }
+
+    /// File name of this region inside the save directory
+    pub fn file_name(&self) -> String {
+        format!("r.{}.{}.bcr", self.x, self.z)
+    }
+}
+
+/// Convert a chunk position to its local coordinates inside its region
+fn chunk_to_region_local(chunk_pos: &ChunkPosition) -> (u8, u8) {
+    (
+        chunk_pos.x.rem_euclid(REGION_SIZE) as u8,
+        chunk_pos.z.rem_euclid(REGION_SIZE) as u8,
+    )
+}
+
+/// In-memory copy of a single region file
+#[derive(Debug, Default)]
+struct RegionFile {
+    /// Compressed chunk payloads keyed by region-local chunk coordinates
+    chunks: HashMap<(u8, u8), Vec<u8>>,
+    /// Flag indicating the region has changes that are not on disk yet
+    dirty: bool,
+}
+
+impl RegionFile {
+    /// Read a region file from disk, returning an empty region if it doesn't exist
+    fn read(path: &Path) -> io::Result<Self> {
+        match fs::read(path) {
+            Ok(bytes) => Self::decode(&bytes),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Write the region to disk through a temporary file so a crash can't truncate it
+    fn write(&self, path: &Path) -> io::Result<()> {
+        let temp_path = path.with_extension("bcr.tmp");
+        fs::write(&temp_path, self.encode())?;
+        fs::rename(&temp_path, path)
+    }
+
+    /// Serialize the region header and all chunk entries
+    fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&REGION_MAGIC);
+        bytes.extend_from_slice(&REGION_FORMAT_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&(self.chunks.len() as u32).to_le_bytes());
+
+        // Sort entries so the same region always produces the same file
+        let mut entries: Vec<_> = self.chunks.iter().collect();
+        entries.sort_by_key(|(local_pos, _)| **local_pos);
+
+        for ((local_x, local_z), payload) in entries {
+            bytes.push(*local_x);
+            bytes.push(*local_z);
+            bytes.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(payload);
+        }
+
+        bytes
+    }
+
+    /// Parse a region file, validating its header
+    fn decode(bytes: &[u8]) -> io::Result<Self> {
+        let mut reader = bytes;
+
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if magic != REGION_MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a region file",
+            ));
+        }
+
+        let version = read_u32(&mut reader)?;
+        if version != REGION_FORMAT_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unsupported region format version {}", version),
+            ));
+        }
+
+        let chunk_count = read_u32(&mut reader)?;
+        let mut chunks = HashMap::new();
+        for _ in 0..chunk_count {
+            let mut local_pos = [0u8; 2];
+            reader.read_exact(&mut local_pos)?;
+            let payload_len = read_u32(&mut reader)? as usize;
+            if payload_len > reader.len() {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "truncated chunk payload",
+                ));
+            }
+            let (payload, rest) = reader.split_at(payload_len);
+            chunks.insert((local_pos[0], local_pos[1]), payload.to_vec());
+            reader = rest;
+        }
+
+        Ok(Self {
+            chunks,
+            dirty: false,
+        })
+    }
+}
+
+/// Read a little-endian u32 from a byte reader
+fn read_u32(reader: &mut &[u8]) -> io::Result<u32> {
+    let mut buffer = [0u8; 4];
+    reader.read_exact(&mut buffer)?;
+    Ok(u32::from_le_bytes(buffer))
+}
+
+/// Compress a chunk into a region payload
+fn encode_chunk(data: &ChunkData, biome_data: &ChunkBiomeData) -> io::Result<Vec<u8>> {
+    let saved_chunk = SavedChunk {
+        data: data.clone(),
+        biome_data: biome_data.clone(),
+    };
+    let serialized = bincode::serialize(&saved_chunk)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&serialized)?;
+    encoder.finish()
+}
+
+/// Decompress a region payload back into chunk data
+/// Chunks that don't fit the world's height or are damaged are refused, to be generated again.
+fn decode_chunk(
+    payload: &[u8],
+    world_height: WorldHeight,
+) -> io::Result<(ChunkData, ChunkBiomeData)> {
+    let mut serialized = Vec::new();
+    ZlibDecoder::new(payload).read_to_end(&mut serialized)?;
+
+    let saved_chunk: SavedChunk = bincode::deserialize(&serialized)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+    if !saved_chunk.data.is_valid_for(world_height) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("chunk data is damaged or doesn't fit {:?}", world_height),
+        ));
+    }
+    if saved_chunk.biome_data.data.len() != CHUNK_AREA {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "chunk biome data is damaged",
+        ));
+    }
+    Ok((saved_chunk.data, saved_chunk.biome_data))
+}
+
+/// Resource that owns the on-disk world save
+#[derive(Resource, Debug)]
+pub struct WorldSave {
+    /// Directory containing the region files
+    pub directory: PathBuf,
+    /// Regions that have been read from disk or modified this session
+    regions: HashMap<RegionPosition, RegionFile>,
+}
+
+impl Default for WorldSave {
+    fn default() -> Self {
+        Self::for_world(DEFAULT_WORLD_NAME)
+    }
+}
+
+impl WorldSave {
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+            regions: HashMap::new(),
+        }
+    }
+
+    /// Open the save for a named world inside the saves directory
+    pub fn for_world(world_name: &str) -> Self {
+        Self::new(Path::new(SAVES_DIRECTORY).join(world_name))
+    }
+
+    /// Path of the world metadata file
+    fn metadata_path(&self) -> PathBuf {
+        self.directory.join(WORLD_METADATA_FILE)
+    }
+
+    /// Load the world metadata, returning None for a world that has not been created yet
+    pub fn load_metadata(&self) -> io::Result<Option<WorldMetadata>> {
+        let text = match fs::read_to_string(self.metadata_path()) {
+            Ok(text) => text,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(error),
+        };
+
+        let mut metadata: WorldMetadata = ron::from_str(&text)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        if !(OLDEST_WORLD_METADATA_VERSION..=WORLD_METADATA_VERSION).contains(&metadata.version) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unsupported world metadata version {}", metadata.version),
+            ));
+        }
+        // Older versions only lack fields that have defaults, so upgrading is just a new number
+        metadata.version = WORLD_METADATA_VERSION;
+        Ok(Some(metadata))
+    }
+
+    /// Write the world metadata
+    pub fn save_metadata(&self, metadata: &WorldMetadata) -> io::Result<()> {
+        let text = ron::ser::to_string_pretty(metadata, ron::ser::PrettyConfig::default())
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        fs::create_dir_all(&self.directory)?;
+        fs::write(self.metadata_path(), text)
+    }
+
+    /// Load the feature blocks that were waiting for chunks when the world was last saved
+    pub fn load_pending_features(&self) -> io::Result<PendingFeatureBlocks> {
+        match fs::read_to_string(self.directory.join(PENDING_FEATURES_FILE)) {
+            Ok(text) => ron::from_str(&text)
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error)),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                Ok(PendingFeatureBlocks::default())
+            }
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Write the feature blocks still waiting for chunks
+    pub fn save_pending_features(&self, pending_features: &PendingFeatureBlocks) -> io::Result<()> {
+        let text = ron::to_string(pending_features)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        fs::create_dir_all(&self.directory)?;
+        fs::write(self.directory.join(PENDING_FEATURES_FILE), text)
+    }
+
+    /// Resolve the settings for this world and record them in the save.
+    /// An existing world always keeps the seed and build limits it was created with; a new
+    /// world uses the requested values, picking a random seed when none is given. Metadata that
+    /// exists but can't be read is an error, so a world never silently loses its seed.
+    pub fn resolve_metadata(
+        &self,
+        requested_seed: Option<u32>,
+        requested_height: Option<WorldHeight>,
+    ) -> io::Result<WorldMetadata> {
+        if let Some(metadata) = self.load_metadata()? {
+            if let Some(seed) = requested_seed
+                && seed != metadata.seed
+            {
+                println!(
+                    "⚠️  World {:?} was created with seed {}, ignoring requested seed {}",
+                    self.directory, metadata.seed, seed
+                );
+            }
+            if let Some(world_height) = requested_height
+                && world_height != metadata.world_height
+            {
+                println!(
+                    "⚠️  World {:?} was created with build limits {:?}, ignoring requested {:?}",
+                    self.directory, metadata.world_height, world_height
+                );
+            }
+            return Ok(metadata);
+        }
+
+        let metadata = WorldMetadata::new(
+            requested_seed.unwrap_or_else(rand::random),
+            requested_height.unwrap_or_default(),
+        );
+        if let Err(error) = self.save_metadata(&metadata) {
+            println!("⚠️  Failed to write world metadata: {}", error);
+        }
+        Ok(metadata)
+    }
+
+    /// Path of the region file for a region position
+    fn region_path(&self, region_pos: &RegionPosition) -> PathBuf {
+        self.directory.join(region_pos.file_name())
+    }
+
+    /// Get a region, reading it from disk on first access
+    /// A region file this build can't decode, from an older format or damaged, is moved aside to
+    /// `*.bcr.bak` and replaced by an empty region, so chunks in it can still be saved.
+    fn region_mut(&mut self, region_pos: RegionPosition) -> io::Result<&mut RegionFile> {
+        if !self.regions.contains_key(&region_pos) {
+            let path = self.region_path(&region_pos);
+            let region = match RegionFile::read(&path) {
+                Ok(region) => region,
+                Err(error)
+                    if matches!(
+                        error.kind(),
+                        io::ErrorKind::InvalidData | io::ErrorKind::UnexpectedEof
+                    ) =>
+                {
+                    let backup_path = path.with_extension("bcr.bak");
+                    println!(
+                        "⚠️  Can't read region file {:?} ({}), moving it to {:?} and starting afresh",
+                        path, error, backup_path
+                    );
+                    fs::rename(&path, &backup_path)?;
+                    RegionFile::default()
+                }
+                Err(error) => return Err(error),
+            };
+            self.regions.insert(region_pos, region);
+        }
+        Ok(self.regions.get_mut(&region_pos).unwrap())
+    }
+
+    /// Check whether a chunk has been saved, without decoding it
+    pub fn has_chunk(&mut self, chunk_pos: ChunkPosition) -> io::Result<bool> {
+        let region = self.region_mut(RegionPosition::from_chunk_position(&chunk_pos))?;
+        Ok(region
+            .chunks
+            .contains_key(&chunk_to_region_local(&chunk_pos)))
+    }
+
+    /// Store a chunk in its region; the region is written to disk on the next flush
+    pub fn save_chunk(
+        &mut self,
+        chunk_pos: ChunkPosition,
+        data: &ChunkData,
+        biome_data: &ChunkBiomeData,
+    ) -> io::Result<()> {
+        let payload = encode_chunk(data, biome_data)?;
+        let region = self.region_mut(RegionPosition::from_chunk_position(&chunk_pos))?;
+        region
+            .chunks
+            .insert(chunk_to_region_local(&chunk_pos), payload);
+        region.dirty = true;
+        Ok(())
+    }
+
+    /// Load a chunk from disk if it has been saved before
+    pub fn load_chunk(
+        &mut self,
+        chunk_pos: ChunkPosition,
+        world_height: WorldHeight,
+    ) -> io::Result<Option<(ChunkData, ChunkBiomeData)>> {
+        let region = self.region_mut(RegionPosition::from_chunk_position(&chunk_pos))?;
+        match region.chunks.get(&chunk_to_region_local(&chunk_pos)) {
+            Some(payload) => decode_chunk(payload, world_height).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Write all modified regions to disk
+    pub fn flush(&mut self) -> io::Result<()> {
+        let dirty_regions: Vec<RegionPosition> = self
+            .regions
+            .iter()
+            .filter(|(_, region)| region.dirty)
+            .map(|(region_pos, _)| *region_pos)
+            .collect();
+
+        if dirty_regions.is_empty() {
+            return Ok(());
+        }
+
+        fs::create_dir_all(&self.directory)?;
+        for region_pos in dirty_regions {
+            let path = self.region_path(&region_pos);
+            let region = self.regions.get_mut(&region_pos).unwrap();
+            region.write(&path)?;
+            region.dirty = false;
+        }
+
+        Ok(())
+    }
+}
+
+/// System to save every loaded chunk when the game exits, with the feature blocks still waiting
+/// for chunks
+pub fn save_world_on_exit_system(
+    mut exit_events: EventReader<AppExit>,
+    chunk_manager: Res<ChunkManager>,
+    chunks: Query<&Chunk>,
+    mut world_save: ResMut<WorldSave>,
+    pending_features: Res<PendingFeatureBlocks>,
+) {
+    if exit_events.read().next().is_none() {
+        return;
+    }
+
+    println!("💾 Saving world to {:?}...", world_save.directory);
+
+    let mut chunks_saved = 0;
+    for (chunk_pos, chunk_entity) in &chunk_manager.loaded_chunks {
+        if let Ok(chunk) = chunks.get(*chunk_entity) {
+            if !chunk.is_generated {
+                continue;
+            }
+            match world_save.save_chunk(*chunk_pos, &chunk.data, &chunk.biome_data) {
+                Ok(()) => chunks_saved += 1,
+                Err(error) => println!(
+                    "⚠️  Failed to save chunk ({}, {}): {}",
+                    chunk_pos.x, chunk_pos.z, error
+                ),
+            }
+        }
+    }
+
+    match world_save.flush() {
+        Ok(()) => println!("✓ Saved {} chunks", chunks_saved),
+        Err(error) => println!("⚠️  Failed to write region files: {}", error),
+    }
+    if let Err(error) = world_save.save_pending_features(&pending_features) {
+        println!(
+            "⚠️  Failed to save feature blocks waiting for chunks: {}",
+            error
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::biome::BiomeId;
+    use crate::block::BlockType;
+
+    fn temp_save_directory(name: &str) -> PathBuf {
+        let directory =
+            std::env::temp_dir().join(format!("bevy_craft_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        directory
+    }
+
+    #[test]
+    fn test_chunk_round_trip_through_region_file() {
+        let directory = temp_save_directory("round_trip");
+        let chunk_pos = ChunkPosition::new(-33, 5);
+
+        let mut data = ChunkData::new(WorldHeight::default());
+        data.set_block(3, 10, 7, BlockType::Stone);
+        data.set_block(15, 0, 15, BlockType::Bedrock);
+        data.set_block(8, -40, 8, BlockType::Lava);
+        let mut biome_data = ChunkBiomeData::new();
+        biome_data.set_biome_data(3, 7, 0.25, 0.75, BiomeId(10));
+
+        let mut world_save = WorldSave::new(&directory);
+        world_save
+            .save_chunk(chunk_pos, &data, &biome_data)
+            .unwrap();
+        world_save.flush().unwrap();
+
+        // A fresh save handle has to read the region back from disk
+        let mut reloaded_save = WorldSave::new(&directory);
+        let (loaded_data, loaded_biome_data) = reloaded_save
+            .load_chunk(chunk_pos, WorldHeight::default())
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(loaded_data.get_block(3, 10, 7), Some(BlockType::Stone));
+        assert_eq!(loaded_data.get_block(15, 0, 15), Some(BlockType::Bedrock));
+        assert_eq!(loaded_data.get_block(8, -40, 8), Some(BlockType::Lava));
+        let biome = loaded_biome_data.get_biome_data(3, 7).unwrap();
+        assert_eq!(biome.biome, BiomeId(10));
+        assert_eq!(biome.moisture, 0.75);
+
+        // Neighbouring chunks in the same region are still missing
+        assert!(reloaded_save
+            .load_chunk(ChunkPosition::new(-34, 5), WorldHeight::default())
+            .unwrap()
+            .is_none());
+
+        let _ = fs::remove_dir_all(&directory);
+    }
+
+    #[test]
+    fn test_chunks_that_dont_fit_the_world_height_are_refused() {
+        let data = ChunkData::new(WorldHeight::default());
+        let payload = encode_chunk(&data, &ChunkBiomeData::new()).unwrap();
+        assert!(decode_chunk(&payload, WorldHeight::default()).is_ok());
+        for world_height in [WorldHeight::new(0, 256), WorldHeight::new(-64, 128)] {
+            assert_eq!(
+                decode_chunk(&payload, world_height).unwrap_err().kind(),
+                io::ErrorKind::InvalidData
+            );
+        }
+
+        let biome_data = ChunkBiomeData { data: Vec::new() };
+        let payload = encode_chunk(&data, &biome_data).unwrap();
+        assert_eq!(
+            decode_chunk(&payload, WorldHeight::default())
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+
+    #[test]
+    fn test_region_header_is_validated() {
+        let mut region = RegionFile::default();
+        region.chunks.insert((1, 2), vec![1, 2, 3]);
+        let mut bytes = region.encode();
+        assert_eq!(
+            RegionFile::decode(&bytes).unwrap().chunks[&(1, 2)],
+            vec![1, 2, 3]
+        );
+
+        // Bump the version field to something this build doesn't understand
+        bytes[4..8].copy_from_slice(&(REGION_FORMAT_VERSION + 1).to_le_bytes());
+        assert!(RegionFile::decode(&bytes).is_err());
+
+        bytes[0] = b'X';
+        assert!(RegionFile::decode(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_unreadable_region_is_moved_aside_and_saving_still_works() {
+        let directory = temp_save_directory("stale_region");
+        let chunk_pos = ChunkPosition::new(2, 3);
+        let region_path =
+            directory.join(RegionPosition::from_chunk_position(&chunk_pos).file_name());
+
+        // A region written by an older version of the format
+        let mut stale = RegionFile::default();
+        stale.chunks.insert((0, 0), vec![1, 2, 3]);
+        let mut bytes = stale.encode();
+        bytes[4..8].copy_from_slice(&(REGION_FORMAT_VERSION - 1).to_le_bytes());
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(&region_path, &bytes).unwrap();
+
+        let mut data = ChunkData::new(WorldHeight::default());
+        data.set_block(1, 2, 3, BlockType::Stone);
+        let mut world_save = WorldSave::new(&directory);
+        world_save
+            .save_chunk(chunk_pos, &data, &ChunkBiomeData::new())
+            .unwrap();
+        world_save.flush().unwrap();
+
+        // The old file is kept next to the new region, which holds the saved chunk
+        assert_eq!(
+            fs::read(region_path.with_extension("bcr.bak")).unwrap(),
+            bytes
+        );
+        let (loaded_data, _) = WorldSave::new(&directory)
+            .load_chunk(chunk_pos, WorldHeight::default())
+            .unwrap()
+            .unwrap();
+        assert_eq!(loaded_data.get_block(1, 2, 3), Some(BlockType::Stone));
+
+        let _ = fs::remove_dir_all(&directory);
+    }
+
+    #[test]
+    fn test_saved_seed_wins_over_requested_seed() {
+        let directory = temp_save_directory("seed");
+        let world_save = WorldSave::new(&directory);
+        assert_eq!(world_save.load_metadata().unwrap(), None);
+
+        // A new world takes the requested seed and records it
+        let world_height = WorldHeight::new(-32, 128);
+        assert_eq!(
+            world_save
+                .resolve_metadata(Some(1234), Some(world_height))
+                .unwrap(),
+            WorldMetadata::new(1234, world_height)
+        );
+        assert_eq!(
+            world_save.load_metadata().unwrap(),
+            Some(WorldMetadata::new(1234, world_height))
+        );
+
+        // Reopening the world keeps the original seed and build limits
+        let reopened = WorldSave::new(&directory)
+            .resolve_metadata(Some(99), None)
+            .unwrap();
+        assert_eq!(reopened.seed, 1234);
+        assert_eq!(reopened.world_height, world_height);
+        assert_eq!(
+            WorldSave::new(&directory)
+                .resolve_metadata(None, Some(WorldHeight::default()))
+                .unwrap()
+                .seed,
+            1234
+        );
+
+        let _ = fs::remove_dir_all(&directory);
+    }
+
+    #[test]
+    fn test_pending_feature_blocks_round_trip() {
+        let directory = temp_save_directory("pending_features");
+        let world_save = WorldSave::new(&directory);
+        assert_eq!(
+            world_save.load_pending_features().unwrap(),
+            PendingFeatureBlocks::default()
+        );
+
+        let mut pending_features = PendingFeatureBlocks::default();
+        pending_features.push(IVec3::new(16, 70, -3), BlockType::Leaves);
+        pending_features.push(IVec3::new(17, 66, -1), BlockType::Wood);
+        pending_features.push(IVec3::new(-1, 12, 40), BlockType::Leaves);
+        world_save.save_pending_features(&pending_features).unwrap();
+
+        assert_eq!(
+            WorldSave::new(&directory).load_pending_features().unwrap(),
+            pending_features
+        );
+
+        let _ = fs::remove_dir_all(&directory);
+    }
+
+    #[test]
+    fn test_version_1_metadata_is_upgraded() {
+        let directory = temp_save_directory("metadata_v1");
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(
+            directory.join(WORLD_METADATA_FILE),
+            "(version: 1, seed: 4321)",
+        )
+        .unwrap();
+
+        let metadata = WorldSave::new(&directory)
+            .resolve_metadata(Some(5), Some(WorldHeight::default()))
+            .unwrap();
+        assert_eq!(
+            metadata,
+            WorldMetadata::new(4321, WorldMetadata::version_1_height())
+        );
+
+        let _ = fs::remove_dir_all(&directory);
+    }
+
+    #[test]
+    fn test_unusable_metadata_is_an_error_and_left_alone() {
+        let directory = temp_save_directory("bad_metadata");
+        let world_save = WorldSave::new(&directory);
+        let metadata_path = directory.join(WORLD_METADATA_FILE);
+        fs::create_dir_all(&directory).unwrap();
+
+        // Corrupt metadata
+        fs::write(&metadata_path, "(version: 2, seed: ").unwrap();
+        assert!(world_save.resolve_metadata(Some(5), None).is_err());
+        assert_eq!(
+            fs::read_to_string(&metadata_path).unwrap(),
+            "(version: 2, seed: "
+        );
+
+        // Metadata from a version this build doesn't know
+        let unknown = "(version: 0, seed: 77, world_height: (min_y: -64, max_y: 256))";
+        fs::write(&metadata_path, unknown).unwrap();
+        assert!(world_save.resolve_metadata(Some(5), None).is_err());
+        assert_eq!(fs::read_to_string(&metadata_path).unwrap(), unknown);
+
+        // Only a missing file makes a new world
+        fs::remove_file(&metadata_path).unwrap();
+        assert_eq!(world_save.resolve_metadata(Some(5), None).unwrap().seed, 5);
+
+        let _ = fs::remove_dir_all(&directory);
+    }
+}